@@ -0,0 +1,50 @@
+use crate::db::Database;
+use crate::models::ai_flavor::{AiFlavorScore, AiFlavorTrendPoint};
+use crate::services::ai_flavor;
+use tauri::State;
+
+/// 用内置的中英文 AI 套话词典与结构信号为任意文本计算 AI 味评分（不调用 LLM）
+#[tauri::command]
+pub fn score_ai_flavor(text: String) -> AiFlavorScore {
+    ai_flavor::score(&text)
+}
+
+/// 列出某个 Skill 生成的文章的 AI 味评分与风格相似度，用于观察 Skill 进化后效果是否提升
+#[tauri::command]
+pub fn get_ai_flavor_trend(
+    db: State<'_, Database>,
+    skill_id: i64,
+) -> Result<Vec<AiFlavorTrendPoint>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    ai_flavor_trend(&conn, skill_id)
+}
+
+/// get_ai_flavor_trend 的实现，按创建时间升序
+pub(crate) fn ai_flavor_trend(
+    conn: &rusqlite::Connection,
+    skill_id: i64,
+) -> Result<Vec<AiFlavorTrendPoint>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, title, skill_version_used, ai_flavor_generated, ai_flavor_refined,
+                    style_similarity, created_at
+             FROM article WHERE skill_id = ?1 ORDER BY created_at, id",
+        )
+        .map_err(|e| e.to_string())?;
+    let points = stmt
+        .query_map(rusqlite::params![skill_id], |row| {
+            Ok(AiFlavorTrendPoint {
+                article_id: row.get(0)?,
+                title: row.get(1)?,
+                skill_version_used: row.get(2)?,
+                ai_flavor_generated: row.get(3)?,
+                ai_flavor_refined: row.get(4)?,
+                style_similarity: row.get(5)?,
+                created_at: row.get(6)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(points)
+}
@@ -1,64 +1,141 @@
+use crate::commands::job::start_job;
+use crate::commands::llm::{load_llm_config, notify_budget, notify_retry};
+use crate::commands::rule::rank_spec_for_generation;
+use crate::commands::sample::list_samples_for;
+use crate::commands::skill::load_current_spec;
+use crate::commands::style_match::match_skill_style;
 use crate::db::Database;
-use crate::models::article::Article;
-use crate::services::llm_service::{self, ChatMessage, LlmConfig};
+use crate::models::article::{Article, FewShotOptions, SampleExcerpt};
+use crate::models::llm::{LlmTask, PromptBudgetReport};
+use crate::services::job_registry::{JobRegistry, JobStatus, JOB_CANCELLED};
+use crate::services::llm_service::{self, ChatMessage, LlmConfig, RetryPolicy};
+use crate::services::prompt_budget::{self, PromptSection, Shrink};
+use crate::services::tokens::TokenEstimator;
+use crate::services::{ai_flavor, few_shot};
 use crate::prompts;
-use tauri::State;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, State};
+
+/// 流式生成增量事件名
+pub const ARTICLE_CHUNK_EVENT: &str = "article://chunk";
+
+/// article://chunk 事件负载。开始时先推送一条空增量，前端借此拿到 article_id 与可取消的 job_id
+#[derive(Debug, Clone, Serialize)]
+pub struct ArticleChunk {
+    pub article_id: i64,
+    pub job_id: u64,
+    pub delta: String,
+    pub done: bool,
+    pub cancelled: bool,
+    /// 生成中途失败时的错误信息（已收到的内容以 partial 状态保存）
+    pub error: Option<String>,
+}
+
+/// 按模型上下文组装生成提示词：先整段去掉靠后的示例片段，再截断 Skill 中间部分，选题尽量保留
+pub(crate) fn fit_generate_prompt(
+    config: &LlmConfig,
+    skill_content: &str,
+    excerpts: &str,
+    topic: &str,
+) -> (String, PromptBudgetReport) {
+    let (fitted, report) = prompt_budget::fit_sections(
+        config,
+        &prompts::generate::build_generate_prompt("", "", ""),
+        prompt_budget::DEFAULT_OUTPUT_RESERVE,
+        vec![
+            PromptSection::new("skill", skill_content, 1, 500, Shrink::Truncate),
+            PromptSection::new(
+                "excerpts",
+                excerpts,
+                0,
+                0,
+                Shrink::Summarize(few_shot::keep_leading_excerpts),
+            ),
+            PromptSection::new("topic", topic, 2, 200, Shrink::Truncate),
+        ],
+    );
+    (
+        prompts::generate::build_generate_prompt(&fitted[0], &fitted[1], &fitted[2]),
+        report,
+    )
+}
+
+/// 内部辅助：按选项为主题挑选 Skill 的原创样本片段作为风格示例，未开启时为空
+pub(crate) fn few_shot_excerpts(
+    conn: &rusqlite::Connection,
+    skill_id: i64,
+    topic: &str,
+    options: Option<&FewShotOptions>,
+    config: &LlmConfig,
+) -> Result<Vec<SampleExcerpt>, String> {
+    let Some(options) = options else {
+        return Ok(Vec::new());
+    };
+    let samples = list_samples_for(conn, skill_id)?;
+    let estimator = TokenEstimator::for_model(&config.provider, &config.model);
+    Ok(few_shot::select_excerpts(
+        &samples, topic, options, &estimator,
+    ))
+}
+
+/// 预览生成时会注入的原创样本片段（不调用 LLM），options 为空时使用默认预算
+#[tauri::command]
+pub fn preview_few_shot_excerpts(
+    db: State<'_, Database>,
+    skill_id: i64,
+    topic: String,
+    options: Option<FewShotOptions>,
+) -> Result<Vec<SampleExcerpt>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let config = load_llm_config(&conn, LlmTask::Generate)?;
+    let options = options.unwrap_or_default();
+    few_shot_excerpts(&conn, skill_id, &topic, Some(&options), &config)
+}
 
 /// 创建文章（AI 生成初稿）
+/// few_shot 不为空时，从该 Skill 的原创样本中挑选与主题相关的片段作为风格示例
 #[tauri::command]
 pub async fn generate_article(
+    app: AppHandle,
     db: State<'_, Database>,
+    jobs: State<'_, JobRegistry>,
     skill_id: i64,
     topic: String,
+    few_shot: Option<FewShotOptions>,
 ) -> Result<Article, String> {
-    // 1. 获取 Skill 当前版本内容
-    let (skill_content, version_used, config) = {
+    // 1. 获取 Skill 当前版本内容与示例片段
+    let (skill_content, version_used, config, excerpts) = {
         let conn = db.conn.lock().map_err(|e| e.to_string())?;
-
-        let skill_content: String = conn
-            .query_row(
-                "SELECT sv.content_markdown FROM skill s
-                 JOIN skill_version sv ON sv.skill_id = s.id AND sv.version_number = s.current_version
-                 WHERE s.id = ?1",
-                rusqlite::params![skill_id],
-                |row| row.get(0),
-            )
-            .map_err(|e| format!("获取 Skill 失败: {}", e))?;
-
-        let version_used: i64 = conn
-            .query_row(
-                "SELECT current_version FROM skill WHERE id = ?1",
-                rusqlite::params![skill_id],
-                |row| row.get(0),
-            )
-            .map_err(|e| format!("获取版本号失败: {}", e))?;
-
-        let config = conn
-            .query_row(
-                "SELECT llm_provider, llm_endpoint, llm_api_key, llm_model FROM user_profile WHERE id = 1",
-                [],
-                |row| {
-                    Ok(LlmConfig {
-                        provider: row.get(0)?,
-                        endpoint: row.get(1)?,
-                        api_key: row.get(2)?,
-                        model: row.get(3)?,
-                    })
-                },
-            )
-            .map_err(|e| format!("读取 LLM 配置失败: {}", e))?;
-
-        (skill_content, version_used, config)
+        let (skill_content, version_used, config) = load_generation_context(&conn, skill_id)?;
+        let excerpts = few_shot_excerpts(&conn, skill_id, &topic, few_shot.as_ref(), &config)?;
+        (skill_content, version_used, config, excerpts)
     };
 
     // 2. 调用 LLM 生成文章
-    let prompt = prompts::generate::build_generate_prompt(&skill_content, &topic);
+    let (prompt, budget) = fit_generate_prompt(
+        &config,
+        &skill_content,
+        &prompts::generate::render_excerpts(&excerpts),
+        &topic,
+    );
     let messages = vec![ChatMessage {
         role: "user".to_string(),
         content: prompt,
     }];
 
-    let ai_content = llm_service::chat_completion(&config, messages, 0.7).await?;
+    // 任务被取消时直接返回，不会写入半成品文章
+    let mut job = start_job(&app, &jobs, "generate_article", &topic);
+    let job_id = job.info().id;
+    notify_budget(&app, job_id, &budget);
+    let ai_content = job
+        .run(llm_service::chat_completion_with_retry(
+            &config,
+            messages,
+            0.7,
+            &RetryPolicy::default(),
+            notify_retry(&app, job_id),
+        ))
+        .await?;
 
     // 3. 保存到数据库
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
@@ -70,28 +147,142 @@ pub async fn generate_article(
     .map_err(|e| e.to_string())?;
 
     let article_id = conn.last_insert_rowid();
+    refresh_ai_flavor(&conn, article_id)?;
+    record_style_match(&conn, article_id)?;
 
-    conn.query_row(
-        "SELECT id, title, original_content, ai_generated_content, user_refined_content,
-                skill_id, skill_version_used, status, created_at, updated_at
-         FROM article WHERE id = ?1",
-        rusqlite::params![article_id],
-        |row| {
-            Ok(Article {
-                id: row.get(0)?,
-                title: row.get(1)?,
-                original_content: row.get(2)?,
-                ai_generated_content: row.get(3)?,
-                user_refined_content: row.get(4)?,
-                skill_id: row.get(5)?,
-                skill_version_used: row.get(6)?,
-                status: row.get(7)?,
-                created_at: row.get(8)?,
-                updated_at: row.get(9)?,
-            })
-        },
+    get_article_by_id(&conn, article_id)
+}
+
+/// 流式生成文章：先创建 generating 状态的文章，再通过 article://chunk 事件推送增量
+/// 生成完成或通过 cancel_job 取消时，将已生成的全文写回文章；中途失败时已收到的内容以
+/// partial 状态保存，不丢弃。few_shot 与 generate_article 相同
+#[tauri::command]
+pub async fn generate_article_stream(
+    app: AppHandle,
+    db: State<'_, Database>,
+    jobs: State<'_, JobRegistry>,
+    skill_id: i64,
+    topic: String,
+    few_shot: Option<FewShotOptions>,
+) -> Result<Article, String> {
+    let (skill_content, config, excerpts, article_id) = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let (skill_content, version_used, config) = load_generation_context(&conn, skill_id)?;
+        let excerpts = few_shot_excerpts(&conn, skill_id, &topic, few_shot.as_ref(), &config)?;
+
+        conn.execute(
+            "INSERT INTO article (title, skill_id, skill_version_used, status)
+             VALUES (?1, ?2, ?3, 'generating')",
+            rusqlite::params![topic, skill_id, version_used],
+        )
+        .map_err(|e| e.to_string())?;
+
+        (skill_content, config, excerpts, conn.last_insert_rowid())
+    };
+
+    let mut job = start_job(&app, &jobs, "generate_article", &topic);
+
+    let (prompt, budget) = fit_generate_prompt(
+        &config,
+        &skill_content,
+        &prompts::generate::render_excerpts(&excerpts),
+        &topic,
+    );
+    let job_id = job.info().id;
+    notify_budget(&app, job_id, &budget);
+    let emit_chunk = |delta: &str, done: bool, cancelled: bool, error: Option<String>| {
+        let _ = app.emit(
+            ARTICLE_CHUNK_EVENT,
+            ArticleChunk {
+                article_id,
+                job_id,
+                delta: delta.to_string(),
+                done,
+                cancelled,
+                error,
+            },
+        );
+    };
+    emit_chunk("", false, false, None);
+
+    let messages = vec![ChatMessage {
+        role: "user".to_string(),
+        content: prompt,
+    }];
+    let mut received = String::new();
+    let result =
+        llm_service::chat_completion_stream(&config, messages, 0.7, job.token(), |delta| {
+            received.push_str(delta);
+            emit_chunk(delta, false, false, None);
+        })
+        .await;
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let outcome = match result {
+        Ok(outcome) if !(outcome.cancelled && outcome.content.is_empty()) => outcome,
+        Err(e) if !received.is_empty() => {
+            // 已推送给前端的内容不丢弃，保存为 partial 供用户继续编辑或重新生成
+            job.set_status(JobStatus::Failed);
+            let message = e.to_string();
+            save_partial_article(&conn, article_id, &received)?;
+            emit_chunk("", true, false, Some(message.clone()));
+            return Err(message);
+        }
+        other => {
+            // 尚未产出任何内容就失败或被取消时，不保留空壳文章
+            conn.execute(
+                "DELETE FROM article WHERE id = ?1",
+                rusqlite::params![article_id],
+            )
+            .map_err(|e| e.to_string())?;
+            return match other {
+                Err(e) => {
+                    job.set_status(JobStatus::Failed);
+                    let message = e.to_string();
+                    emit_chunk("", true, false, Some(message.clone()));
+                    Err(message)
+                }
+                Ok(_) => {
+                    job.set_status(JobStatus::Cancelled);
+                    emit_chunk("", true, true, None);
+                    Err(JOB_CANCELLED.to_string())
+                }
+            };
+        }
+    };
+    job.set_status(if outcome.cancelled {
+        JobStatus::Cancelled
+    } else {
+        JobStatus::Completed
+    });
+
+    conn.execute(
+        "UPDATE article SET ai_generated_content = ?1, status = 'editing', updated_at = datetime('now')
+         WHERE id = ?2",
+        rusqlite::params![outcome.content, article_id],
     )
-    .map_err(|e| format!("查询文章失败: {}", e))
+    .map_err(|e| e.to_string())?;
+    refresh_ai_flavor(&conn, article_id)?;
+    record_style_match(&conn, article_id)?;
+
+    emit_chunk("", true, outcome.cancelled, None);
+
+    get_article_by_id(&conn, article_id)
+}
+
+/// 内部辅助：流式生成中途失败时保存已收到的内容，状态记为 partial
+pub(crate) fn save_partial_article(
+    conn: &rusqlite::Connection,
+    article_id: i64,
+    content: &str,
+) -> Result<(), String> {
+    conn.execute(
+        "UPDATE article SET ai_generated_content = ?1, status = 'partial', updated_at = datetime('now')
+         WHERE id = ?2",
+        rusqlite::params![content, article_id],
+    )
+    .map_err(|e| e.to_string())?;
+    refresh_ai_flavor(conn, article_id)
 }
 
 /// 保存用户修改后的文章内容
@@ -107,34 +298,14 @@ pub fn save_article(
         rusqlite::params![content, article_id],
     )
     .map_err(|e| e.to_string())?;
-    Ok(())
+    refresh_ai_flavor(&conn, article_id)
 }
 
 /// 获取单篇文章
 #[tauri::command]
 pub fn get_article(db: State<'_, Database>, article_id: i64) -> Result<Article, String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    conn.query_row(
-        "SELECT id, title, original_content, ai_generated_content, user_refined_content,
-                skill_id, skill_version_used, status, created_at, updated_at
-         FROM article WHERE id = ?1",
-        rusqlite::params![article_id],
-        |row| {
-            Ok(Article {
-                id: row.get(0)?,
-                title: row.get(1)?,
-                original_content: row.get(2)?,
-                ai_generated_content: row.get(3)?,
-                user_refined_content: row.get(4)?,
-                skill_id: row.get(5)?,
-                skill_version_used: row.get(6)?,
-                status: row.get(7)?,
-                created_at: row.get(8)?,
-                updated_at: row.get(9)?,
-            })
-        },
-    )
-    .map_err(|e| format!("获取文章失败: {}", e))
+    get_article_by_id(&conn, article_id)
 }
 
 /// 列出文章
@@ -144,29 +315,165 @@ pub fn list_articles(db: State<'_, Database>) -> Result<Vec<Article>, String> {
     let mut stmt = conn
         .prepare(
             "SELECT id, title, original_content, ai_generated_content, user_refined_content,
-                    skill_id, skill_version_used, status, created_at, updated_at
+                    skill_id, skill_version_used, status, ai_flavor_generated, ai_flavor_refined,
+                    ai_flavor_dict_version, style_similarity, created_at, updated_at
              FROM article ORDER BY updated_at DESC",
         )
         .map_err(|e| e.to_string())?;
 
     let articles = stmt
-        .query_map([], |row| {
-            Ok(Article {
-                id: row.get(0)?,
-                title: row.get(1)?,
-                original_content: row.get(2)?,
-                ai_generated_content: row.get(3)?,
-                user_refined_content: row.get(4)?,
-                skill_id: row.get(5)?,
-                skill_version_used: row.get(6)?,
-                status: row.get(7)?,
-                created_at: row.get(8)?,
-                updated_at: row.get(9)?,
-            })
-        })
+        .query_map([], row_to_article)
         .map_err(|e| e.to_string())?
         .collect::<Result<Vec<_>, _>>()
         .map_err(|e| e.to_string())?;
 
     Ok(articles)
 }
+
+/// 内部辅助：按 ID 查询文章
+pub(crate) fn get_article_by_id(conn: &rusqlite::Connection, id: i64) -> Result<Article, String> {
+    conn.query_row(
+        "SELECT id, title, original_content, ai_generated_content, user_refined_content,
+                skill_id, skill_version_used, status, ai_flavor_generated, ai_flavor_refined,
+                ai_flavor_dict_version, style_similarity, created_at, updated_at
+         FROM article WHERE id = ?1",
+        rusqlite::params![id],
+        row_to_article,
+    )
+    .map_err(|e| format!("获取文章失败: {}", e))
+}
+
+fn row_to_article(row: &rusqlite::Row) -> rusqlite::Result<Article> {
+    Ok(Article {
+        id: row.get(0)?,
+        title: row.get(1)?,
+        original_content: row.get(2)?,
+        ai_generated_content: row.get(3)?,
+        user_refined_content: row.get(4)?,
+        skill_id: row.get(5)?,
+        skill_version_used: row.get(6)?,
+        status: row.get(7)?,
+        ai_flavor_generated: row.get(8)?,
+        ai_flavor_refined: row.get(9)?,
+        ai_flavor_dict_version: row.get(10)?,
+        style_similarity: row.get(11)?,
+        created_at: row.get(12)?,
+        updated_at: row.get(13)?,
+    })
+}
+
+/// 内部辅助：把 AI 初稿与生成时所用 Skill 版本的样本指纹比较，记录风格相似度
+pub(crate) fn record_style_match(
+    conn: &rusqlite::Connection,
+    article_id: i64,
+) -> Result<(), String> {
+    let (skill_id, version_used, generated): (Option<i64>, Option<i64>, String) = conn
+        .query_row(
+            "SELECT skill_id, skill_version_used, ai_generated_content FROM article WHERE id = ?1",
+            rusqlite::params![article_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .map_err(|e| format!("获取文章失败: {}", e))?;
+    let similarity = match skill_id {
+        Some(skill_id) if !generated.trim().is_empty() => {
+            match_skill_style(conn, skill_id, version_used, &generated)?.map(|m| m.similarity)
+        }
+        _ => None,
+    };
+    conn.execute(
+        "UPDATE article SET style_similarity = ?1 WHERE id = ?2",
+        rusqlite::params![similarity, article_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 内部辅助：按当前内置词典重新计算文章初稿与修改稿的 AI 味评分，
+/// 两者始终使用同一词典版本，便于对比
+pub(crate) fn refresh_ai_flavor(
+    conn: &rusqlite::Connection,
+    article_id: i64,
+) -> Result<(), String> {
+    let (generated, refined): (String, String) = conn
+        .query_row(
+            "SELECT ai_generated_content, user_refined_content FROM article WHERE id = ?1",
+            rusqlite::params![article_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| format!("获取文章失败: {}", e))?;
+    let score_of = |text: &str| (!text.trim().is_empty()).then(|| ai_flavor::score(text).score);
+    conn.execute(
+        "UPDATE article SET ai_flavor_generated = ?1, ai_flavor_refined = ?2, ai_flavor_dict_version = ?3
+         WHERE id = ?4",
+        rusqlite::params![
+            score_of(&generated),
+            score_of(&refined),
+            ai_flavor::DICTIONARY_VERSION,
+            article_id
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 内部辅助：读取生成所需的 Skill 当前版本内容、版本号和 LLM 配置
+fn load_generation_context(
+    conn: &rusqlite::Connection,
+    skill_id: i64,
+) -> Result<(String, i64, LlmConfig), String> {
+    let skill_content: String = conn
+        .query_row(
+            "SELECT sv.content_markdown FROM skill s
+             JOIN skill_version sv ON sv.skill_id = s.id AND sv.version_number = s.current_version
+             WHERE s.id = ?1",
+            rusqlite::params![skill_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("获取 Skill 失败: {}", e))?;
+
+    let version_used: i64 = conn
+        .query_row(
+            "SELECT current_version FROM skill WHERE id = ?1",
+            rusqlite::params![skill_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("获取版本号失败: {}", e))?;
+
+    let config = load_llm_config(conn, LlmTask::Generate)?;
+
+    Ok((
+        ranked_skill_content(conn, skill_id, skill_content)?,
+        version_used,
+        config,
+    ))
+}
+
+/// Markdown 由结构化内容渲染而来（未被手工改写）时，按规则置信度重排、过滤后重新渲染，
+/// 避免一次偶然的修改长期左右生成风格；手工改写过的 Markdown 原样使用
+pub(crate) fn ranked_skill_content(
+    conn: &rusqlite::Connection,
+    skill_id: i64,
+    skill_content: String,
+) -> Result<String, String> {
+    // 读取或解析失败要报错，只有确实没有结构化内容时才原样使用 Markdown
+    let Some(spec) = load_current_spec(conn, skill_id)? else {
+        return Ok(skill_content);
+    };
+    let name: String = conn
+        .query_row(
+            "SELECT name FROM skill WHERE id = ?1",
+            rusqlite::params![skill_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("获取 Skill 失败: {}", e))?;
+
+    // 忽略标题行比较，Skill 改名不影响判断
+    let body = |md: &str| md.split_once('\n').map(|(_, rest)| rest.trim().to_string());
+    let rendered = prompts::analyze_style::spec_to_markdown(&name, &spec);
+    if body(&rendered) != body(&skill_content) {
+        return Ok(skill_content);
+    }
+
+    let ranked = rank_spec_for_generation(conn, skill_id, &spec)?;
+    Ok(prompts::analyze_style::spec_to_markdown(&name, &ranked))
+}
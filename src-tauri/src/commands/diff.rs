@@ -1,14 +1,30 @@
+use crate::commands::job::start_job;
+use crate::commands::llm::{load_llm_config, notify_budget, notify_retry};
+use crate::commands::rule::{record_rule_feedback, RuleSource};
+use crate::commands::skill::{insert_next_version, load_current_spec};
 use crate::db::Database;
+use crate::models::analysis::DiffAnalysis;
 use crate::models::article::DiffRecord;
-use crate::services::llm_service::{self, ChatMessage, LlmConfig};
+use crate::models::llm::LlmTask;
+use crate::models::skill::{EvolutionPreview, EvolutionResult, RuleConflict};
+use crate::models::skill_spec::{RuleSection, SkillSpec};
+use crate::services::job_registry::JobRegistry;
+use crate::services::llm_service::{ChatMessage, RetryPolicy};
+use crate::services::prompt_budget::{self, PromptSection, Shrink};
+use crate::services::{skill_evolution, structured_output};
 use crate::prompts;
 use similar::{ChangeTag, TextDiff};
-use tauri::State;
+use tauri::{AppHandle, State};
 
 /// 计算两段文本的 Diff
 #[tauri::command]
 pub fn compute_diff(original: String, modified: String) -> Result<Vec<DiffChunk>, String> {
-    let diff = TextDiff::from_lines(&original, &modified);
+    Ok(line_diff(&original, &modified))
+}
+
+/// 内部辅助：按行计算文本差异
+pub(crate) fn line_diff(original: &str, modified: &str) -> Vec<DiffChunk> {
+    let diff = TextDiff::from_lines(original, modified);
     let mut chunks = Vec::new();
 
     for change in diff.iter_all_changes() {
@@ -23,7 +39,7 @@ pub fn compute_diff(original: String, modified: String) -> Result<Vec<DiffChunk>
         });
     }
 
-    Ok(chunks)
+    chunks
 }
 
 /// Diff 块
@@ -36,7 +52,9 @@ pub struct DiffChunk {
 /// 分析 Diff 并提取规则（调用 LLM）
 #[tauri::command]
 pub async fn analyze_diff(
+    app: AppHandle,
     db: State<'_, Database>,
+    jobs: State<'_, JobRegistry>,
     article_id: i64,
     original: String,
     modified: String,
@@ -54,7 +72,7 @@ pub async fn analyze_diff(
     }
 
     // 2. 获取当前 Skill 内容和 LLM 配置
-    let (current_skill, config) = {
+    let (skill_id, current_skill, config) = {
         let conn = db.conn.lock().map_err(|e| e.to_string())?;
 
         let skill_id: Option<i64> = conn
@@ -78,49 +96,80 @@ pub async fn analyze_diff(
             None => String::new(),
         };
 
-        let config = conn
-            .query_row(
-                "SELECT llm_provider, llm_endpoint, llm_api_key, llm_model FROM user_profile WHERE id = 1",
-                [],
-                |row| {
-                    Ok(LlmConfig {
-                        provider: row.get(0)?,
-                        endpoint: row.get(1)?,
-                        api_key: row.get(2)?,
-                        model: row.get(3)?,
-                    })
-                },
-            )
-            .map_err(|e| format!("读取 LLM 配置失败: {}", e))?;
+        let config = load_llm_config(&conn, LlmTask::DiffAnalyze)?;
 
-        (current_skill, config)
+        (skill_id, current_skill, config)
     };
 
-    // 3. 调用 LLM 分析 diff
+    // 3. 按模型上下文压缩各段：修改后的内容最重要，完整 diff 摘要可由原文和修改稿推出，最先压缩
+    let (fitted, budget) = prompt_budget::fit_sections(
+        &config,
+        &prompts::diff_analyze::build_diff_analyze_prompt("", "", "", ""),
+        prompt_budget::DEFAULT_OUTPUT_RESERVE,
+        vec![
+            PromptSection::new("original", original.as_str(), 1, 300, Shrink::Truncate),
+            PromptSection::new("modified", modified.as_str(), 3, 600, Shrink::Truncate),
+            PromptSection::new(
+                "diff_summary",
+                diff_summary.as_str(),
+                0,
+                200,
+                Shrink::Summarize(prompt_budget::summarize_diff),
+            ),
+            PromptSection::new(
+                "current_skill",
+                current_skill.as_str(),
+                2,
+                400,
+                Shrink::Truncate,
+            ),
+        ],
+    );
     let prompt = prompts::diff_analyze::build_diff_analyze_prompt(
-        &original,
-        &modified,
-        &diff_summary,
-        &current_skill,
+        &fitted[0], &fitted[1], &fitted[2], &fitted[3],
     );
     let messages = vec![ChatMessage {
         role: "user".to_string(),
         content: prompt,
     }];
 
-    let analysis = llm_service::chat_completion(&config, messages, 0.3).await?;
+    // 长 diff 分析代价高，遇到限流或 5xx 时按策略重试而不是直接丢弃
+    let mut job = start_job(
+        &app,
+        &jobs,
+        "analyze_diff",
+        &format!("文章 #{}", article_id),
+    );
+    let job_id = job.info().id;
+    notify_budget(&app, job_id, &budget);
+    let (analysis, analysis_json) = job
+        .run(structured_output::chat_structured::<DiffAnalysis, _>(
+            &config,
+            messages,
+            0.3,
+            &RetryPolicy::default(),
+            notify_retry(&app, job_id),
+        ))
+        .await?;
+    let extracted_rules =
+        serde_json::to_string_pretty(&analysis.new_rules).map_err(|e| e.to_string())?;
 
     // 4. 保存到数据库
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
     conn.execute(
         "INSERT INTO diff_record (article_id, diff_data, llm_analysis, extracted_rules)
          VALUES (?1, ?2, ?3, ?4)",
-        rusqlite::params![article_id, diff_summary, analysis, analysis],
+        rusqlite::params![article_id, diff_summary, analysis_json, extracted_rules],
     )
     .map_err(|e| e.to_string())?;
 
     let record_id = conn.last_insert_rowid();
 
+    // 用本次分析更新现有规则的置信度（重新提取 → 支持，被违背 → 降低）
+    if let Some(skill_id) = skill_id {
+        record_rule_feedback(&conn, skill_id, record_id, &analysis, &original, &modified)?;
+    }
+
     conn.query_row(
         "SELECT id, article_id, diff_data, llm_analysis, extracted_rules, applied_to_skill, created_at
          FROM diff_record WHERE id = ?1",
@@ -140,7 +189,7 @@ pub async fn analyze_diff(
     .map_err(|e| format!("查询 Diff 记录失败: {}", e))
 }
 
-/// 将 Diff 分析结果应用到 Skill（创建新版本）
+/// 将 Diff 分析结果应用到 Skill（创建新版本），返回新版本中检测到的规则冲突
 #[tauri::command]
 pub fn evolve_skill(
     db: State<'_, Database>,
@@ -148,34 +197,200 @@ pub fn evolve_skill(
     new_content_markdown: String,
     new_content_json: String,
     change_summary: String,
-) -> Result<(), String> {
+) -> Result<Vec<RuleConflict>, String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
 
-    // 获取当前版本号
-    let current_version: i64 = conn
+    // 结构化内容在入口处校验；只编辑了 Markdown（content_json 为空或 "{}"）时沿用当前版本的结构化内容，
+    // 当前版本的内容损坏时报错，而不是写入空的结构化内容
+    let new_content_json = match SkillSpec::from_content_json(&new_content_json)? {
+        Some(spec) => spec.to_content_json()?,
+        None => match load_current_spec(&conn, skill_id)? {
+            Some(current) => current.to_content_json()?,
+            None => "{}".to_string(),
+        },
+    };
+
+    let (_, conflicts) = insert_next_version(
+        &conn,
+        skill_id,
+        &new_content_markdown,
+        &new_content_json,
+        &change_summary,
+        RuleSource::Manual,
+        None,
+    )?;
+
+    Ok(conflicts)
+}
+
+/// 在后端自动进化 Skill：解析 Diff 记录中的 new_rules，去重合并到当前版本并标记为已应用
+#[tauri::command]
+pub fn apply_diff_record(
+    db: State<'_, Database>,
+    diff_record_id: i64,
+) -> Result<EvolutionResult, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    commit_diff_rules(&conn, diff_record_id, None)
+}
+
+/// 预览 Diff 记录会给 Skill 带来的变更（不写数据库），候选规则按章节分组并带稳定 ID
+#[tauri::command]
+pub fn preview_evolution(
+    db: State<'_, Database>,
+    diff_record_id: i64,
+) -> Result<EvolutionPreview, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    build_evolution_preview(&conn, diff_record_id)
+}
+
+/// 只用用户批准的候选规则构建新版本；未批准的规则被丢弃，Diff 记录同样标记为已应用
+#[tauri::command]
+pub fn commit_evolution(
+    db: State<'_, Database>,
+    diff_record_id: i64,
+    accepted_rule_ids: Vec<String>,
+) -> Result<EvolutionResult, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    commit_diff_rules(&conn, diff_record_id, Some(&accepted_rule_ids))
+}
+
+/// preview_evolution 的实现
+pub(crate) fn build_evolution_preview(
+    conn: &rusqlite::Connection,
+    diff_record_id: i64,
+) -> Result<EvolutionPreview, String> {
+    let (skill_id, analysis) = load_pending_diff(conn, diff_record_id)?;
+    let (_, base_version, spec) = load_evolution_base(conn, skill_id)?;
+
+    let candidates = skill_evolution::candidate_rules(&spec, &analysis.new_rules);
+    let in_section = |section: RuleSection| {
+        candidates
+            .iter()
+            .filter(|c| c.section == section)
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+
+    Ok(EvolutionPreview {
+        diff_record_id,
+        skill_id,
+        base_version,
+        summary: analysis.summary.clone(),
+        style_principles: in_section(RuleSection::StylePrinciples),
+        forbidden_words: in_section(RuleSection::ForbiddenWords),
+        forbidden_patterns: in_section(RuleSection::ForbiddenPatterns),
+    })
+}
+
+/// 合并 Diff 记录中的候选规则并标记为已应用（事务内完成建版本与标记）。
+/// accepted 为 None 时接受全部候选规则，否则只接受列出的规则 ID
+pub(crate) fn commit_diff_rules(
+    conn: &rusqlite::Connection,
+    diff_record_id: i64,
+    accepted: Option<&[String]>,
+) -> Result<EvolutionResult, String> {
+    let (skill_id, analysis) = load_pending_diff(conn, diff_record_id)?;
+    let (skill_name, current_version, mut spec) = load_evolution_base(conn, skill_id)?;
+
+    let mut candidates = skill_evolution::candidate_rules(&spec, &analysis.new_rules);
+    if let Some(accepted) = accepted {
+        // 预览之后 Skill 可能已进化，过期的 ID 直接报错，避免静默丢弃用户的选择
+        if let Some(unknown) = accepted
+            .iter()
+            .find(|id| !candidates.iter().any(|c| &c.id == *id))
+        {
+            return Err(format!("候选规则 {} 不存在或已过期，请重新预览", unknown));
+        }
+        candidates.retain(|c| accepted.contains(&c.id));
+    }
+
+    let report = skill_evolution::apply_candidates(&mut spec, &candidates);
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+
+    let (version_number, change_summary, conflicts) = if report.is_empty() {
+        (
+            current_version,
+            "没有新的风格规则需要合并".to_string(),
+            Vec::new(),
+        )
+    } else {
+        let change_summary =
+            skill_evolution::build_change_summary(diff_record_id, &report, &analysis.summary);
+        let markdown = prompts::analyze_style::spec_to_markdown(&skill_name, &spec);
+        let (version, conflicts) = insert_next_version(
+            &tx,
+            skill_id,
+            &markdown,
+            &spec.to_content_json()?,
+            &change_summary,
+            RuleSource::DiffRecord(diff_record_id),
+            None,
+        )?;
+        (version, change_summary, conflicts)
+    };
+
+    tx.execute(
+        "UPDATE diff_record SET applied_to_skill = 1 WHERE id = ?1",
+        rusqlite::params![diff_record_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(EvolutionResult {
+        skill_id,
+        version_number,
+        created_version: !report.is_empty(),
+        change_summary,
+        added_principles: report.added_principles,
+        added_forbidden_words: report.added_forbidden_words,
+        added_forbidden_patterns: report.added_forbidden_patterns,
+        conflicts,
+    })
+}
+
+/// 内部辅助：读取进化所需的 Skill 名称、当前版本号及结构化内容
+fn load_evolution_base(
+    conn: &rusqlite::Connection,
+    skill_id: i64,
+) -> Result<(String, i64, SkillSpec), String> {
+    let (skill_name, current_version): (String, i64) = conn
         .query_row(
-            "SELECT current_version FROM skill WHERE id = ?1",
+            "SELECT name, current_version FROM skill WHERE id = ?1",
             rusqlite::params![skill_id],
-            |row| row.get(0),
+            |row| Ok((row.get(0)?, row.get(1)?)),
         )
-        .map_err(|e| format!("获取 Skill 失败: {}", e))?;
+        .map_err(|e| format!("Skill 未找到: {}", e))?;
 
-    let new_version = current_version + 1;
+    let spec = load_current_spec(conn, skill_id)?
+        .ok_or_else(|| "当前 Skill 版本没有结构化内容，无法自动合并规则".to_string())?;
 
-    // 创建新版本
-    conn.execute(
-        "INSERT INTO skill_version (skill_id, version_number, content_markdown, content_json, change_summary)
-         VALUES (?1, ?2, ?3, ?4, ?5)",
-        rusqlite::params![skill_id, new_version, new_content_markdown, new_content_json, change_summary],
-    )
-    .map_err(|e| e.to_string())?;
+    Ok((skill_name, current_version, spec))
+}
 
-    // 更新 Skill 当前版本号
-    conn.execute(
-        "UPDATE skill SET current_version = ?1, updated_at = datetime('now') WHERE id = ?2",
-        rusqlite::params![new_version, skill_id],
-    )
-    .map_err(|e| e.to_string())?;
+/// 内部辅助：读取尚未应用的 Diff 记录，返回所属 Skill 及解析后的分析结果
+pub(crate) fn load_pending_diff(
+    conn: &rusqlite::Connection,
+    diff_record_id: i64,
+) -> Result<(i64, DiffAnalysis), String> {
+    let (llm_analysis, applied, skill_id): (String, bool, Option<i64>) = conn
+        .query_row(
+            "SELECT d.llm_analysis, d.applied_to_skill, a.skill_id
+             FROM diff_record d JOIN article a ON a.id = d.article_id
+             WHERE d.id = ?1",
+            rusqlite::params![diff_record_id],
+            |row| Ok((row.get(0)?, row.get::<_, i64>(1)? != 0, row.get(2)?)),
+        )
+        .map_err(|e| format!("Diff 记录未找到: {}", e))?;
+
+    if applied {
+        return Err("该 Diff 记录已应用到 Skill".to_string());
+    }
+    let skill_id = skill_id.ok_or_else(|| "文章未关联 Skill，无法应用".to_string())?;
+
+    let analysis = structured_output::parse_structured::<DiffAnalysis>(&llm_analysis)
+        .map_err(|e| format!("Diff 分析结果无法解析: {}", e))?;
 
-    Ok(())
+    Ok((skill_id, analysis))
 }
@@ -0,0 +1,359 @@
+use crate::commands::job::start_job;
+use crate::commands::llm::{load_embedding_config, notify_retry};
+use crate::db::Database;
+use crate::models::embedding::{
+    EmbeddingIndexReport, EmbeddingSource, SemanticHit, SemanticSearchResult,
+};
+use crate::services::embedding::{self, Embedder};
+use crate::services::job_registry::JobRegistry;
+use crate::services::llm_error::LlmError;
+use crate::services::llm_service::{RetryNotice, RetryPolicy};
+use crate::services::sample_import::content_hash;
+use std::collections::HashMap;
+use tauri::{AppHandle, State};
+
+/// 检索结果中摘要的字数
+const SNIPPET_CHARS: usize = 80;
+/// 未指定条数时返回的检索结果数
+const DEFAULT_SEARCH_LIMIT: usize = 10;
+
+/// 增量更新向量索引：只为新增或内容有变化的样本与文章计算向量，并清理已删除内容的向量。
+/// skill_id 为空时处理全部内容
+#[tauri::command]
+pub async fn refresh_embedding_index(
+    app: AppHandle,
+    db: State<'_, Database>,
+    jobs: State<'_, JobRegistry>,
+    skill_id: Option<i64>,
+) -> Result<EmbeddingIndexReport, String> {
+    let embedder = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        Embedder::new(load_embedding_config(&conn)?)
+    };
+    let mut job = start_job(&app, &jobs, "embedding_index", &embedder.model_key());
+    let job_id = job.info().id;
+    job.run(update_index(
+        &db,
+        &embedder,
+        skill_id,
+        &RetryPolicy::default(),
+        notify_retry(&app, job_id),
+    ))
+    .await
+}
+
+/// 语义检索原创样本与文章：先增量更新索引，再按与查询的余弦相似度排序。
+/// sources 为空时同时检索两类内容；远端向量服务不可达时改用本地哈希向量并在结果中注明
+#[tauri::command]
+pub async fn semantic_search(
+    db: State<'_, Database>,
+    query: String,
+    skill_id: Option<i64>,
+    sources: Option<Vec<EmbeddingSource>>,
+    limit: Option<usize>,
+) -> Result<SemanticSearchResult, String> {
+    if query.trim().is_empty() {
+        return Err("检索内容不能为空".to_string());
+    }
+    let configured = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        Embedder::new(load_embedding_config(&conn)?)
+    };
+    let policy = RetryPolicy::default();
+    let report = update_index(&db, &configured, skill_id, &policy, |_| {}).await?;
+    let mut fallback = report.fallback;
+    let mut embedder = if fallback.is_some() {
+        Embedder::Local
+    } else {
+        configured
+    };
+
+    let queries = [query];
+    let query_vector = match embedder.embed(&queries, &policy, |_| {}).await {
+        Ok(mut vectors) => vectors.pop().unwrap_or_default(),
+        // 索引刚更新完、查询时才断网：本地向量也要先补齐索引才能比较
+        Err(e) if e.is_unreachable() && matches!(embedder, Embedder::Remote(_)) => {
+            embedder = Embedder::Local;
+            update_index(&db, &embedder, skill_id, &policy, |_| {}).await?;
+            fallback = Some(fallback_note(&e));
+            embedding::hash_embed(&queries[0])
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    let sources = sources.unwrap_or_else(|| EmbeddingSource::ALL.to_vec());
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let model = embedder.model_key();
+    let hits = nearest(
+        &conn,
+        &model,
+        &query_vector,
+        skill_id,
+        &sources,
+        limit.unwrap_or(DEFAULT_SEARCH_LIMIT),
+    )?;
+    Ok(SemanticSearchResult {
+        model,
+        fallback,
+        hits,
+    })
+}
+
+/// 可被检索的一条内容
+pub(crate) struct Document {
+    pub source: EmbeddingSource,
+    pub source_id: i64,
+    pub skill_id: Option<i64>,
+    pub title: String,
+    pub text: String,
+}
+
+/// 内部辅助：增量更新索引。远端向量服务连不上（网络错误或超时）时改用本地哈希向量，
+/// 两者按各自的模型标识分开存放，不会混用不同维度的向量；其他错误（如鉴权失败）照常返回
+pub(crate) async fn update_index<R>(
+    db: &Database,
+    embedder: &Embedder,
+    skill_id: Option<i64>,
+    policy: &RetryPolicy,
+    mut on_retry: R,
+) -> Result<EmbeddingIndexReport, String>
+where
+    R: FnMut(&RetryNotice),
+{
+    let error = match index_once(db, embedder, skill_id, policy, &mut on_retry).await? {
+        Ok(report) => return Ok(report),
+        Err(e) if e.is_unreachable() && matches!(embedder, Embedder::Remote(_)) => e,
+        Err(e) => return Err(e.into()),
+    };
+    let mut report = index_once(db, &Embedder::Local, skill_id, policy, &mut on_retry).await??;
+    report.fallback = Some(fallback_note(&error));
+    Ok(report)
+}
+
+/// 用指定方式更新一次索引。外层错误来自数据库，内层错误来自向量化调用。
+/// 每批向量返回后立即在单独的事务中写入，中途失败时已完成的批次不必重算
+async fn index_once<R>(
+    db: &Database,
+    embedder: &Embedder,
+    skill_id: Option<i64>,
+    policy: &RetryPolicy,
+    mut on_retry: R,
+) -> Result<Result<EmbeddingIndexReport, LlmError>, String>
+where
+    R: FnMut(&RetryNotice),
+{
+    let model = embedder.model_key();
+    let (pending, unchanged, removed) = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let configured = Embedder::new(load_embedding_config(&conn)?).model_key();
+        let removed =
+            remove_orphan_embeddings(&conn)? + remove_stale_model_embeddings(&conn, &configured)?;
+        let (pending, unchanged) = pending_documents(&conn, &model, corpus(&conn, skill_id)?)?;
+        (pending, unchanged, removed)
+    };
+
+    for batch in pending.chunks(embedding::EMBED_BATCH) {
+        let texts: Vec<String> = batch.iter().map(|(doc, _)| doc.text.clone()).collect();
+        let vectors = match embedder.embed(&texts, policy, &mut on_retry).await {
+            Ok(vectors) => vectors,
+            Err(e) => return Ok(Err(e)),
+        };
+
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+        store_embeddings(&tx, &model, batch, &vectors)?;
+        tx.commit().map_err(|e| e.to_string())?;
+    }
+
+    Ok(Ok(EmbeddingIndexReport {
+        model,
+        indexed: pending.len(),
+        unchanged,
+        removed,
+        fallback: None,
+    }))
+}
+
+fn fallback_note(error: &LlmError) -> String {
+    format!("远端向量服务不可达，已改用本地哈希向量（{}）", error)
+}
+
+/// 内部辅助：读取可检索的内容。文章取用户修改稿，没有时依次取 AI 初稿、原文；空内容与生成中的文章不参与
+pub(crate) fn corpus(
+    conn: &rusqlite::Connection,
+    skill_id: Option<i64>,
+) -> Result<Vec<Document>, String> {
+    let mut docs = Vec::new();
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, skill_id, title, content FROM original_sample
+             WHERE ?1 IS NULL OR skill_id = ?1 ORDER BY id",
+        )
+        .map_err(|e| e.to_string())?;
+    let samples = stmt
+        .query_map(rusqlite::params![skill_id], |row| {
+            Ok(Document {
+                source: EmbeddingSource::Sample,
+                source_id: row.get(0)?,
+                skill_id: row.get(1)?,
+                title: row.get(2)?,
+                text: row.get(3)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    for doc in samples {
+        docs.push(doc.map_err(|e| e.to_string())?);
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, skill_id, title,
+                    CASE WHEN user_refined_content != '' THEN user_refined_content
+                         WHEN ai_generated_content != '' THEN ai_generated_content
+                         ELSE original_content END
+             FROM article
+             WHERE (?1 IS NULL OR skill_id = ?1) AND status != 'generating' ORDER BY id",
+        )
+        .map_err(|e| e.to_string())?;
+    let articles = stmt
+        .query_map(rusqlite::params![skill_id], |row| {
+            Ok(Document {
+                source: EmbeddingSource::Article,
+                source_id: row.get(0)?,
+                skill_id: row.get(1)?,
+                title: row.get(2)?,
+                text: row.get(3)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    for doc in articles {
+        docs.push(doc.map_err(|e| e.to_string())?);
+    }
+
+    docs.retain(|d| !d.text.trim().is_empty());
+    Ok(docs)
+}
+
+/// 内部辅助：挑出在该模型下还没有向量或内容已变化的条目，返回（待计算条目及其内容哈希，未变化条数）
+pub(crate) fn pending_documents(
+    conn: &rusqlite::Connection,
+    model: &str,
+    docs: Vec<Document>,
+) -> Result<(Vec<(Document, String)>, usize), String> {
+    let mut stmt = conn
+        .prepare("SELECT source, source_id, content_hash FROM embedding WHERE model = ?1")
+        .map_err(|e| e.to_string())?;
+    let existing: HashMap<(String, i64), String> = stmt
+        .query_map(rusqlite::params![model], |row| {
+            Ok(((row.get(0)?, row.get(1)?), row.get(2)?))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut pending = Vec::new();
+    let mut unchanged = 0;
+    for doc in docs {
+        let hash = content_hash(&doc.text);
+        let key = (doc.source.as_str().to_string(), doc.source_id);
+        if existing.get(&key) == Some(&hash) {
+            unchanged += 1;
+        } else {
+            pending.push((doc, hash));
+        }
+    }
+    Ok((pending, unchanged))
+}
+
+/// 内部辅助：写入（或覆盖）向量
+pub(crate) fn store_embeddings(
+    conn: &rusqlite::Connection,
+    model: &str,
+    docs: &[(Document, String)],
+    vectors: &[Vec<f32>],
+) -> Result<(), String> {
+    for ((doc, hash), vector) in docs.iter().zip(vectors) {
+        conn.execute(
+            "INSERT INTO embedding (source, source_id, model, content_hash, dim, vector)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT(source, source_id, model) DO UPDATE SET
+                content_hash = excluded.content_hash, dim = excluded.dim,
+                vector = excluded.vector, created_at = datetime('now')",
+            rusqlite::params![
+                doc.source.as_str(),
+                doc.source_id,
+                model,
+                hash,
+                vector.len() as i64,
+                embedding::encode_vector(vector),
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// 内部辅助：删除原样本或文章已不存在的向量，返回删除条数
+pub(crate) fn remove_orphan_embeddings(conn: &rusqlite::Connection) -> Result<usize, String> {
+    conn.execute(
+        "DELETE FROM embedding
+         WHERE (source = 'sample' AND source_id NOT IN (SELECT id FROM original_sample))
+            OR (source = 'article' AND source_id NOT IN (SELECT id FROM article))",
+        [],
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// 内部辅助：删除既不属于当前配置的远端模型、也不属于本地哈希向量的向量（换过模型后的旧数据），
+/// 返回删除条数。远端暂时不可达时本地向量与远端向量并存，两者都保留
+pub(crate) fn remove_stale_model_embeddings(
+    conn: &rusqlite::Connection,
+    configured_model: &str,
+) -> Result<usize, String> {
+    conn.execute(
+        "DELETE FROM embedding WHERE model NOT IN (?1, ?2)",
+        rusqlite::params![configured_model, embedding::LOCAL_MODEL],
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// 内部辅助：在该模型已有的向量中查找与查询最相近的条目
+pub(crate) fn nearest(
+    conn: &rusqlite::Connection,
+    model: &str,
+    query: &[f32],
+    skill_id: Option<i64>,
+    sources: &[EmbeddingSource],
+    limit: usize,
+) -> Result<Vec<SemanticHit>, String> {
+    let mut stmt = conn
+        .prepare("SELECT source, source_id, vector FROM embedding WHERE model = ?1")
+        .map_err(|e| e.to_string())?;
+    let vectors: HashMap<(String, i64), Vec<u8>> = stmt
+        .query_map(rusqlite::params![model], |row| {
+            Ok(((row.get(0)?, row.get(1)?), row.get(2)?))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut hits: Vec<SemanticHit> = corpus(conn, skill_id)?
+        .into_iter()
+        .filter(|doc| sources.contains(&doc.source))
+        .filter_map(|doc| {
+            let bytes = vectors.get(&(doc.source.as_str().to_string(), doc.source_id))?;
+            Some(SemanticHit {
+                similarity: embedding::cosine(query, &embedding::decode_vector(bytes)),
+                snippet: doc.text.trim().chars().take(SNIPPET_CHARS).collect(),
+                source: doc.source,
+                source_id: doc.source_id,
+                skill_id: doc.skill_id,
+                title: doc.title,
+            })
+        })
+        .collect();
+    hits.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+    hits.truncate(limit);
+    Ok(hits)
+}
@@ -1,4 +1,6 @@
+use crate::commands::skill::load_current_spec;
 use crate::db::Database;
+use crate::prompts;
 use tauri::State;
 
 /// 导出 Skill 为 Markdown 格式
@@ -32,6 +34,14 @@ pub fn export_skill_markdown(db: State<'_, Database>, skill_id: i64) -> Result<S
         )
         .map_err(|e| e.to_string())?;
 
+    // Markdown 为空时由结构化内容渲染
+    let content = match load_current_spec(&conn, skill_id)? {
+        Some(spec) if content.trim().is_empty() => {
+            prompts::analyze_style::spec_to_markdown(&name, &spec)
+        }
+        _ => content,
+    };
+
     let markdown = format!(
         "# {} — Writing Style Skill\n\n**分类**: {} | **版本**: v{}\n\n{}\n\n---\n\n{}\n\n---\n\n> 由 Savor (余香) 导出 | 可直接作为 System Prompt 使用\n",
         name, category, version, description, content
@@ -53,22 +63,15 @@ pub fn export_skill_json(db: State<'_, Database>, skill_id: i64) -> Result<Strin
         )
         .map_err(|e| format!("Skill 未找到: {}", e))?;
 
-    let content_json: String = conn
-        .query_row(
-            "SELECT sv.content_json FROM skill s
-             JOIN skill_version sv ON sv.skill_id = s.id AND sv.version_number = s.current_version
-             WHERE s.id = ?1",
-            rusqlite::params![skill_id],
-            |row| row.get(0),
-        )
-        .map_err(|e| format!("获取版本内容失败: {}", e))?;
+    // 损坏的结构化内容直接报错，而不是静默导出为 null；仅有 Markdown 的版本导出为 null
+    let spec = load_current_spec(&conn, skill_id)?;
 
     let export = serde_json::json!({
         "name": name,
         "category": category,
         "description": description,
         "version": version,
-        "skill": serde_json::from_str::<serde_json::Value>(&content_json).unwrap_or(serde_json::Value::Null),
+        "skill": spec,
         "exported_by": "Savor (余香)"
     });
 
@@ -0,0 +1,29 @@
+use crate::services::job_registry::{JobGuard, JobInfo, JobRegistry};
+use tauri::{AppHandle, Emitter, State};
+
+/// 任务开始事件名（负载为 JobInfo，前端据此拿到可取消的任务 ID）
+pub const JOB_STARTED_EVENT: &str = "job://started";
+
+/// 登记任务并通知前端
+pub(crate) fn start_job<'a>(
+    app: &AppHandle,
+    jobs: &'a JobRegistry,
+    kind: &str,
+    label: &str,
+) -> JobGuard<'a> {
+    let guard = jobs.start(kind, label);
+    let _ = app.emit(JOB_STARTED_EVENT, guard.info().clone());
+    guard
+}
+
+/// 列出进行中和最近结束的任务
+#[tauri::command]
+pub fn list_jobs(jobs: State<'_, JobRegistry>) -> Result<Vec<JobInfo>, String> {
+    Ok(jobs.list())
+}
+
+/// 取消任务（任务不存在或已结束时返回 false）
+#[tauri::command]
+pub fn cancel_job(jobs: State<'_, JobRegistry>, job_id: u64) -> Result<bool, String> {
+    Ok(jobs.cancel(job_id))
+}
@@ -0,0 +1,29 @@
+use crate::commands::skill::load_current_spec;
+use crate::db::Database;
+use crate::models::lint::LintIssue;
+use crate::services::linter;
+use tauri::State;
+
+/// 用 Skill 当前版本的禁用词与禁用句式在本地检查文本（不调用 LLM），
+/// 返回命中位置供编辑器标注
+#[tauri::command]
+pub fn lint_text(
+    db: State<'_, Database>,
+    skill_id: i64,
+    text: String,
+) -> Result<Vec<LintIssue>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    lint_with_skill(&conn, skill_id, &text)
+}
+
+/// lint_text 的实现；只有 Markdown 的 Skill 没有可检查的规则，返回空列表
+pub(crate) fn lint_with_skill(
+    conn: &rusqlite::Connection,
+    skill_id: i64,
+    text: &str,
+) -> Result<Vec<LintIssue>, String> {
+    let Some(spec) = load_current_spec(conn, skill_id)? else {
+        return Ok(Vec::new());
+    };
+    Ok(linter::lint(text, &linter::rules_from_spec(&spec)))
+}
@@ -1,6 +1,56 @@
 use crate::db::Database;
-use crate::services::llm_service::{self, LlmConfig};
-use tauri::State;
+use crate::models::llm::{LlmProfile, LlmProfileRequest, LlmTask, PromptBudgetReport, TaskRoute};
+use crate::services::llm_service::{self, LlmConfig, LlmProvider, RetryNotice};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, State};
+
+/// LLM 调用重试事件名（负载中的 error.kind 区分限流、过载、网络等原因）
+pub const LLM_RETRY_EVENT: &str = "llm://retry";
+
+/// llm://retry 事件负载
+#[derive(Debug, Clone, Serialize)]
+pub struct LlmRetryEvent {
+    pub job_id: u64,
+    #[serde(flatten)]
+    pub notice: RetryNotice,
+}
+
+/// 生成重试回调：每次重试前通知前端"服务繁忙，正在重试"
+pub(crate) fn notify_retry(app: &AppHandle, job_id: u64) -> impl FnMut(&RetryNotice) + '_ {
+    move |notice| {
+        let _ = app.emit(
+            LLM_RETRY_EVENT,
+            LlmRetryEvent {
+                job_id,
+                notice: notice.clone(),
+            },
+        );
+    }
+}
+
+/// 提示词超出上下文预算的预警事件名（发送前触发，调用仍会继续）
+pub const LLM_BUDGET_EVENT: &str = "llm://budget";
+
+/// llm://budget 事件负载
+#[derive(Debug, Clone, Serialize)]
+pub struct LlmBudgetEvent {
+    pub job_id: u64,
+    #[serde(flatten)]
+    pub report: PromptBudgetReport,
+}
+
+/// 提示词被截断、摘要或仍然放不下时通知前端
+pub(crate) fn notify_budget(app: &AppHandle, job_id: u64, report: &PromptBudgetReport) {
+    if report.needs_warning() {
+        let _ = app.emit(
+            LLM_BUDGET_EVENT,
+            LlmBudgetEvent {
+                job_id,
+                report: report.clone(),
+            },
+        );
+    }
+}
 
 /// 保存 LLM 配置
 #[tauri::command]
@@ -24,6 +74,189 @@ pub fn save_llm_config(
 #[tauri::command]
 pub fn get_llm_config(db: State<'_, Database>) -> Result<LlmConfig, String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    load_default_config(&conn)
+}
+
+/// 测试 LLM 连接（profile_id 为空时测试默认配置）
+#[tauri::command]
+pub async fn test_llm_connection(
+    db: State<'_, Database>,
+    profile_id: Option<i64>,
+) -> Result<String, String> {
+    let config = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        match profile_id {
+            Some(id) => profile_to_config(get_profile_by_id(&conn, id)?),
+            None => load_default_config(&conn)?,
+        }
+    };
+
+    llm_service::test_connection(&config)
+        .await
+        .map_err(String::from)
+}
+
+/// 创建 LLM 配置档案
+#[tauri::command]
+pub fn create_llm_profile(
+    db: State<'_, Database>,
+    request: LlmProfileRequest,
+) -> Result<LlmProfile, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    let name = request
+        .name
+        .map(|n| n.trim().to_string())
+        .filter(|n| !n.is_empty())
+        .ok_or_else(|| "配置档案名称不能为空".to_string())?;
+    let provider = request.provider.unwrap_or_else(|| "openai".to_string());
+    let endpoint = request.endpoint.unwrap_or_else(|| {
+        LlmProvider::from_str(&provider)
+            .default_endpoint()
+            .to_string()
+    });
+
+    conn.execute(
+        "INSERT INTO llm_profile (name, provider, endpoint, api_key, model) VALUES (?1, ?2, ?3, ?4, ?5)",
+        rusqlite::params![
+            name,
+            provider,
+            endpoint,
+            request.api_key.unwrap_or_default(),
+            request.model.unwrap_or_default()
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    get_profile_by_id(&conn, conn.last_insert_rowid())
+}
+
+/// 列出所有 LLM 配置档案
+#[tauri::command]
+pub fn list_llm_profiles(db: State<'_, Database>) -> Result<Vec<LlmProfile>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, name, provider, endpoint, api_key, model, created_at, updated_at
+             FROM llm_profile ORDER BY id",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let profiles = stmt
+        .query_map([], row_to_profile)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(profiles)
+}
+
+/// 更新 LLM 配置档案
+#[tauri::command]
+pub fn update_llm_profile(
+    db: State<'_, Database>,
+    id: i64,
+    request: LlmProfileRequest,
+) -> Result<LlmProfile, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    // 先获取当前值用于合并
+    let current = get_profile_by_id(&conn, id)?;
+
+    let name = request
+        .name
+        .map(|n| n.trim().to_string())
+        .filter(|n| !n.is_empty())
+        .unwrap_or(current.name);
+    let provider = request.provider.unwrap_or(current.provider);
+    let endpoint = request.endpoint.unwrap_or(current.endpoint);
+    let api_key = request.api_key.unwrap_or(current.api_key);
+    let model = request.model.unwrap_or(current.model);
+
+    conn.execute(
+        "UPDATE llm_profile SET name = ?1, provider = ?2, endpoint = ?3, api_key = ?4, model = ?5,
+                updated_at = datetime('now') WHERE id = ?6",
+        rusqlite::params![name, provider, endpoint, api_key, model, id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    get_profile_by_id(&conn, id)
+}
+
+/// 删除 LLM 配置档案（引用它的任务回落到默认配置）
+#[tauri::command]
+pub fn delete_llm_profile(db: State<'_, Database>, id: i64) -> Result<(), String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM llm_profile WHERE id = ?1",
+        rusqlite::params![id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 获取所有任务的配置档案路由
+#[tauri::command]
+pub fn get_task_routes(db: State<'_, Database>) -> Result<Vec<TaskRoute>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    LlmTask::ALL
+        .iter()
+        .map(|&task| {
+            Ok(TaskRoute {
+                task,
+                profile_id: get_route_profile_id(&conn, task)?,
+            })
+        })
+        .collect()
+}
+
+/// 为任务指定配置档案（profile_id 为空则恢复默认配置）
+#[tauri::command]
+pub fn set_task_route(
+    db: State<'_, Database>,
+    task: LlmTask,
+    profile_id: Option<i64>,
+) -> Result<(), String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    if let Some(id) = profile_id {
+        get_profile_by_id(&conn, id)?;
+    }
+
+    conn.execute(
+        "INSERT INTO llm_task_route (task, profile_id) VALUES (?1, ?2)
+         ON CONFLICT(task) DO UPDATE SET profile_id = excluded.profile_id",
+        rusqlite::params![task.as_str(), profile_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 读取任务应使用的 LLM 配置：优先使用路由指定的档案，否则使用默认配置
+pub(crate) fn load_llm_config(
+    conn: &rusqlite::Connection,
+    task: LlmTask,
+) -> Result<LlmConfig, String> {
+    match get_route_profile_id(conn, task)? {
+        Some(id) => Ok(profile_to_config(get_profile_by_id(conn, id)?)),
+        None => load_default_config(conn),
+    }
+}
+
+/// 读取向量化应使用的配置：只有为 embed 任务指定了档案时才调用远端接口，否则为 None（使用本地向量）
+pub(crate) fn load_embedding_config(
+    conn: &rusqlite::Connection,
+) -> Result<Option<LlmConfig>, String> {
+    match get_route_profile_id(conn, LlmTask::Embed)? {
+        Some(id) => Ok(Some(profile_to_config(get_profile_by_id(conn, id)?))),
+        None => Ok(None),
+    }
+}
+
+/// 内部辅助：读取 user_profile 中的默认 LLM 配置
+fn load_default_config(conn: &rusqlite::Connection) -> Result<LlmConfig, String> {
     conn.query_row(
         "SELECT llm_provider, llm_endpoint, llm_api_key, llm_model FROM user_profile WHERE id = 1",
         [],
@@ -39,27 +272,49 @@ pub fn get_llm_config(db: State<'_, Database>) -> Result<LlmConfig, String> {
     .map_err(|e| format!("读取配置失败: {}", e))
 }
 
-/// 测试 LLM 连接
-#[tauri::command]
-pub async fn test_llm_connection(
-    db: State<'_, Database>,
-) -> Result<String, String> {
-    let config = {
-        let conn = db.conn.lock().map_err(|e| e.to_string())?;
-        conn.query_row(
-            "SELECT llm_provider, llm_endpoint, llm_api_key, llm_model FROM user_profile WHERE id = 1",
-            [],
-            |row| {
-                Ok(LlmConfig {
-                    provider: row.get(0)?,
-                    endpoint: row.get(1)?,
-                    api_key: row.get(2)?,
-                    model: row.get(3)?,
-                })
-            },
-        )
-        .map_err(|e| format!("读取配置失败: {}", e))?
-    };
+/// 内部辅助：查询任务路由到的档案 ID
+fn get_route_profile_id(conn: &rusqlite::Connection, task: LlmTask) -> Result<Option<i64>, String> {
+    conn.query_row(
+        "SELECT profile_id FROM llm_task_route WHERE task = ?1",
+        rusqlite::params![task.as_str()],
+        |row| row.get::<_, Option<i64>>(0),
+    )
+    .or_else(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => Ok(None),
+        e => Err(e),
+    })
+    .map_err(|e| format!("读取任务路由失败: {}", e))
+}
+
+/// 内部辅助：按 ID 查询配置档案
+fn get_profile_by_id(conn: &rusqlite::Connection, id: i64) -> Result<LlmProfile, String> {
+    conn.query_row(
+        "SELECT id, name, provider, endpoint, api_key, model, created_at, updated_at
+         FROM llm_profile WHERE id = ?1",
+        rusqlite::params![id],
+        row_to_profile,
+    )
+    .map_err(|e| format!("配置档案未找到: {}", e))
+}
+
+fn row_to_profile(row: &rusqlite::Row) -> rusqlite::Result<LlmProfile> {
+    Ok(LlmProfile {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        provider: row.get(2)?,
+        endpoint: row.get(3)?,
+        api_key: row.get(4)?,
+        model: row.get(5)?,
+        created_at: row.get(6)?,
+        updated_at: row.get(7)?,
+    })
+}
 
-    llm_service::test_connection(&config).await
+fn profile_to_config(profile: LlmProfile) -> LlmConfig {
+    LlmConfig {
+        provider: profile.provider,
+        endpoint: profile.endpoint,
+        api_key: profile.api_key,
+        model: profile.model,
+    }
 }
@@ -1,6 +1,13 @@
+pub mod ai_flavor;
 pub mod article;
 pub mod diff;
+pub mod embedding;
 pub mod export;
+pub mod job;
+pub mod lint;
 pub mod llm;
 pub mod onboarding;
+pub mod rule;
+pub mod sample;
 pub mod skill;
+pub mod style_match;
@@ -0,0 +1,447 @@
+use crate::db::{with_savepoint, Database};
+use crate::models::analysis::DiffAnalysis;
+use crate::models::skill::SkillRule;
+use crate::models::skill_spec::{RuleSection, SkillSpec};
+use crate::services::skill_evolution::{self, rule_confidence, rule_id, MIN_RULE_CONFIDENCE};
+use std::collections::{HashMap, HashSet};
+use tauri::State;
+
+/// 新版本中新增规则的来源
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum RuleSource {
+    /// 手动编辑或回滚：沿用同一规则此前记录的出处（如果有）
+    Manual,
+    DiffRecord(i64),
+    /// 从整批原创样本中提取：不对应其中某一篇，original_sample_id 留空
+    Samples,
+}
+
+/// 列出 Skill 的规则；默认只返回当前版本中仍然存在的规则
+#[tauri::command]
+pub fn list_skill_rules(
+    db: State<'_, Database>,
+    skill_id: i64,
+    include_removed: Option<bool>,
+) -> Result<Vec<SkillRule>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    list_rules(&conn, skill_id, include_removed.unwrap_or(false))
+}
+
+/// 查询一条规则的完整历史：同一 Skill 中同章节、同归一化文本的每一次引入与移除
+#[tauri::command]
+pub fn get_rule_history(db: State<'_, Database>, rule_id: i64) -> Result<Vec<SkillRule>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    rule_history(&conn, rule_id)
+}
+
+/// list_skill_rules 的实现（只读；旧数据的规则记录由启动时的 backfill_all_rules 补齐）
+pub(crate) fn list_rules(
+    conn: &rusqlite::Connection,
+    skill_id: i64,
+    include_removed: bool,
+) -> Result<Vec<SkillRule>, String> {
+    conn.query_row(
+        "SELECT id FROM skill WHERE id = ?1",
+        rusqlite::params![skill_id],
+        |row| row.get::<_, i64>(0),
+    )
+    .map_err(|e| format!("Skill 未找到: {}", e))?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, skill_id, section, text, introduced_version, removed_version,
+                    diff_record_id, original_sample_id, support_count, contradiction_count,
+                    created_at
+             FROM skill_rule
+             WHERE skill_id = ?1 AND (?2 OR removed_version IS NULL)
+             ORDER BY id",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rules = stmt
+        .query_map(rusqlite::params![skill_id, include_removed], row_to_rule)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(rules)
+}
+
+/// get_rule_history 的实现，按引入版本升序
+pub(crate) fn rule_history(
+    conn: &rusqlite::Connection,
+    rule_id: i64,
+) -> Result<Vec<SkillRule>, String> {
+    let (skill_id, rule_key): (i64, String) = conn
+        .query_row(
+            "SELECT skill_id, rule_key FROM skill_rule WHERE id = ?1",
+            rusqlite::params![rule_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| format!("规则未找到: {}", e))?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, skill_id, section, text, introduced_version, removed_version,
+                    diff_record_id, original_sample_id, support_count, contradiction_count,
+                    created_at
+             FROM skill_rule WHERE skill_id = ?1 AND rule_key = ?2
+             ORDER BY introduced_version, id",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let history = stmt
+        .query_map(rusqlite::params![skill_id, rule_key], row_to_rule)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(history)
+}
+
+/// 根据一次 Diff 分析更新规则置信度：分析再次提取出的现有规则支持次数 +1，
+/// 被修改违背的规则（模型列出的 contradicted_rules，或用户在修改中重新用到的禁用词）违背次数 +1。
+/// 同一篇文章对同一规则的每种反馈只计一次，重新分析同一篇文章不会重复累加
+pub(crate) fn record_rule_feedback(
+    conn: &rusqlite::Connection,
+    skill_id: i64,
+    diff_record_id: i64,
+    analysis: &DiffAnalysis,
+    original: &str,
+    modified: &str,
+) -> Result<(), String> {
+    let current_version: i64 = conn
+        .query_row(
+            "SELECT current_version FROM skill WHERE id = ?1",
+            rusqlite::params![skill_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Skill 未找到: {}", e))?;
+    let article_id: i64 = conn
+        .query_row(
+            "SELECT article_id FROM diff_record WHERE id = ?1",
+            rusqlite::params![diff_record_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Diff 记录未找到: {}", e))?;
+
+    with_savepoint(conn, "record_rule_feedback", || {
+        backfill_rules(conn, skill_id, current_version + 1)?;
+
+        let active = list_rules(conn, skill_id, false)?;
+        let find = |section: RuleSection, text: &str| {
+            let key = rule_id(section, text);
+            active
+                .iter()
+                .find(|r| r.section == section.as_str() && rule_id(section, &r.text) == key)
+                .map(|r| r.id)
+        };
+
+        let mut reinforced = HashSet::new();
+        for section in RuleSection::ALL {
+            for text in analysis.new_rules.additions(section) {
+                reinforced.extend(find(section, text));
+            }
+        }
+
+        let mut contradicted = HashSet::new();
+        for text in &analysis.contradicted_rules {
+            for section in RuleSection::ALL {
+                contradicted.extend(find(section, text));
+            }
+        }
+        let (original, modified) = (original.to_lowercase(), modified.to_lowercase());
+        for rule in active
+            .iter()
+            .filter(|r| r.section == RuleSection::ForbiddenWords.as_str())
+        {
+            let word = rule.text.to_lowercase();
+            if modified.matches(&word).count() > original.matches(&word).count() {
+                contradicted.insert(rule.id);
+            }
+        }
+
+        for (ids, kind, column) in [
+            (&reinforced, "support", "support_count"),
+            (&contradicted, "contradiction", "contradiction_count"),
+        ] {
+            for id in ids {
+                let first_time = conn
+                    .execute(
+                        "INSERT OR IGNORE INTO rule_feedback
+                             (article_id, skill_id, rule_key, kind, diff_record_id)
+                         SELECT ?1, skill_id, rule_key, ?2, ?3 FROM skill_rule WHERE id = ?4",
+                        rusqlite::params![article_id, kind, diff_record_id, id],
+                    )
+                    .map_err(|e| e.to_string())?
+                    > 0;
+                if first_time {
+                    conn.execute(
+                        &format!("UPDATE skill_rule SET {0} = {0} + 1 WHERE id = ?1", column),
+                        rusqlite::params![id],
+                    )
+                    .map_err(|e| e.to_string())?;
+                }
+            }
+        }
+        Ok(())
+    })
+}
+
+/// 生成前按置信度重排规则并过滤低置信度规则
+pub(crate) fn rank_spec_for_generation(
+    conn: &rusqlite::Connection,
+    skill_id: i64,
+    spec: &SkillSpec,
+) -> Result<SkillSpec, String> {
+    let confidence = rule_confidences(conn, skill_id)?;
+    Ok(skill_evolution::rank_rules(
+        spec,
+        MIN_RULE_CONFIDENCE,
+        |section, text| confidence.get(&rule_id(section, text)).copied(),
+    ))
+}
+
+/// 当前有效规则的置信度，以 rule_id 为键
+pub(crate) fn rule_confidences(
+    conn: &rusqlite::Connection,
+    skill_id: i64,
+) -> Result<HashMap<String, f64>, String> {
+    Ok(list_rules(conn, skill_id, false)?
+        .into_iter()
+        .filter_map(|r| {
+            let section = RuleSection::ALL
+                .into_iter()
+                .find(|s| s.as_str() == r.section)?;
+            Some((rule_id(section, &r.text), r.confidence))
+        })
+        .collect())
+}
+
+/// 新版本写入后同步 skill_rule：消失的规则记为在该版本移除，新出现的规则记为在该版本引入。
+/// 只有 Markdown 的版本没有可追踪的规则，直接跳过
+pub(crate) fn sync_skill_rules(
+    conn: &rusqlite::Connection,
+    skill_id: i64,
+    version_number: i64,
+    source: RuleSource,
+) -> Result<(), String> {
+    let Some(spec) = load_version_spec(conn, skill_id, version_number)? else {
+        return Ok(());
+    };
+    backfill_rules(conn, skill_id, version_number)?;
+    record_version_rules(conn, skill_id, version_number, &spec, source)
+}
+
+/// 启动时的一次性迁移：为还没有 skill_rule 记录的 Skill 回放历史版本补齐规则，
+/// 每个 Skill 单独提交，已有记录的 Skill 直接跳过
+pub(crate) fn backfill_all_rules(conn: &rusqlite::Connection) -> Result<(), String> {
+    let skills: Vec<(i64, i64)> = {
+        let mut stmt = conn
+            .prepare("SELECT id, current_version FROM skill ORDER BY id")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?
+    };
+    for (skill_id, current_version) in skills {
+        with_savepoint(conn, "backfill_rules", || {
+            backfill_rules(conn, skill_id, current_version + 1)
+        })?;
+    }
+    Ok(())
+}
+
+/// 旧数据没有 skill_rule 记录时，按版本顺序回放历史版本补齐（出处未知）
+fn backfill_rules(
+    conn: &rusqlite::Connection,
+    skill_id: i64,
+    before_version: i64,
+) -> Result<(), String> {
+    let existing: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM skill_rule WHERE skill_id = ?1",
+            rusqlite::params![skill_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if existing > 0 {
+        return Ok(());
+    }
+
+    let versions: Vec<i64> = {
+        let mut stmt = conn
+            .prepare(
+                "SELECT version_number FROM skill_version
+                 WHERE skill_id = ?1 AND version_number < ?2 ORDER BY version_number",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(rusqlite::params![skill_id, before_version], |row| {
+                row.get(0)
+            })
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?
+    };
+
+    for version in versions {
+        // 历史版本的 JSON 可能已损坏，补齐时跳过而不是让新版本保存失败
+        if let Ok(Some(spec)) = load_version_spec(conn, skill_id, version) {
+            record_version_rules(conn, skill_id, version, &spec, RuleSource::Manual)?;
+        }
+    }
+    Ok(())
+}
+
+/// 对比当前有效规则与 spec，写入引入/移除记录
+fn record_version_rules(
+    conn: &rusqlite::Connection,
+    skill_id: i64,
+    version_number: i64,
+    spec: &SkillSpec,
+    source: RuleSource,
+) -> Result<(), String> {
+    let active: Vec<(i64, String)> = {
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, rule_key FROM skill_rule
+                 WHERE skill_id = ?1 AND removed_version IS NULL",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(rusqlite::params![skill_id], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?
+    };
+
+    let mut current = Vec::new();
+    let mut seen = HashSet::new();
+    for section in RuleSection::ALL {
+        for text in spec.rules(section) {
+            let key = rule_id(section, text);
+            if seen.insert(key.clone()) {
+                current.push((section, text, key));
+            }
+        }
+    }
+
+    for (id, key) in &active {
+        if !seen.contains(key) {
+            conn.execute(
+                "UPDATE skill_rule SET removed_version = ?1 WHERE id = ?2",
+                rusqlite::params![version_number, id],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+    }
+
+    for (section, text, key) in current {
+        if active.iter().any(|(_, k)| *k == key) {
+            continue;
+        }
+        // 被移除后又回来的规则沿用此前的置信度记录
+        let previous = previous_rule(conn, skill_id, &key)?;
+        let (diff_record_id, original_sample_id) = match source {
+            RuleSource::DiffRecord(id) => (Some(id), None),
+            RuleSource::Samples => (None, None),
+            RuleSource::Manual => previous
+                .as_ref()
+                .map_or((None, None), |p| (p.diff_record_id, p.original_sample_id)),
+        };
+        let (support_count, contradiction_count) = previous
+            .as_ref()
+            .map_or((1, 0), |p| (p.support_count, p.contradiction_count));
+        conn.execute(
+            "INSERT INTO skill_rule (skill_id, section, text, rule_key, introduced_version,
+                                     diff_record_id, original_sample_id,
+                                     support_count, contradiction_count)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            rusqlite::params![
+                skill_id,
+                section.as_str(),
+                text,
+                key,
+                version_number,
+                diff_record_id,
+                original_sample_id,
+                support_count,
+                contradiction_count
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+/// 同一规则最近一次的记录（回滚或手动恢复规则时沿用其出处与置信度）
+struct PreviousRule {
+    diff_record_id: Option<i64>,
+    original_sample_id: Option<i64>,
+    support_count: i64,
+    contradiction_count: i64,
+}
+
+fn previous_rule(
+    conn: &rusqlite::Connection,
+    skill_id: i64,
+    rule_key: &str,
+) -> Result<Option<PreviousRule>, String> {
+    match conn.query_row(
+        "SELECT diff_record_id, original_sample_id, support_count, contradiction_count
+         FROM skill_rule WHERE skill_id = ?1 AND rule_key = ?2 ORDER BY id DESC LIMIT 1",
+        rusqlite::params![skill_id, rule_key],
+        |row| {
+            Ok(PreviousRule {
+                diff_record_id: row.get(0)?,
+                original_sample_id: row.get(1)?,
+                support_count: row.get(2)?,
+                contradiction_count: row.get(3)?,
+            })
+        },
+    ) {
+        Ok(previous) => Ok(Some(previous)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// 内部辅助：读取指定版本的结构化内容
+fn load_version_spec(
+    conn: &rusqlite::Connection,
+    skill_id: i64,
+    version_number: i64,
+) -> Result<Option<SkillSpec>, String> {
+    let content_json: String = conn
+        .query_row(
+            "SELECT content_json FROM skill_version WHERE skill_id = ?1 AND version_number = ?2",
+            rusqlite::params![skill_id, version_number],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("版本未找到: {}", e))?;
+
+    SkillSpec::from_content_json(&content_json)
+}
+
+fn row_to_rule(row: &rusqlite::Row) -> rusqlite::Result<SkillRule> {
+    Ok(SkillRule {
+        id: row.get(0)?,
+        skill_id: row.get(1)?,
+        section: row.get(2)?,
+        text: row.get(3)?,
+        introduced_version: row.get(4)?,
+        removed_version: row.get(5)?,
+        diff_record_id: row.get(6)?,
+        original_sample_id: row.get(7)?,
+        support_count: row.get(8)?,
+        contradiction_count: row.get(9)?,
+        confidence: rule_confidence(row.get(8)?, row.get(9)?),
+        created_at: row.get(10)?,
+    })
+}
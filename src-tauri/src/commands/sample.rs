@@ -0,0 +1,227 @@
+use crate::db::Database;
+use crate::models::skill::{OriginalSample, SampleImportReport, SkipReason, SkippedFile};
+use crate::services::sample_import::{self, ExtractedSample};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tauri::State;
+
+/// 列出 Skill 保存的原创样本（按添加顺序）
+#[tauri::command]
+pub fn list_samples(db: State<'_, Database>, skill_id: i64) -> Result<Vec<OriginalSample>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    list_samples_for(&conn, skill_id)
+}
+
+/// 获取单篇样本
+#[tauri::command]
+pub fn get_sample(db: State<'_, Database>, sample_id: i64) -> Result<OriginalSample, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    get_sample_by_id(&conn, sample_id)
+}
+
+/// 为 Skill 添加一篇原创样本；未提供标题时取正文首行
+#[tauri::command]
+pub fn add_sample(
+    db: State<'_, Database>,
+    skill_id: i64,
+    title: Option<String>,
+    content: String,
+) -> Result<OriginalSample, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let id = insert_sample(&conn, skill_id, title.as_deref(), &content)?;
+    get_sample_by_id(&conn, id)
+}
+
+/// 修改样本标题或正文
+#[tauri::command]
+pub fn update_sample(
+    db: State<'_, Database>,
+    sample_id: i64,
+    title: Option<String>,
+    content: Option<String>,
+) -> Result<OriginalSample, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let existing = get_sample_by_id(&conn, sample_id)?;
+
+    let content = content.unwrap_or(existing.content);
+    if content.trim().is_empty() {
+        return Err("样本内容不能为空".to_string());
+    }
+    let title = title
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .unwrap_or(existing.title);
+
+    conn.execute(
+        "UPDATE original_sample SET title = ?1, content = ?2 WHERE id = ?3",
+        rusqlite::params![title, content.trim(), sample_id],
+    )
+    .map_err(|e| e.to_string())?;
+    get_sample_by_id(&conn, sample_id)
+}
+
+/// 删除样本；由它提取的规则保留，只是不再记录出处
+#[tauri::command]
+pub fn delete_sample(db: State<'_, Database>, sample_id: i64) -> Result<(), String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let affected = conn
+        .execute(
+            "DELETE FROM original_sample WHERE id = ?1",
+            rusqlite::params![sample_id],
+        )
+        .map_err(|e| e.to_string())?;
+    if affected == 0 {
+        return Err("样本未找到".to_string());
+    }
+    Ok(())
+}
+
+/// 从目录或文件列表批量导入样本：支持 .md / .txt / .html，去掉 front-matter 与标记，
+/// 按内容哈希与已有样本及本批文件去重，返回导入结果和跳过明细。
+/// 遍历和读取文件时不占用数据库，全部读完后在一个事务中写入
+#[tauri::command]
+pub async fn import_samples(
+    db: State<'_, Database>,
+    skill_id: i64,
+    paths: Vec<String>,
+) -> Result<SampleImportReport, String> {
+    let paths: Vec<PathBuf> = paths.into_iter().map(PathBuf::from).collect();
+    let (samples, skipped) =
+        tokio::task::spawn_blocking(move || sample_import::read_samples(&paths))
+            .await
+            .map_err(|e| e.to_string())?;
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    store_imported_samples(&conn, skill_id, samples, skipped)
+}
+
+/// import_samples 的写入部分：去重后在一个事务中写入，任一条失败则整批不导入
+pub(crate) fn store_imported_samples(
+    conn: &rusqlite::Connection,
+    skill_id: i64,
+    samples: Vec<(PathBuf, ExtractedSample)>,
+    mut skipped: Vec<SkippedFile>,
+) -> Result<SampleImportReport, String> {
+    conn.query_row(
+        "SELECT id FROM skill WHERE id = ?1",
+        rusqlite::params![skill_id],
+        |row| row.get::<_, i64>(0),
+    )
+    .map_err(|e| format!("Skill 未找到: {}", e))?;
+
+    // 内容哈希 → 已有样本标题 / 本批中先出现的文件路径
+    let mut existing: HashMap<String, String> = list_samples_for(conn, skill_id)?
+        .into_iter()
+        .map(|s| {
+            (
+                sample_import::content_hash(&s.content),
+                format!("已有样本《{}》", s.title),
+            )
+        })
+        .collect();
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let mut imported = Vec::new();
+    for (path, sample) in samples {
+        let hash = sample_import::content_hash(&sample.content);
+        if let Some(origin) = existing.get(&hash) {
+            skipped.push(SkippedFile {
+                path: path.display().to_string(),
+                reason: SkipReason::Duplicate,
+                detail: format!("内容与{}相同", origin),
+            });
+            continue;
+        }
+        let id = insert_sample(&tx, skill_id, sample.title.as_deref(), &sample.content)?;
+        existing.insert(hash, format!("本次导入的 {}", path.display()));
+        imported.push(get_sample_by_id(&tx, id)?);
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(SampleImportReport {
+        skill_id,
+        imported,
+        skipped,
+    })
+}
+
+/// 内部辅助：写入一篇样本，返回样本 ID
+pub(crate) fn insert_sample(
+    conn: &rusqlite::Connection,
+    skill_id: i64,
+    title: Option<&str>,
+    content: &str,
+) -> Result<i64, String> {
+    let content = content.trim();
+    if content.is_empty() {
+        return Err("样本内容不能为空".to_string());
+    }
+    let title = match title.map(str::trim) {
+        Some(title) if !title.is_empty() => title.to_string(),
+        _ => sample_title(content),
+    };
+    conn.execute(
+        "INSERT INTO original_sample (title, content, skill_id) VALUES (?1, ?2, ?3)",
+        rusqlite::params![title, content, skill_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// 内部辅助：按添加顺序列出 Skill 的样本
+pub(crate) fn list_samples_for(
+    conn: &rusqlite::Connection,
+    skill_id: i64,
+) -> Result<Vec<OriginalSample>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, title, content, skill_id, created_at
+             FROM original_sample WHERE skill_id = ?1 ORDER BY id",
+        )
+        .map_err(|e| e.to_string())?;
+    let samples = stmt
+        .query_map(rusqlite::params![skill_id], row_to_sample)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(samples)
+}
+
+/// 内部辅助：按 ID 查询样本
+pub(crate) fn get_sample_by_id(
+    conn: &rusqlite::Connection,
+    id: i64,
+) -> Result<OriginalSample, String> {
+    conn.query_row(
+        "SELECT id, title, content, skill_id, created_at FROM original_sample WHERE id = ?1",
+        rusqlite::params![id],
+        row_to_sample,
+    )
+    .map_err(|e| format!("样本未找到: {}", e))
+}
+
+/// 内部辅助：取样本首个非空行作为标题（去掉 Markdown 标题符号，最多 50 个字符）
+pub(crate) fn sample_title(content: &str) -> String {
+    let title: String = content
+        .lines()
+        .map(|line| line.trim().trim_start_matches('#').trim())
+        .find(|line| !line.is_empty())
+        .unwrap_or_default()
+        .chars()
+        .take(50)
+        .collect();
+    if title.is_empty() {
+        "未命名样本".to_string()
+    } else {
+        title
+    }
+}
+
+fn row_to_sample(row: &rusqlite::Row) -> rusqlite::Result<OriginalSample> {
+    Ok(OriginalSample {
+        id: row.get(0)?,
+        title: row.get(1)?,
+        content: row.get(2)?,
+        skill_id: row.get(3)?,
+        created_at: row.get(4)?,
+    })
+}
@@ -1,8 +1,26 @@
-use crate::db::Database;
-use crate::models::skill::{CreateSkillRequest, Skill, SkillVersion, UpdateSkillRequest};
+use crate::commands::diff::{line_diff, DiffChunk};
+use crate::commands::job::start_job;
+use crate::commands::llm::{load_llm_config, notify_retry};
+use crate::commands::rule::{rule_confidences, sync_skill_rules, RuleSource};
+use crate::commands::sample::{insert_sample, list_samples_for};
+use crate::db::{with_savepoint, Database};
+use crate::models::fingerprint::StyleFingerprint;
+use crate::models::llm::LlmTask;
+use crate::models::skill::{
+    AnalysisProgress, CompactionResult, CreateSkillRequest, ReanalysisResult, RuleConflict,
+    SectionChanges, Skill, SkillVersion, UpdateSkillRequest,
+};
+use crate::models::skill_spec::SkillSpec;
 use crate::prompts;
-use crate::services::llm_service::{self, ChatMessage, LlmConfig};
-use tauri::State;
+use crate::services::job_registry::JobRegistry;
+use crate::services::llm_service::{ChatMessage, LlmConfig, LlmProvider, RetryPolicy};
+use crate::services::skill_evolution::{self, rule_id};
+use crate::services::tokens::TokenEstimator;
+use crate::services::{
+    consistency, skill_compaction, skill_diff, structured_output, style_analysis, stylometry,
+};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, State};
 
 /// 创建新 Skill（同时创建 v1 版本）
 #[tauri::command]
@@ -11,25 +29,39 @@ pub fn create_skill(db: State<'_, Database>, request: CreateSkillRequest) -> Res
 
     let category = request.category.unwrap_or_else(|| "通用".to_string());
     let description = request.description.unwrap_or_default();
-    let content_md = request.content_markdown.unwrap_or_default();
-    let content_json = request.content_json.unwrap_or_else(|| "{}".to_string());
+
+    // 结构化内容在入口处校验，损坏或残缺的 JSON 直接拒绝
+    let spec = SkillSpec::from_content_json(request.content_json.as_deref().unwrap_or_default())?;
+    let content_json = match &spec {
+        Some(spec) => spec.to_content_json()?,
+        None => "{}".to_string(),
+    };
+    let content_md = match (request.content_markdown, &spec) {
+        (Some(md), _) if !md.trim().is_empty() => md,
+        (_, Some(spec)) => prompts::analyze_style::spec_to_markdown(&request.name, spec),
+        (md, None) => md.unwrap_or_default(),
+    };
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
 
     // 插入 Skill
-    conn.execute(
+    tx.execute(
         "INSERT INTO skill (name, category, description) VALUES (?1, ?2, ?3)",
         rusqlite::params![request.name, category, description],
     )
     .map_err(|e| e.to_string())?;
 
-    let skill_id = conn.last_insert_rowid();
+    let skill_id = tx.last_insert_rowid();
 
     // 创建 v1 版本
-    conn.execute(
+    tx.execute(
         "INSERT INTO skill_version (skill_id, version_number, content_markdown, content_json, change_summary)
          VALUES (?1, 1, ?2, ?3, '初始版本')",
         rusqlite::params![skill_id, content_md, content_json],
     )
     .map_err(|e| e.to_string())?;
+    sync_skill_rules(&tx, skill_id, 1, RuleSource::Manual)?;
+    tx.commit().map_err(|e| e.to_string())?;
 
     // 返回创建的 Skill
     get_skill_by_id(&conn, skill_id)
@@ -117,7 +149,8 @@ pub fn get_skill_versions(
 
     let mut stmt = conn
         .prepare(
-            "SELECT id, skill_id, version_number, content_markdown, content_json, change_summary, created_at
+            "SELECT id, skill_id, version_number, content_markdown, content_json, change_summary,
+                    fingerprint_json, created_at
              FROM skill_version WHERE skill_id = ?1 ORDER BY version_number DESC",
         )
         .map_err(|e| e.to_string())?;
@@ -131,7 +164,8 @@ pub fn get_skill_versions(
                 content_markdown: row.get(3)?,
                 content_json: row.get(4)?,
                 change_summary: row.get(5)?,
-                created_at: row.get(6)?,
+                fingerprint_json: row.get(6)?,
+                created_at: row.get(7)?,
             })
         })
         .map_err(|e| e.to_string())?
@@ -149,9 +183,237 @@ pub fn get_skill_version(
     version_number: i64,
 ) -> Result<SkillVersion, String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    get_version_by_number(&conn, skill_id, version_number)
+}
+
+/// 回滚到历史版本：把目标版本的内容复制为一个新版本，历史保持只追加，
+/// 已记录 skill_version_used 的文章仍能找到原来的版本
+#[tauri::command]
+pub fn rollback_skill(
+    db: State<'_, Database>,
+    skill_id: i64,
+    target_version: i64,
+) -> Result<SkillVersion, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    rollback_to_version(&conn, skill_id, target_version)
+}
+
+/// rollback_skill 的实现，返回新建的版本
+pub(crate) fn rollback_to_version(
+    conn: &rusqlite::Connection,
+    skill_id: i64,
+    target_version: i64,
+) -> Result<SkillVersion, String> {
+    let skill = get_skill_by_id(conn, skill_id)?;
+    if target_version == skill.current_version {
+        return Err(format!("v{} 已经是当前版本，无需回滚", target_version));
+    }
+    let target = get_version_by_number(conn, skill_id, target_version)?;
+
+    let (new_version, _) = insert_next_version(
+        conn,
+        skill_id,
+        &target.content_markdown,
+        &target.content_json,
+        &format!("rolled back from v{}", target_version),
+        RuleSource::Manual,
+        None,
+    )?;
+
+    get_version_by_number(conn, skill_id, new_version)
+}
+
+/// 检查 Skill 版本中相互矛盾的规则（默认检查当前版本）
+#[tauri::command]
+pub fn check_skill_consistency(
+    db: State<'_, Database>,
+    skill_id: i64,
+    version_number: Option<i64>,
+) -> Result<Vec<RuleConflict>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let version_number = match version_number {
+        Some(v) => v,
+        None => get_skill_by_id(&conn, skill_id)?.current_version,
+    };
+    let version = get_version_by_number(&conn, skill_id, version_number)?;
+    Ok(SkillSpec::from_content_json(&version.content_json)?
+        .map(|spec| consistency::check_spec(&spec))
+        .unwrap_or_default())
+}
+
+/// 两个 Skill 版本的对比结果
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SkillVersionComparison {
+    pub skill_id: i64,
+    pub from_version: i64,
+    pub to_version: i64,
+    /// 规则级变化，只包含有变化的章节
+    pub sections: Vec<SectionChanges>,
+    /// content_markdown 的逐行差异
+    pub markdown_diff: Vec<DiffChunk>,
+}
+
+/// 对比两个版本：按章节列出新增/删除/修改的规则，并附带 Markdown 行级 diff
+#[tauri::command]
+pub fn compare_skill_versions(
+    db: State<'_, Database>,
+    skill_id: i64,
+    from: i64,
+    to: i64,
+) -> Result<SkillVersionComparison, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    compare_versions(&conn, skill_id, from, to)
+}
 
+/// compare_skill_versions 的实现；只有 Markdown 的版本按空的结构化内容参与对比
+pub(crate) fn compare_versions(
+    conn: &rusqlite::Connection,
+    skill_id: i64,
+    from: i64,
+    to: i64,
+) -> Result<SkillVersionComparison, String> {
+    let from_version = get_version_by_number(conn, skill_id, from)?;
+    let to_version = get_version_by_number(conn, skill_id, to)?;
+
+    let parse = |version: &SkillVersion| {
+        SkillSpec::from_content_json(&version.content_json)
+            .map(Option::unwrap_or_default)
+            .map_err(|e| format!("v{} {}", version.version_number, e))
+    };
+    let sections = skill_diff::compare_specs(&parse(&from_version)?, &parse(&to_version)?);
+
+    Ok(SkillVersionComparison {
+        skill_id,
+        from_version: from,
+        to_version: to,
+        sections,
+        markdown_diff: line_diff(&from_version.content_markdown, &to_version.content_markdown),
+    })
+}
+
+/// 压缩 Skill：先做确定性的本地去重，可选再由 LLM 整理，最后按 token 预算裁剪
+/// 低置信度规则，结果保存为新版本
+#[tauri::command]
+pub async fn compact_skill(
+    app: AppHandle,
+    db: State<'_, Database>,
+    jobs: State<'_, JobRegistry>,
+    skill_id: i64,
+    token_budget: Option<usize>,
+    use_llm: Option<bool>,
+) -> Result<CompactionResult, String> {
+    let budget = token_budget.unwrap_or(skill_compaction::DEFAULT_TOKEN_BUDGET);
+
+    // 1. 按置信度排序后本地去重，保证裁剪时先移除最不可信的规则
+    let (name, original, mut spec, mut merged, config, estimator) = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let skill = get_skill_by_id(&conn, skill_id)?;
+        let original = load_current_spec(&conn, skill_id)?
+            .ok_or_else(|| "当前 Skill 版本没有结构化内容，无法压缩".to_string())?;
+        let confidence = rule_confidences(&conn, skill_id)?;
+        let mut spec = skill_evolution::rank_rules(&original, 0.0, |section, text| {
+            confidence.get(&rule_id(section, text)).copied()
+        });
+        let merged = skill_compaction::dedup_spec(&mut spec);
+        let config = match use_llm {
+            Some(true) => Some(load_llm_config(&conn, LlmTask::CompactSkill)?),
+            _ => None,
+        };
+        // Skill 最终注入生成任务的 prompt，预算按生成模型的分词器计算
+        let generate = load_llm_config(&conn, LlmTask::Generate)?;
+        let estimator = TokenEstimator::for_model(&generate.provider, &generate.model);
+        (skill.name, original, spec, merged, config, estimator)
+    };
+
+    // 2. 可选的 LLM 整理；输出同样经过 SkillSpec 校验，并再做一次本地去重
+    let llm_consolidated = config.is_some();
+    if let Some(config) = config {
+        let prompt = prompts::compact_skill::build_compact_prompt(&spec.to_content_json()?, budget);
+        let messages = vec![ChatMessage {
+            role: "user".to_string(),
+            content: prompt,
+        }];
+
+        let mut job = start_job(&app, &jobs, "compact_skill", &name);
+        let job_id = job.info().id;
+        let (consolidated, _) = job
+            .run(structured_output::chat_structured::<SkillSpec, _>(
+                &config,
+                messages,
+                0.2,
+                &RetryPolicy::default(),
+                notify_retry(&app, job_id),
+            ))
+            .await?;
+        spec = consolidated;
+        merged.extend(skill_compaction::dedup_spec(&mut spec));
+    }
+
+    // 3. 按 token 预算裁剪
+    let render = |spec: &SkillSpec| prompts::analyze_style::spec_to_markdown(&name, spec);
+    let tokens_before = estimator.estimate(&render(&original));
+    let trimmed = skill_compaction::enforce_budget(&mut spec, budget, &estimator, render);
+    let tokens_after = estimator.estimate(&render(&spec));
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let changed = !merged.is_empty()
+        || !trimmed.is_empty()
+        || !skill_diff::compare_specs(&original, &spec).is_empty();
+    if !changed {
+        return Ok(CompactionResult {
+            skill_id,
+            version_number: get_skill_by_id(&conn, skill_id)?.current_version,
+            created_version: false,
+            change_summary: "没有需要压缩的内容".to_string(),
+            tokens_before,
+            tokens_after: tokens_before,
+            merged,
+            trimmed,
+            llm_consolidated,
+            conflicts: consistency::check_spec(&original),
+        });
+    }
+
+    let change_summary = skill_compaction::build_compaction_summary(
+        &merged,
+        &trimmed,
+        llm_consolidated,
+        tokens_before,
+        tokens_after,
+    );
+    let (version_number, conflicts) = insert_next_version(
+        &conn,
+        skill_id,
+        &render(&spec),
+        &spec.to_content_json()?,
+        &change_summary,
+        RuleSource::Manual,
+        None,
+    )?;
+
+    Ok(CompactionResult {
+        skill_id,
+        version_number,
+        created_version: true,
+        change_summary,
+        tokens_before,
+        tokens_after,
+        merged,
+        trimmed,
+        llm_consolidated,
+        conflicts,
+    })
+}
+
+/// 内部辅助：按版本号查询 Skill 版本
+pub(crate) fn get_version_by_number(
+    conn: &rusqlite::Connection,
+    skill_id: i64,
+    version_number: i64,
+) -> Result<SkillVersion, String> {
     conn.query_row(
-        "SELECT id, skill_id, version_number, content_markdown, content_json, change_summary, created_at
+        "SELECT id, skill_id, version_number, content_markdown, content_json, change_summary,
+                fingerprint_json, created_at
          FROM skill_version WHERE skill_id = ?1 AND version_number = ?2",
         rusqlite::params![skill_id, version_number],
         |row| {
@@ -162,13 +424,109 @@ pub fn get_skill_version(
                 content_markdown: row.get(3)?,
                 content_json: row.get(4)?,
                 change_summary: row.get(5)?,
-                created_at: row.get(6)?,
+                fingerprint_json: row.get(6)?,
+                created_at: row.get(7)?,
             })
         },
     )
     .map_err(|e| format!("版本未找到: {}", e))
 }
 
+/// 内部辅助：在当前版本之后追加一个新版本并切换 current_version，
+/// 返回新版本号及保存前一致性检查发现的规则冲突（冲突不阻止保存，交给用户处理）。
+/// fingerprint_json 为空表示样本没有变化，沿用当前版本的量化指纹
+pub(crate) fn insert_next_version(
+    conn: &rusqlite::Connection,
+    skill_id: i64,
+    content_markdown: &str,
+    content_json: &str,
+    change_summary: &str,
+    source: RuleSource,
+    fingerprint_json: Option<&str>,
+) -> Result<(i64, Vec<RuleConflict>), String> {
+    let conflicts = match SkillSpec::from_content_json(content_json)? {
+        Some(spec) => consistency::check_spec(&spec),
+        None => Vec::new(),
+    };
+
+    // 获取当前版本号
+    let current_version: i64 = conn
+        .query_row(
+            "SELECT current_version FROM skill WHERE id = ?1",
+            rusqlite::params![skill_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("获取 Skill 失败: {}", e))?;
+
+    let new_version = current_version + 1;
+
+    // 版本、current_version 与规则出处一起写入，任一步失败都不留下半个版本
+    with_savepoint(conn, "insert_next_version", || {
+        // 创建新版本
+        conn.execute(
+            "INSERT INTO skill_version (skill_id, version_number, content_markdown, content_json, change_summary, fingerprint_json)
+             VALUES (?1, ?2, ?3, ?4, ?5,
+                     COALESCE(?7, (SELECT fingerprint_json FROM skill_version WHERE skill_id = ?1 AND version_number = ?6), ''))",
+            rusqlite::params![skill_id, new_version, content_markdown, content_json, change_summary, current_version, fingerprint_json],
+        )
+        .map_err(|e| e.to_string())?;
+
+        // 更新 Skill 当前版本号
+        conn.execute(
+            "UPDATE skill SET current_version = ?1, updated_at = datetime('now') WHERE id = ?2",
+            rusqlite::params![new_version, skill_id],
+        )
+        .map_err(|e| e.to_string())?;
+
+        sync_skill_rules(conn, skill_id, new_version, source)
+    })?;
+
+    Ok((new_version, conflicts))
+}
+
+/// 内部辅助：读取 Skill 当前版本的结构化内容（仅有 Markdown 的版本返回 None）
+pub(crate) fn load_current_spec(
+    conn: &rusqlite::Connection,
+    skill_id: i64,
+) -> Result<Option<SkillSpec>, String> {
+    let content_json: String = conn
+        .query_row(
+            "SELECT sv.content_json FROM skill s
+             JOIN skill_version sv ON sv.skill_id = s.id AND sv.version_number = s.current_version
+             WHERE s.id = ?1",
+            rusqlite::params![skill_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("获取版本内容失败: {}", e))?;
+
+    SkillSpec::from_content_json(&content_json)
+}
+
+/// 内部辅助：读取 Skill 某个版本（默认当前版本）的量化文风指纹，没有样本指纹时返回 None
+pub(crate) fn load_fingerprint(
+    conn: &rusqlite::Connection,
+    skill_id: i64,
+    version_number: Option<i64>,
+) -> Result<Option<StyleFingerprint>, String> {
+    let fingerprint_json: String = conn
+        .query_row(
+            "SELECT sv.fingerprint_json FROM skill s
+             JOIN skill_version sv ON sv.skill_id = s.id
+                 AND sv.version_number = COALESCE(?2, s.current_version)
+             WHERE s.id = ?1",
+            rusqlite::params![skill_id, version_number],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("获取版本内容失败: {}", e))?;
+
+    if fingerprint_json.trim().is_empty() {
+        return Ok(None);
+    }
+    serde_json::from_str(&fingerprint_json)
+        .map(Some)
+        .map_err(|e| format!("解析文风指纹失败: {}", e))
+}
+
 /// 内部辅助：按 ID 查询 Skill
 fn get_skill_by_id(conn: &rusqlite::Connection, id: i64) -> Result<Skill, String> {
     conn.query_row(
@@ -190,11 +548,24 @@ fn get_skill_by_id(conn: &rusqlite::Connection, id: i64) -> Result<Skill, String
     .map_err(|e| format!("Skill 未找到: {}", e))
 }
 
+/// 分批风格分析进度事件名
+pub const ANALYSIS_PROGRESS_EVENT: &str = "skill://analysis_progress";
+
+/// skill://analysis_progress 事件负载
+#[derive(Debug, Clone, Serialize)]
+pub struct AnalysisProgressEvent {
+    pub job_id: u64,
+    #[serde(flatten)]
+    pub progress: AnalysisProgress,
+}
+
 /// 创建 Skill 并通过样本文章进行初始建模
 /// 样本由分隔线 "---" 分割，调用 LLM 分析风格后写入 v1 版本
 #[tauri::command]
 pub async fn create_skill_with_samples(
+    app: AppHandle,
     db: State<'_, Database>,
+    jobs: State<'_, JobRegistry>,
     name: String,
     category: String,
     description: String,
@@ -214,54 +585,147 @@ pub async fn create_skill_with_samples(
     // 2. 读取 LLM 配置
     let config = {
         let conn = db.conn.lock().map_err(|e| e.to_string())?;
-        conn.query_row(
-            "SELECT llm_provider, llm_endpoint, llm_api_key, llm_model FROM user_profile WHERE id = 1",
-            [],
-            |row| {
-                Ok(LlmConfig {
-                    provider: row.get(0)?,
-                    endpoint: row.get(1)?,
-                    api_key: row.get(2)?,
-                    model: row.get(3)?,
-                })
-            },
-        )
-        .map_err(|e| format!("读取 LLM 配置失败，请先在设置中配置: {}", e))?
+        load_llm_config(&conn, LlmTask::AnalyzeStyle)?
     };
 
-    if config.api_key.is_empty() {
-        return Err("请先在设置中配置 LLM API Key".to_string());
-    }
-
-    // 3. 调用 LLM 分析风格
-    let prompt = prompts::analyze_style::build_analyze_prompt(&samples);
-    let messages = vec![ChatMessage {
-        role: "user".to_string(),
-        content: prompt,
-    }];
-
-    let json_content = llm_service::chat_completion(&config, messages, 0.3).await?;
+    // 3. 本地统计量化指纹，连同样本一起交给 LLM 分析风格
+    let (spec, json_content, fingerprint_json) = analyze_samples(
+        &app,
+        &jobs,
+        &config,
+        &samples,
+        "create_skill_with_samples",
+        &name,
+    )
+    .await?;
 
     // 4. 转为 Markdown 格式
-    let markdown_content = prompts::analyze_style::json_to_markdown(&name, &json_content);
+    let markdown_content = prompts::analyze_style::spec_to_markdown(&name, &spec);
 
     // 5. 创建 Skill + v1 版本
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
 
-    conn.execute(
+    tx.execute(
         "INSERT INTO skill (name, category, description) VALUES (?1, ?2, ?3)",
         rusqlite::params![name, category, description],
     )
     .map_err(|e| e.to_string())?;
 
-    let skill_id = conn.last_insert_rowid();
+    let skill_id = tx.last_insert_rowid();
 
-    conn.execute(
-        "INSERT INTO skill_version (skill_id, version_number, content_markdown, content_json, change_summary)
-         VALUES (?1, 1, ?2, ?3, '从原创样本中提取初始风格')",
-        rusqlite::params![skill_id, markdown_content, json_content],
+    // 保存样本原文，之后可以重新建模
+    for sample in &samples {
+        insert_sample(&tx, skill_id, None, sample)?;
+    }
+
+    tx.execute(
+        "INSERT INTO skill_version (skill_id, version_number, content_markdown, content_json, change_summary, fingerprint_json)
+         VALUES (?1, 1, ?2, ?3, '从原创样本中提取初始风格', ?4)",
+        rusqlite::params![skill_id, markdown_content, json_content, fingerprint_json],
     )
     .map_err(|e| e.to_string())?;
 
+    // 风格是从整批样本中提取的，规则不归属于其中某一篇
+    sync_skill_rules(&tx, skill_id, 1, RuleSource::Samples)?;
+    tx.commit().map_err(|e| e.to_string())?;
+
     get_skill_by_id(&conn, skill_id)
 }
+
+/// 用已保存的全部原创样本重新建模，生成新版本（例如换用更强的模型后）。
+/// 新版本同时更新样本指纹，旧版本保留可回滚
+#[tauri::command]
+pub async fn reanalyze_skill_from_samples(
+    app: AppHandle,
+    db: State<'_, Database>,
+    jobs: State<'_, JobRegistry>,
+    skill_id: i64,
+) -> Result<ReanalysisResult, String> {
+    let (name, samples, config) = {
+        let conn = db.conn.lock().map_err(|e| e.to_string())?;
+        let skill = get_skill_by_id(&conn, skill_id)?;
+        let samples = list_samples_for(&conn, skill_id)?;
+        let config = load_llm_config(&conn, LlmTask::AnalyzeStyle)?;
+        (skill.name, samples, config)
+    };
+    if samples.is_empty() {
+        return Err("该 Skill 没有保存的原创样本，请先添加样本".to_string());
+    }
+
+    let contents: Vec<String> = samples.iter().map(|s| s.content.clone()).collect();
+    let (spec, json_content, fingerprint_json) = analyze_samples(
+        &app,
+        &jobs,
+        &config,
+        &contents,
+        "reanalyze_skill_from_samples",
+        &name,
+    )
+    .await?;
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let previous = load_current_spec(&conn, skill_id)?.unwrap_or_default();
+    let change_summary = format!(
+        "从 {} 篇原创样本重新建模（{}）",
+        samples.len(),
+        config.model
+    );
+    let (version_number, conflicts) = insert_next_version(
+        &conn,
+        skill_id,
+        &prompts::analyze_style::spec_to_markdown(&name, &spec),
+        &json_content,
+        &change_summary,
+        RuleSource::Samples,
+        Some(&fingerprint_json),
+    )?;
+
+    Ok(ReanalysisResult {
+        skill_id,
+        version_number,
+        sample_count: samples.len(),
+        change_summary,
+        sections: skill_diff::compare_specs(&previous, &spec),
+        conflicts,
+    })
+}
+
+/// 内部辅助：统计样本指纹并调用 LLM 分析风格，返回结构化内容、其 JSON 以及指纹 JSON。
+/// 样本超出单次上下文预算时分批分析再合并，每完成一批推送 skill://analysis_progress 事件
+async fn analyze_samples(
+    app: &AppHandle,
+    jobs: &JobRegistry,
+    config: &LlmConfig,
+    samples: &[String],
+    job_kind: &str,
+    label: &str,
+) -> Result<(SkillSpec, String, String), String> {
+    // 本地 Ollama 无需 API Key
+    let provider = LlmProvider::from_str(&config.provider);
+    if config.api_key.is_empty() && !matches!(provider, LlmProvider::Ollama) {
+        return Err("请先在设置中配置 LLM API Key".to_string());
+    }
+
+    let fingerprint = stylometry::fingerprint(samples);
+    let fingerprint_json = serde_json::to_string(&fingerprint).map_err(|e| e.to_string())?;
+
+    let mut job = start_job(app, jobs, job_kind, label);
+    let job_id = job.info().id;
+    let (spec, json_content) = job
+        .run(style_analysis::map_reduce_analysis(
+            config,
+            samples,
+            style_analysis::batch_tokens_for(config),
+            &RetryPolicy::default(),
+            notify_retry(app, job_id),
+            |progress| {
+                let _ = app.emit(
+                    ANALYSIS_PROGRESS_EVENT,
+                    AnalysisProgressEvent { job_id, progress },
+                );
+            },
+        ))
+        .await?;
+    Ok((spec, json_content, fingerprint_json))
+}
@@ -0,0 +1,31 @@
+use crate::commands::skill::load_fingerprint;
+use crate::db::Database;
+use crate::models::fingerprint::StyleMatch;
+use crate::services::{style_match, stylometry};
+use tauri::State;
+
+/// 将文本与 Skill 当前版本的样本指纹比较，返回逐项偏差与总体相似度（不调用 LLM）
+#[tauri::command]
+pub fn score_style_match(
+    db: State<'_, Database>,
+    skill_id: i64,
+    text: String,
+) -> Result<StyleMatch, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    match_skill_style(&conn, skill_id, None, &text)?
+        .ok_or_else(|| "该 Skill 没有样本指纹，请先通过原创样本建模".to_string())
+}
+
+/// score_style_match 的实现；指定版本没有样本指纹时返回 None
+pub(crate) fn match_skill_style(
+    conn: &rusqlite::Connection,
+    skill_id: i64,
+    version_number: Option<i64>,
+    text: &str,
+) -> Result<Option<StyleMatch>, String> {
+    let Some(expected) = load_fingerprint(conn, skill_id, version_number)? else {
+        return Ok(None);
+    };
+    let actual = stylometry::fingerprint(&[text.to_string()]);
+    Ok(Some(style_match::compare(&expected, &actual)))
+}
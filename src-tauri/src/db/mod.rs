@@ -25,3 +25,26 @@ impl Database {
         })
     }
 }
+
+/// 在保存点中执行一组写入：成功时释放保存点，失败时回滚到保存点。
+/// 与事务不同，保存点可以嵌套在调用方已开启的事务中
+pub(crate) fn with_savepoint<T>(
+    conn: &Connection,
+    name: &str,
+    f: impl FnOnce() -> Result<T, String>,
+) -> Result<T, String> {
+    conn.execute_batch(&format!("SAVEPOINT {}", name))
+        .map_err(|e| e.to_string())?;
+    match f() {
+        Ok(value) => {
+            conn.execute_batch(&format!("RELEASE {}", name))
+                .map_err(|e| e.to_string())?;
+            Ok(value)
+        }
+        Err(e) => {
+            // 回滚失败时仍以原始错误为准
+            let _ = conn.execute_batch(&format!("ROLLBACK TO {0}; RELEASE {0}", name));
+            Err(e)
+        }
+    }
+}
@@ -32,6 +32,7 @@ pub fn create_tables(conn: &Connection) -> Result<(), rusqlite::Error> {
             content_markdown TEXT NOT NULL DEFAULT '',
             content_json    TEXT NOT NULL DEFAULT '{}',
             change_summary  TEXT NOT NULL DEFAULT '初始版本',
+            fingerprint_json TEXT NOT NULL DEFAULT '',
             created_at      TEXT NOT NULL DEFAULT (datetime('now')),
             FOREIGN KEY (skill_id) REFERENCES skill(id) ON DELETE CASCADE
         );
@@ -45,6 +46,10 @@ pub fn create_tables(conn: &Connection) -> Result<(), rusqlite::Error> {
             skill_id            INTEGER,
             skill_version_used  INTEGER,
             status              TEXT NOT NULL DEFAULT 'draft',
+            ai_flavor_generated REAL,
+            ai_flavor_refined   REAL,
+            ai_flavor_dict_version INTEGER,
+            style_similarity    REAL,
             created_at          TEXT NOT NULL DEFAULT (datetime('now')),
             updated_at          TEXT NOT NULL DEFAULT (datetime('now')),
             FOREIGN KEY (skill_id) REFERENCES skill(id) ON DELETE SET NULL
@@ -70,9 +75,108 @@ pub fn create_tables(conn: &Connection) -> Result<(), rusqlite::Error> {
             FOREIGN KEY (skill_id) REFERENCES skill(id) ON DELETE CASCADE
         );
 
+        CREATE TABLE IF NOT EXISTS llm_profile (
+            id          INTEGER PRIMARY KEY AUTOINCREMENT,
+            name        TEXT NOT NULL,
+            provider    TEXT NOT NULL DEFAULT 'openai',
+            endpoint    TEXT NOT NULL DEFAULT '',
+            api_key     TEXT NOT NULL DEFAULT '',
+            model       TEXT NOT NULL DEFAULT '',
+            created_at  TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at  TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        -- 任务 → 配置档案路由，未配置的任务使用 user_profile 中的默认配置
+        CREATE TABLE IF NOT EXISTS llm_task_route (
+            task        TEXT PRIMARY KEY,
+            profile_id  INTEGER,
+            FOREIGN KEY (profile_id) REFERENCES llm_profile(id) ON DELETE SET NULL
+        );
+
+        -- 规则出处：每条规则在哪个版本引入、在哪个版本移除，以及来自哪条 Diff 记录或原创样本
+        CREATE TABLE IF NOT EXISTS skill_rule (
+            id                  INTEGER PRIMARY KEY AUTOINCREMENT,
+            skill_id            INTEGER NOT NULL,
+            section             TEXT NOT NULL,
+            text                TEXT NOT NULL,
+            rule_key            TEXT NOT NULL,
+            introduced_version  INTEGER NOT NULL,
+            removed_version     INTEGER,
+            diff_record_id      INTEGER,
+            original_sample_id  INTEGER,
+            support_count       INTEGER NOT NULL DEFAULT 1,
+            contradiction_count INTEGER NOT NULL DEFAULT 0,
+            created_at          TEXT NOT NULL DEFAULT (datetime('now')),
+            FOREIGN KEY (skill_id) REFERENCES skill(id) ON DELETE CASCADE,
+            FOREIGN KEY (diff_record_id) REFERENCES diff_record(id) ON DELETE SET NULL,
+            FOREIGN KEY (original_sample_id) REFERENCES original_sample(id) ON DELETE SET NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_skill_rule_key ON skill_rule (skill_id, rule_key);
+
+        -- 规则置信度反馈：同一篇文章对同一规则的支持（support）或违背（contradiction）只计一次，
+        -- 重新分析同一篇文章不会重复累加；diff_record_id 记录首次计入时的 Diff
+        CREATE TABLE IF NOT EXISTS rule_feedback (
+            article_id      INTEGER NOT NULL,
+            skill_id        INTEGER NOT NULL,
+            rule_key        TEXT NOT NULL,
+            kind            TEXT NOT NULL,
+            diff_record_id  INTEGER NOT NULL,
+            created_at      TEXT NOT NULL DEFAULT (datetime('now')),
+            PRIMARY KEY (article_id, skill_id, rule_key, kind),
+            FOREIGN KEY (skill_id) REFERENCES skill(id) ON DELETE CASCADE
+        );
+
+        -- 语义检索向量：source 为 sample（original_sample）或 article，不同向量模型的结果分开存放
+        CREATE TABLE IF NOT EXISTS embedding (
+            id            INTEGER PRIMARY KEY AUTOINCREMENT,
+            source        TEXT NOT NULL,
+            source_id     INTEGER NOT NULL,
+            model         TEXT NOT NULL,
+            content_hash  TEXT NOT NULL,
+            dim           INTEGER NOT NULL,
+            vector        BLOB NOT NULL,
+            created_at    TEXT NOT NULL DEFAULT (datetime('now')),
+            UNIQUE (source, source_id, model)
+        );
+
         -- 确保至少有一条用户配置记录
         INSERT OR IGNORE INTO user_profile (id, display_name) VALUES (1, '默认用户');
         ",
     )?;
+
+    // 已有数据库中的表不会被 CREATE TABLE IF NOT EXISTS 更新，新增列在这里补齐
+    add_column_if_missing(
+        conn,
+        "skill_version",
+        "fingerprint_json",
+        "TEXT NOT NULL DEFAULT ''",
+    )?;
+    add_column_if_missing(conn, "article", "ai_flavor_generated", "REAL")?;
+    add_column_if_missing(conn, "article", "ai_flavor_refined", "REAL")?;
+    add_column_if_missing(conn, "article", "ai_flavor_dict_version", "INTEGER")?;
+    add_column_if_missing(conn, "article", "style_similarity", "REAL")?;
+    Ok(())
+}
+
+/// 表中不存在该列时执行 ALTER TABLE ADD COLUMN
+fn add_column_if_missing(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<(), rusqlite::Error> {
+    let exists = conn
+        .prepare(&format!("PRAGMA table_info({})", table))?
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<Result<Vec<_>, _>>()?
+        .iter()
+        .any(|name| name == column);
+    if !exists {
+        conn.execute_batch(&format!(
+            "ALTER TABLE {} ADD COLUMN {} {};",
+            table, column, definition
+        ))?;
+    }
     Ok(())
 }
@@ -8,6 +8,7 @@ mod services;
 mod tests;
 
 use db::Database;
+use services::job_registry::JobRegistry;
 use tauri::Manager;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -19,9 +20,15 @@ pub fn run() {
             let app_data_dir = app.path().app_data_dir().expect("无法获取应用数据目录");
 
             let database = Database::new(&app_data_dir).expect("数据库初始化失败");
+            {
+                // 旧数据没有规则出处记录时一次性补齐，之后列出规则只读不写
+                let conn = database.conn.lock().expect("数据库锁获取失败");
+                commands::rule::backfill_all_rules(&conn).expect("补齐规则记录失败");
+            }
 
             // 将 Database 注册为 Tauri 全局状态
             app.manage(database);
+            app.manage(JobRegistry::default());
 
             Ok(())
         })
@@ -34,12 +41,27 @@ pub fn run() {
             commands::skill::delete_skill,
             commands::skill::get_skill_versions,
             commands::skill::get_skill_version,
+            commands::skill::rollback_skill,
+            commands::skill::compare_skill_versions,
+            commands::skill::compact_skill,
+            commands::skill::check_skill_consistency,
+            // Rules
+            commands::rule::list_skill_rules,
+            commands::rule::get_rule_history,
             // LLM
             commands::llm::save_llm_config,
             commands::llm::get_llm_config,
             commands::llm::test_llm_connection,
+            commands::llm::create_llm_profile,
+            commands::llm::list_llm_profiles,
+            commands::llm::update_llm_profile,
+            commands::llm::delete_llm_profile,
+            commands::llm::get_task_routes,
+            commands::llm::set_task_route,
             // Article
             commands::article::generate_article,
+            commands::article::generate_article_stream,
+            commands::article::preview_few_shot_excerpts,
             commands::article::save_article,
             commands::article::get_article,
             commands::article::list_articles,
@@ -47,12 +69,34 @@ pub fn run() {
             commands::diff::compute_diff,
             commands::diff::analyze_diff,
             commands::diff::evolve_skill,
+            commands::diff::apply_diff_record,
+            commands::diff::preview_evolution,
+            commands::diff::commit_evolution,
+            // Lint & style scoring
+            commands::lint::lint_text,
+            commands::ai_flavor::score_ai_flavor,
+            commands::ai_flavor::get_ai_flavor_trend,
+            commands::style_match::score_style_match,
+            // Jobs
+            commands::job::list_jobs,
+            commands::job::cancel_job,
             // Export
             commands::export::export_skill_markdown,
             commands::export::export_skill_json,
             // Onboarding
             commands::onboarding::get_onboarding_status,
             commands::skill::create_skill_with_samples,
+            // Samples
+            commands::sample::list_samples,
+            commands::sample::get_sample,
+            commands::sample::add_sample,
+            commands::sample::update_sample,
+            commands::sample::delete_sample,
+            commands::sample::import_samples,
+            commands::skill::reanalyze_skill_from_samples,
+            // Semantic search
+            commands::embedding::refresh_embedding_index,
+            commands::embedding::semantic_search,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
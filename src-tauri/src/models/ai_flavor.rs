@@ -0,0 +1,33 @@
+use crate::models::lint::LintIssue;
+use serde::{Deserialize, Serialize};
+
+/// 一段文本的 AI 味评分
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AiFlavorScore {
+    /// 0–100，越高越像模型的惯用腔调
+    pub score: f64,
+    /// 评分所用内置短语词典的版本
+    pub dictionary_version: i64,
+    /// 每千字命中的加权短语数
+    pub phrase_density: f64,
+    /// 列表行（- / 1. / 1、）占非空行的比例
+    pub list_line_ratio: f64,
+    /// 句长变异系数，越小句子越整齐；句子不足 3 句时为 None
+    pub sentence_length_cv: Option<f64>,
+    /// 每千字的 **加粗** 片段数
+    pub bold_density: f64,
+    /// 命中的词典短语，可直接在编辑器中标注
+    pub hits: Vec<LintIssue>,
+}
+
+/// 某个 Skill 生成的文章的 AI 味与风格相似度记录，按创建时间排列，用于对比各版本效果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AiFlavorTrendPoint {
+    pub article_id: i64,
+    pub title: String,
+    pub skill_version_used: Option<i64>,
+    pub ai_flavor_generated: Option<f64>,
+    pub ai_flavor_refined: Option<f64>,
+    pub style_similarity: Option<f64>,
+    pub created_at: String,
+}
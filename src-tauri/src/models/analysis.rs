@@ -0,0 +1,86 @@
+use crate::models::skill_spec::RuleSection;
+use serde::{Deserialize, Serialize};
+
+/// Diff 分析结果（对应 prompts::diff_analyze 中定义的 JSON 结构）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DiffAnalysis {
+    #[serde(default)]
+    pub modification_analysis: Vec<Modification>,
+    pub new_rules: NewRules,
+    /// 被本次修改违背的现有规则（照抄 Skill 中的原文）
+    #[serde(default)]
+    pub contradicted_rules: Vec<String>,
+    #[serde(default)]
+    pub summary: String,
+}
+
+/// 单条修改分析
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Modification {
+    #[serde(rename = "type", default)]
+    pub kind: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub intent: String,
+}
+
+/// 从修改中提取的新规则
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NewRules {
+    #[serde(default)]
+    pub add_to_style_principles: Vec<String>,
+    #[serde(default)]
+    pub add_to_blocklist_words: Vec<String>,
+    #[serde(default)]
+    pub add_to_blocklist_patterns: Vec<String>,
+    #[serde(default)]
+    pub other_observations: Vec<String>,
+}
+
+impl NewRules {
+    /// 建议追加到指定章节的规则
+    pub fn additions(&self, section: RuleSection) -> &[String] {
+        match section {
+            RuleSection::StylePrinciples => &self.add_to_style_principles,
+            RuleSection::ForbiddenWords => &self.add_to_blocklist_words,
+            RuleSection::ForbiddenPatterns => &self.add_to_blocklist_patterns,
+        }
+    }
+}
+
+impl DiffAnalysis {
+    /// 去除空白条目
+    pub fn sanitize(&mut self) {
+        for list in [
+            &mut self.new_rules.add_to_style_principles,
+            &mut self.new_rules.add_to_blocklist_words,
+            &mut self.new_rules.add_to_blocklist_patterns,
+            &mut self.new_rules.other_observations,
+            &mut self.contradicted_rules,
+        ] {
+            retain_non_blank(list);
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(m) = self
+            .modification_analysis
+            .iter()
+            .find(|m| m.description.trim().is_empty())
+        {
+            return Err(format!(
+                "modification_analysis 中类型为 \"{}\" 的条目缺少 description",
+                m.kind
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn retain_non_blank(list: &mut Vec<String>) {
+    list.retain(|s| !s.trim().is_empty());
+    for s in list.iter_mut() {
+        *s = s.trim().to_string();
+    }
+}
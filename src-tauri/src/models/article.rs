@@ -11,6 +11,13 @@ pub struct Article {
     pub skill_id: Option<i64>,
     pub skill_version_used: Option<i64>,
     pub status: String,
+    /// AI 初稿与用户修改稿的 AI 味评分（0–100），内容为空时为 None
+    pub ai_flavor_generated: Option<f64>,
+    pub ai_flavor_refined: Option<f64>,
+    /// 计算上述评分时的内置词典版本
+    pub ai_flavor_dict_version: Option<i64>,
+    /// AI 初稿与所用 Skill 版本样本指纹的风格相似度（0–100），该版本没有指纹时为 None
+    pub style_similarity: Option<f64>,
     pub created_at: String,
     pub updated_at: String,
 }
@@ -26,3 +33,32 @@ pub struct DiffRecord {
     pub applied_to_skill: bool,
     pub created_at: String,
 }
+
+/// 生成文章时注入原创样本片段作为风格示例的选项
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FewShotOptions {
+    /// 示例片段合计最多占用的 token 数
+    pub token_budget: usize,
+    /// 最多注入的片段数
+    pub max_excerpts: usize,
+}
+
+impl Default for FewShotOptions {
+    fn default() -> Self {
+        FewShotOptions {
+            token_budget: 800,
+            max_excerpts: 3,
+        }
+    }
+}
+
+/// 被选作风格示例的原创样本片段
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SampleExcerpt {
+    pub sample_id: i64,
+    pub sample_title: String,
+    pub text: String,
+    /// 与主题的 BM25 相关度，主题与样本没有共同词项时为 0
+    pub score: f64,
+}
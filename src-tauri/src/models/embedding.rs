@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+
+/// 可被语义检索的内容来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmbeddingSource {
+    /// 原创样本（original_sample）
+    Sample,
+    /// 文章（article），取用户修改稿，没有时取 AI 初稿
+    Article,
+}
+
+impl EmbeddingSource {
+    pub const ALL: [EmbeddingSource; 2] = [EmbeddingSource::Sample, EmbeddingSource::Article];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EmbeddingSource::Sample => "sample",
+            EmbeddingSource::Article => "article",
+        }
+    }
+}
+
+/// 增量更新向量索引的结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EmbeddingIndexReport {
+    /// 使用的向量模型（远端为 provider:model，本地为 local-hash-v1）
+    pub model: String,
+    /// 本次新建或因内容变化重新计算的条目数
+    pub indexed: usize,
+    /// 内容未变、沿用已有向量的条目数
+    pub unchanged: usize,
+    /// 原内容已删除、或所属向量模型已不再使用而被清理的条目数
+    pub removed: usize,
+    /// 远端向量服务不可达、改用本地哈希向量时的说明；为空表示按配置完成
+    pub fallback: Option<String>,
+}
+
+/// 语义检索结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SemanticSearchResult {
+    /// 实际使用的向量模型
+    pub model: String,
+    /// 同 EmbeddingIndexReport.fallback
+    pub fallback: Option<String>,
+    pub hits: Vec<SemanticHit>,
+}
+
+/// 一条语义检索结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SemanticHit {
+    pub source: EmbeddingSource,
+    pub source_id: i64,
+    pub skill_id: Option<i64>,
+    pub title: String,
+    /// 正文开头的一小段，便于在列表中辨认
+    pub snippet: String,
+    /// 与查询的余弦相似度（-1–1）
+    pub similarity: f64,
+}
@@ -0,0 +1,75 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// 长度分布（单位：非空白字符数）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LengthDistribution {
+    pub count: usize,
+    pub mean: f64,
+    pub median: f64,
+    pub p10: f64,
+    pub p90: f64,
+    /// 变异系数（标准差 / 均值），越小长度越整齐
+    pub cv: f64,
+}
+
+/// 标点使用习惯，频次均为每千字
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PunctuationProfile {
+    /// 全角标点占全部标点的比例
+    pub full_width_ratio: f64,
+    pub comma_per_k: f64,
+    pub period_per_k: f64,
+    pub question_per_k: f64,
+    pub exclamation_per_k: f64,
+    /// 破折号（—— / —）
+    pub em_dash_per_k: f64,
+    /// 省略号（…… / … / ...）
+    pub ellipsis_per_k: f64,
+    /// 引号（“” / 「」 / ""）
+    pub quote_per_k: f64,
+}
+
+/// 原创样本的可量化文风指纹，作为 LLM 风格分析的客观依据
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StyleFingerprint {
+    pub sample_count: usize,
+    /// 非空白字符总数
+    pub char_count: usize,
+    pub sentence_length: LengthDistribution,
+    pub paragraph_length: LengthDistribution,
+    pub punctuation: PunctuationProfile,
+    /// 虚词每千字出现次数，只记录出现过的词
+    #[serde(default)]
+    pub function_words: BTreeMap<String, f64>,
+    /// 中文字符占（中文字符 + 英文字母）的比例
+    pub cjk_ratio: f64,
+    /// 每千字中的英文单词数，衡量中英混排程度
+    pub latin_words_per_k: f64,
+}
+
+/// 单项特征与 Skill 指纹的偏差
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeatureDeviation {
+    /// 特征标识，如 "sentence_length.mean"
+    pub feature: String,
+    /// 中文名称，如 "平均句长"
+    pub label: String,
+    /// Skill 样本中的取值
+    pub expected: f64,
+    /// 待评估文本中的取值
+    pub actual: f64,
+    /// 0–1 的归一化距离，0 表示一致
+    pub distance: f64,
+    /// 可读说明，如 "句子比你的长 40%"
+    pub message: String,
+}
+
+/// 文本与 Skill 指纹的风格匹配结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StyleMatch {
+    /// 0–100 的总体相似度
+    pub similarity: f64,
+    /// 各特征偏差，按距离从大到小排列
+    pub deviations: Vec<FeatureDeviation>,
+}
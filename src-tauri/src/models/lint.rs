@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+
+/// 命中规则的严重程度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LintSeverity {
+    /// 禁用词
+    Error,
+    /// 禁用句式
+    Warning,
+    /// 提示性规则
+    Info,
+}
+
+/// 一处规则命中
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LintIssue {
+    /// 起止偏移（UTF-16 码元，与 JS 字符串下标及 Monaco 的 offset 一致），左闭右开
+    pub start: usize,
+    pub end: usize,
+    /// 命中的原文片段
+    pub matched: String,
+    /// 规则所在章节，如 "forbidden_words"
+    pub section: String,
+    /// 规则原文
+    pub rule: String,
+    pub severity: LintSeverity,
+}
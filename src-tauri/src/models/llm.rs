@@ -0,0 +1,98 @@
+use serde::{Deserialize, Serialize};
+
+/// 命名 LLM 配置档案
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LlmProfile {
+    pub id: i64,
+    pub name: String,
+    pub provider: String,
+    pub endpoint: String,
+    pub api_key: String,
+    pub model: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// 创建 / 更新 LLM 配置档案请求（更新时未提供的字段保持不变）
+#[derive(Debug, Deserialize)]
+pub struct LlmProfileRequest {
+    pub name: Option<String>,
+    pub provider: Option<String>,
+    pub endpoint: Option<String>,
+    pub api_key: Option<String>,
+    pub model: Option<String>,
+}
+
+/// 需要调用 LLM 的任务类型，每种任务可单独指定配置档案
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LlmTask {
+    /// 样本风格分析（create_skill_with_samples）
+    AnalyzeStyle,
+    /// 文章生成（generate_article）
+    Generate,
+    /// Diff 分析（analyze_diff）
+    DiffAnalyze,
+    /// Skill 压缩的 LLM 整理（compact_skill）
+    CompactSkill,
+    /// 语义检索的向量化（/embeddings）。未指定档案时不回落到默认配置，而是使用本地哈希向量
+    Embed,
+}
+
+impl LlmTask {
+    pub const ALL: [LlmTask; 5] = [
+        LlmTask::AnalyzeStyle,
+        LlmTask::Generate,
+        LlmTask::DiffAnalyze,
+        LlmTask::CompactSkill,
+        LlmTask::Embed,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LlmTask::AnalyzeStyle => "analyze_style",
+            LlmTask::Generate => "generate",
+            LlmTask::DiffAnalyze => "diff_analyze",
+            LlmTask::CompactSkill => "compact_skill",
+            LlmTask::Embed => "embed",
+        }
+    }
+}
+
+/// 任务路由：profile_id 为空表示使用默认配置（设置页中的 LLM 配置）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskRoute {
+    pub task: LlmTask,
+    pub profile_id: Option<i64>,
+}
+
+/// 提示词中某一段为适应上下文预算所做的调整
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SectionAdjustment {
+    pub section: String,
+    pub tokens_before: usize,
+    pub tokens_after: usize,
+    /// "truncated" 或 "summarized"
+    pub action: String,
+}
+
+/// 发送前的上下文预算检查结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PromptBudgetReport {
+    pub model: String,
+    pub context_window: usize,
+    /// 为模型输出预留的 token 数
+    pub reserved_output: usize,
+    /// 调整后提示词的估算 token 数
+    pub prompt_tokens: usize,
+    /// 调整后是否能放进上下文窗口
+    pub fits: bool,
+    pub adjustments: Vec<SectionAdjustment>,
+}
+
+impl PromptBudgetReport {
+    /// 有内容被截断、被摘要或仍然放不下时需要提醒用户
+    pub fn needs_warning(&self) -> bool {
+        !self.fits || !self.adjustments.is_empty()
+    }
+}
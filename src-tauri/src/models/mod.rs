@@ -1,2 +1,9 @@
 pub mod skill;
+pub mod skill_spec;
 pub mod article;
+pub mod analysis;
+pub mod llm;
+pub mod lint;
+pub mod ai_flavor;
+pub mod fingerprint;
+pub mod embedding;
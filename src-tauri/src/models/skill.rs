@@ -1,3 +1,4 @@
+use crate::models::skill_spec::RuleSection;
 use serde::{Deserialize, Serialize};
 
 /// Skill 主结构
@@ -21,6 +22,8 @@ pub struct SkillVersion {
     pub content_markdown: String,
     pub content_json: String,
     pub change_summary: String,
+    /// 样本的量化文风指纹（StyleFingerprint 的 JSON），没有样本时为空字符串
+    pub fingerprint_json: String,
     pub created_at: String,
 }
 
@@ -34,6 +37,26 @@ pub struct OriginalSample {
     pub created_at: String,
 }
 
+/// Skill 规则及其出处（skill_rule 表）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkillRule {
+    pub id: i64,
+    pub skill_id: i64,
+    pub section: String,
+    pub text: String,
+    pub introduced_version: i64,
+    /// 仍在当前版本中的规则为 None
+    pub removed_version: Option<i64>,
+    pub diff_record_id: Option<i64>,
+    pub original_sample_id: Option<i64>,
+    /// 被后续 Diff 分析重新提取的次数（含首次）
+    pub support_count: i64,
+    /// 被用户修改违背的次数
+    pub contradiction_count: i64,
+    pub confidence: f64,
+    pub created_at: String,
+}
+
 /// 创建 Skill 请求
 #[derive(Debug, Deserialize)]
 pub struct CreateSkillRequest {
@@ -51,3 +74,173 @@ pub struct UpdateSkillRequest {
     pub category: Option<String>,
     pub description: Option<String>,
 }
+
+/// Skill 进化结果
+#[derive(Debug, Clone, Serialize)]
+pub struct EvolutionResult {
+    pub skill_id: i64,
+    /// 进化后的当前版本号（没有新增规则时保持不变）
+    pub version_number: i64,
+    pub created_version: bool,
+    pub change_summary: String,
+    pub added_principles: Vec<String>,
+    pub added_forbidden_words: Vec<String>,
+    pub added_forbidden_patterns: Vec<String>,
+    /// 新版本中检测到的规则冲突，需要用户处理
+    pub conflicts: Vec<RuleConflict>,
+}
+
+/// 进化预览中的候选规则，id 由章节与归一化文本决定，预览与提交之间保持稳定
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CandidateRule {
+    pub id: String,
+    pub section: RuleSection,
+    pub text: String,
+}
+
+/// Skill 进化预览（dry-run），按章节分组列出可逐条批准的候选规则
+#[derive(Debug, Clone, Serialize)]
+pub struct EvolutionPreview {
+    pub diff_record_id: i64,
+    pub skill_id: i64,
+    /// 预览所基于的当前版本号
+    pub base_version: i64,
+    pub summary: String,
+    pub style_principles: Vec<CandidateRule>,
+    pub forbidden_words: Vec<CandidateRule>,
+    pub forbidden_patterns: Vec<CandidateRule>,
+}
+
+/// 两个版本之间某一章节的规则变化
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SectionChanges {
+    pub section: String,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub modified: Vec<ModifiedRule>,
+}
+
+impl SectionChanges {
+    pub fn new(section: &str) -> Self {
+        SectionChanges {
+            section: section.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+/// 被改写的规则（旧措辞 → 新措辞）
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ModifiedRule {
+    pub from: String,
+    pub to: String,
+}
+
+/// 压缩时被合并的近似重复规则
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MergedRule {
+    pub section: String,
+    pub kept: String,
+    pub merged: Vec<String>,
+}
+
+/// 为满足 token 预算而移除的规则
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TrimmedRule {
+    pub section: String,
+    pub text: String,
+}
+
+/// Skill 压缩结果
+#[derive(Debug, Clone, Serialize)]
+pub struct CompactionResult {
+    pub skill_id: i64,
+    /// 压缩后的当前版本号（没有可压缩内容时保持不变）
+    pub version_number: i64,
+    pub created_version: bool,
+    pub change_summary: String,
+    pub tokens_before: usize,
+    pub tokens_after: usize,
+    pub merged: Vec<MergedRule>,
+    pub trimmed: Vec<TrimmedRule>,
+    pub llm_consolidated: bool,
+    pub conflicts: Vec<RuleConflict>,
+}
+
+/// 同一版本中相互矛盾的两条规则
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RuleConflict {
+    /// "forbidden_terminology" | "opposing_principles"
+    pub kind: String,
+    pub section_a: String,
+    pub rule_a: String,
+    pub section_b: String,
+    pub rule_b: String,
+    pub message: String,
+}
+
+/// 从原创样本重新建模的结果
+#[derive(Debug, Clone, Serialize)]
+pub struct ReanalysisResult {
+    pub skill_id: i64,
+    pub version_number: i64,
+    pub sample_count: usize,
+    pub change_summary: String,
+    /// 新版本相对上一版本的规则级变化
+    pub sections: Vec<SectionChanges>,
+    /// 新版本中检测到的规则冲突
+    pub conflicts: Vec<RuleConflict>,
+}
+
+/// 批量导入样本时跳过文件的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    /// 路径不存在
+    NotFound,
+    /// 不是 .md / .txt / .html 文件
+    UnsupportedType,
+    /// 读取失败或不是 UTF-8 编码
+    ReadFailed,
+    /// 去除元数据和标记后没有正文
+    Empty,
+    /// 与已有样本或本次导入的其他文件内容相同
+    Duplicate,
+    /// 目录中以 . 开头的隐藏文件或目录
+    Hidden,
+    /// 指向目录的符号链接，为避免循环不跟随
+    Symlink,
+}
+
+/// 批量导入中被跳过的文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkippedFile {
+    pub path: String,
+    pub reason: SkipReason,
+    /// 可读说明
+    pub detail: String,
+}
+
+/// 批量导入样本的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SampleImportReport {
+    pub skill_id: i64,
+    pub imported: Vec<OriginalSample>,
+    pub skipped: Vec<SkippedFile>,
+}
+
+/// 分批风格分析的进度
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AnalysisProgress {
+    /// "map"：逐批分析样本；"reduce"：合并各批结果
+    pub stage: String,
+    /// 合并轮次，map 阶段为 0
+    pub round: usize,
+    /// 本阶段（本轮）已完成的批次数
+    pub completed: usize,
+    pub total: usize,
+}
@@ -0,0 +1,138 @@
+use serde::{Deserialize, Serialize};
+
+/// Skill 结构化内容（skill_version.content_json），
+/// 结构与 prompts::analyze_style 要求模型输出的 JSON 一致
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SkillSpec {
+    pub role: SkillRole,
+    pub style_principles: Vec<String>,
+    pub blocklist: Blocklist,
+    pub references: References,
+}
+
+/// 可由 Diff 分析自动增删的规则章节
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleSection {
+    StylePrinciples,
+    ForbiddenWords,
+    ForbiddenPatterns,
+}
+
+impl RuleSection {
+    pub const ALL: [RuleSection; 3] = [
+        RuleSection::StylePrinciples,
+        RuleSection::ForbiddenWords,
+        RuleSection::ForbiddenPatterns,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RuleSection::StylePrinciples => "style_principles",
+            RuleSection::ForbiddenWords => "forbidden_words",
+            RuleSection::ForbiddenPatterns => "forbidden_patterns",
+        }
+    }
+}
+
+/// 作者角色定位
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SkillRole {
+    pub identity: String,
+    pub tone: String,
+    #[serde(default)]
+    pub audience: String,
+}
+
+/// 禁止清单
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Blocklist {
+    #[serde(default)]
+    pub forbidden_words: Vec<String>,
+    #[serde(default)]
+    pub forbidden_patterns: Vec<String>,
+    #[serde(default)]
+    pub forbidden_structures: Vec<String>,
+}
+
+/// 参考资料
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct References {
+    #[serde(default)]
+    pub terminology: Vec<String>,
+    #[serde(default)]
+    pub sample_features: Vec<String>,
+}
+
+impl SkillSpec {
+    /// 解析 content_json：空字符串或 "{}" 表示该版本只有 Markdown 内容（返回 None），
+    /// 其余内容必须是完整且通过校验的 SkillSpec
+    pub fn from_content_json(content_json: &str) -> Result<Option<SkillSpec>, String> {
+        let trimmed = content_json.trim();
+        if trimmed.is_empty() || trimmed == "{}" {
+            return Ok(None);
+        }
+
+        let mut spec: SkillSpec =
+            serde_json::from_str(trimmed).map_err(|e| format!("Skill 结构化内容无效: {}", e))?;
+        spec.sanitize();
+        spec.validate()
+            .map_err(|e| format!("Skill 结构化内容无效: {}", e))?;
+        Ok(Some(spec))
+    }
+
+    /// 序列化为规范化的 content_json
+    pub fn to_content_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(self).map_err(|e| e.to_string())
+    }
+
+    /// 指定章节的规则列表
+    pub fn rules(&self, section: RuleSection) -> &Vec<String> {
+        match section {
+            RuleSection::StylePrinciples => &self.style_principles,
+            RuleSection::ForbiddenWords => &self.blocklist.forbidden_words,
+            RuleSection::ForbiddenPatterns => &self.blocklist.forbidden_patterns,
+        }
+    }
+
+    pub fn rules_mut(&mut self, section: RuleSection) -> &mut Vec<String> {
+        match section {
+            RuleSection::StylePrinciples => &mut self.style_principles,
+            RuleSection::ForbiddenWords => &mut self.blocklist.forbidden_words,
+            RuleSection::ForbiddenPatterns => &mut self.blocklist.forbidden_patterns,
+        }
+    }
+
+    /// 去除空白条目及首尾空白
+    pub fn sanitize(&mut self) {
+        self.role.identity = self.role.identity.trim().to_string();
+        self.role.tone = self.role.tone.trim().to_string();
+        self.role.audience = self.role.audience.trim().to_string();
+        for list in [
+            &mut self.style_principles,
+            &mut self.blocklist.forbidden_words,
+            &mut self.blocklist.forbidden_patterns,
+            &mut self.blocklist.forbidden_structures,
+            &mut self.references.terminology,
+            &mut self.references.sample_features,
+        ] {
+            list.retain(|s| !s.trim().is_empty());
+            for s in list.iter_mut() {
+                *s = s.trim().to_string();
+            }
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.role.identity.trim().is_empty() {
+            return Err("role.identity 不能为空".to_string());
+        }
+        if self.role.tone.trim().is_empty() {
+            return Err("role.tone 不能为空".to_string());
+        }
+        if self.style_principles.is_empty() {
+            return Err("style_principles 至少需要一条风格原则".to_string());
+        }
+        Ok(())
+    }
+}
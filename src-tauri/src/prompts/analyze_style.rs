@@ -1,5 +1,8 @@
-/// 初始建模提示词：从原创样本中提取文风 Skill
-pub fn build_analyze_prompt(samples: &[String]) -> String {
+use crate::models::fingerprint::StyleFingerprint;
+use crate::models::skill_spec::SkillSpec;
+
+/// 初始建模提示词：从原创样本中提取文风 Skill，附带本地统计的量化指纹
+pub fn build_analyze_prompt(samples: &[String], fingerprint: &StyleFingerprint) -> String {
     let samples_text = samples
         .iter()
         .enumerate()
@@ -12,6 +15,10 @@ pub fn build_analyze_prompt(samples: &[String]) -> String {
 
 {}
 
+以下是程序对全部样本的客观统计（量化指纹），可作为判断依据：
+
+{}
+
 请按照以下 JSON 格式输出分析结果（不要添加 markdown 代码块标记）：
 
 {{
@@ -40,29 +47,116 @@ pub fn build_analyze_prompt(samples: &[String]) -> String {
 1. 深入阅读每篇样本，找出共性的写作习惯
 2. 风格原则应具体、可执行，不要泛泛而谈
 3. 禁止清单要精确，列出作者显然不会使用的"AI味"词汇和句式
-4. 术语表应反映作者的专业领域用语习惯"#,
-        samples_text
+4. 术语表应反映作者的专业领域用语习惯
+5. 涉及句长、段落长度、标点、中英混排的原则必须与量化指纹一致，尽量引用其中的具体数字"#,
+        samples_text,
+        fingerprint_evidence(fingerprint)
     )
 }
 
-/// 将 JSON 分析结果转为可读的 Markdown 格式
-pub fn json_to_markdown(skill_name: &str, json_content: &str) -> String {
-    format!(
-        r#"# {} — Writing Style Skill
+/// 将量化指纹渲染为提示词中的要点列表
+pub fn fingerprint_evidence(fp: &StyleFingerprint) -> String {
+    let mut lines = vec![
+        format!(
+            "- 样本：{} 篇，共 {} 字",
+            fp.sample_count, fp.char_count
+        ),
+        format!(
+            "- 句长：平均 {} 字，中位数 {}，10%–90% 区间 {}–{}，变异系数 {}",
+            fp.sentence_length.mean,
+            fp.sentence_length.median,
+            fp.sentence_length.p10,
+            fp.sentence_length.p90,
+            fp.sentence_length.cv
+        ),
+        format!(
+            "- 段落长度：平均 {} 字，中位数 {}，共 {} 段",
+            fp.paragraph_length.mean, fp.paragraph_length.median, fp.paragraph_length.count
+        ),
+        format!(
+            "- 标点（每千字）：逗号 {}，句号 {}，问号 {}，感叹号 {}，破折号 {}，省略号 {}，引号 {}；全角标点占比 {}%",
+            fp.punctuation.comma_per_k,
+            fp.punctuation.period_per_k,
+            fp.punctuation.question_per_k,
+            fp.punctuation.exclamation_per_k,
+            fp.punctuation.em_dash_per_k,
+            fp.punctuation.ellipsis_per_k,
+            fp.punctuation.quote_per_k,
+            (fp.punctuation.full_width_ratio * 100.0).round()
+        ),
+        format!(
+            "- 中英混排：中文字符占比 {}%，每千字英文单词 {} 个",
+            (fp.cjk_ratio * 100.0).round(),
+            fp.latin_words_per_k
+        ),
+    ];
+    if !fp.function_words.is_empty() {
+        let mut words: Vec<(&String, &f64)> = fp.function_words.iter().collect();
+        words.sort_by(|a, b| b.1.total_cmp(a.1));
+        let top: Vec<String> = words
+            .iter()
+            .take(10)
+            .map(|(w, n)| format!("{} {}", w, n))
+            .collect();
+        lines.push(format!("- 常用虚词（每千字）：{}", top.join("，")));
+    }
+    lines.join("\n")
+}
 
-## 📋 由 AI 从原创样本中提取
+/// 将结构化 Skill 渲染为可读的 Markdown 格式
+pub fn spec_to_markdown(skill_name: &str, spec: &SkillSpec) -> String {
+    let mut md = format!("# {} — Writing Style Skill\n\n", skill_name);
 
----
+    md.push_str("## 角色定位\n\n");
+    md.push_str(&format!("- **身份**：{}\n", spec.role.identity));
+    md.push_str(&format!("- **语气**：{}\n", spec.role.tone));
+    if !spec.role.audience.is_empty() {
+        md.push_str(&format!("- **读者**：{}\n", spec.role.audience));
+    }
 
-```json
-{}
-```
+    md.push_str("\n## 风格原则\n\n");
+    for (i, principle) in spec.style_principles.iter().enumerate() {
+        md.push_str(&format!("{}. {}\n", i + 1, principle));
+    }
+
+    let blocklist = [
+        ("禁用词", &spec.blocklist.forbidden_words),
+        ("禁用句式", &spec.blocklist.forbidden_patterns),
+        ("禁用结构", &spec.blocklist.forbidden_structures),
+    ];
+    push_list_section(&mut md, "禁止清单", &blocklist);
 
+    let references = [
+        ("术语", &spec.references.terminology),
+        ("样本特征", &spec.references.sample_features),
+    ];
+    push_list_section(&mut md, "参考", &references);
+
+    md.push_str(
+        r#"
 ---
 
 > 使用说明：将此 Skill 作为 System Prompt 的一部分发送给任何 LLM，即可让 AI 模仿你的写作风格。
 > 每次修改 AI 生成的内容后，系统会分析差异并更新此 Skill，使其持续进化。
 "#,
-        skill_name, json_content
-    )
+    );
+    md
+}
+
+/// 渲染带子标题的列表章节，空列表跳过，全部为空时整个章节省略
+fn push_list_section(md: &mut String, title: &str, lists: &[(&str, &Vec<String>)]) {
+    if lists.iter().all(|(_, items)| items.is_empty()) {
+        return;
+    }
+
+    md.push_str(&format!("\n## {}\n", title));
+    for (subtitle, items) in lists {
+        if items.is_empty() {
+            continue;
+        }
+        md.push_str(&format!("\n### {}\n\n", subtitle));
+        for item in items.iter() {
+            md.push_str(&format!("- {}\n", item));
+        }
+    }
 }
@@ -0,0 +1,19 @@
+/// Skill 压缩提示词：在不丢失约束的前提下合并语义重复的规则
+pub fn build_compact_prompt(skill_json: &str, token_budget: usize) -> String {
+    format!(
+        r#"你是一位写作风格规范的编辑。下面是一份经过多轮迭代的 Writing Style Skill（JSON），其中积累了不少语义重复或可以合并的规则。
+
+{}
+
+请整理这份 Skill：
+
+1. 合并表达同一要求的风格原则，保留最具体、可执行的措辞
+2. 合并重复或被其他条目覆盖的禁用词、禁用句式和禁用结构
+3. 不要删除任何独立的约束，不要编造新规则，role 字段保持原意
+4. 规则顺序保持不变：越靠前的规则越可信
+5. 整理后的内容渲染为 Markdown 后应控制在约 {} tokens 以内
+
+按原有 JSON 结构输出完整结果（不要添加 markdown 代码块标记）。"#,
+        skill_json, token_budget
+    )
+}
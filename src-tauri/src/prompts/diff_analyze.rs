@@ -42,6 +42,7 @@ pub fn build_diff_analyze_prompt(
     "add_to_blocklist_patterns": ["应新增的禁用句式"],
     "other_observations": ["其他观察到的风格偏好"]
   }},
+  "contradicted_rules": ["被本次修改违背的现有 Skill 规则，照抄原文"],
   "summary": "一句话总结本次修改对 Skill 的改进方向"
 }}
 
@@ -49,7 +50,9 @@ pub fn build_diff_analyze_prompt(
 1. 关注系统性的偏好，而非一次性的内容修正
 2. 区分"内容性修改"（不影响 Skill）和"风格性修改"（应纳入 Skill）
 3. 新规则应具体可执行，避免笼统描述
-4. 如果修改很少或无风格意义，new_rules 可以为空数组"#,
+4. 如果修改很少或无风格意义，new_rules 可以为空数组
+5. 如果用户的修改与当前 Skill 中某条规则相反（例如重新使用了禁用词），把该规则原文列入 contradicted_rules
+6. 如果修改再次体现了当前 Skill 中已有的规则，照常列入 new_rules，用于累积该规则的可信度"#,
         original, modified, diff_summary, current_skill
     )
 }
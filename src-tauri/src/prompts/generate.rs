@@ -1,5 +1,23 @@
-/// 写作生成提示词：根据 Skill + 主题生成符合风格的文章初稿
-pub fn build_generate_prompt(skill_content: &str, topic: &str) -> String {
+use crate::models::article::SampleExcerpt;
+
+/// 每个示例片段的标题前缀，按片段压缩提示词时以此为分界
+pub const EXCERPT_HEADING: &str = "### 片段";
+
+/// 写作生成提示词：根据 Skill + 主题生成符合风格的文章初稿。
+/// excerpts 为 render_excerpts 渲染的作者原文片段，为空时不加入示例部分
+pub fn build_generate_prompt(skill_content: &str, excerpts: &str, topic: &str) -> String {
+    let (excerpt_section, excerpt_requirement) = if excerpts.trim().is_empty() {
+        (String::new(), "")
+    } else {
+        (
+            format!(
+                "## 作者原文片段\n\n以下摘自作者本人的文章，用来感受真实的语感、句式节奏和用词习惯：\n\n{}\n\n---\n\n",
+                excerpts
+            ),
+            "\n6. 模仿原文片段的语感和节奏，但不要照搬其中的句子和具体内容",
+        )
+    };
+
     format!(
         r#"你是一位专业代笔作家。请严格按照以下 Writing Style Skill 的要求来写作。
 
@@ -9,7 +27,7 @@ pub fn build_generate_prompt(skill_content: &str, topic: &str) -> String {
 
 ---
 
-## 写作任务
+{}## 写作任务
 
 请根据以上风格规范，围绕以下主题撰写一篇文章：
 
@@ -20,9 +38,27 @@ pub fn build_generate_prompt(skill_content: &str, topic: &str) -> String {
 2. 绝对避免禁止清单中的词汇、句式和结构
 3. 使用作者惯用的术语和表达方式
 4. 保持作者的真实声音，不要有"AI味"
-5. 内容要有深度和观点，不要停留在表面
+5. 内容要有深度和观点，不要停留在表面{}
 
 直接输出文章正文，不要添加额外的说明或元信息。"#,
-        skill_content, topic
+        skill_content, excerpt_section, topic, excerpt_requirement
     )
 }
+
+/// 把选出的样本片段渲染为提示词中的示例列表
+pub fn render_excerpts(excerpts: &[SampleExcerpt]) -> String {
+    excerpts
+        .iter()
+        .enumerate()
+        .map(|(i, e)| {
+            format!(
+                "{} {}（摘自《{}》）\n\n{}",
+                EXCERPT_HEADING,
+                i + 1,
+                e.sample_title,
+                e.text
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
@@ -0,0 +1,34 @@
+use crate::models::fingerprint::StyleFingerprint;
+use crate::prompts::analyze_style::fingerprint_evidence;
+
+/// 归并提示词：把分批分析得到的多份 Skill 合并为一份
+pub fn build_merge_prompt(partial_specs: &[String], fingerprint: &StyleFingerprint) -> String {
+    let specs_text = partial_specs
+        .iter()
+        .enumerate()
+        .map(|(i, s)| format!("--- 分析结果 {} ---\n{}", i + 1, s))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    format!(
+        r#"你是一位专业的写作风格分析师。同一位作者的原创样本过多，已分成若干批分别分析，每批得到一份 Writing Style Skill（JSON）：
+
+{}
+
+以下是程序对全部样本的客观统计（量化指纹）：
+
+{}
+
+请把这些分析结果合并为一份完整的 Skill：
+
+1. 多批中反复出现的特征最可信，排在各列表前面；只在一批中出现、且与其他批次矛盾的规则应舍弃
+2. 合并表达同一要求的规则，保留最具体、可执行的措辞
+3. role 字段综合各批的描述，不要简单拼接
+4. 涉及句长、段落长度、标点、中英混排的原则必须与量化指纹一致
+5. 不要编造任何一批中都没有依据的新规则
+
+按与各份分析结果相同的 JSON 结构输出（不要添加 markdown 代码块标记）。"#,
+        specs_text,
+        fingerprint_evidence(fingerprint)
+    )
+}
@@ -1,3 +1,6 @@
 pub mod analyze_style;
+pub mod compact_skill;
 pub mod diff_analyze;
 pub mod generate;
+pub mod merge_specs;
+pub mod repair_json;
@@ -0,0 +1,12 @@
+/// JSON 修复提示词：模型输出未通过解析或校验时，要求其按原结构重新输出
+pub fn build_repair_prompt(error: &str) -> String {
+    format!(
+        r#"你上一次的输出未能通过校验：
+
+{}
+
+请修正上述问题，按原先要求的 JSON 结构重新输出完整结果。
+只输出一个 JSON 对象，不要添加任何解释文字或 markdown 代码块标记。"#,
+        error
+    )
+}
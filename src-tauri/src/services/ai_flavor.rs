@@ -0,0 +1,181 @@
+use crate::models::ai_flavor::AiFlavorScore;
+use crate::models::lint::LintSeverity;
+use crate::services::linter::{self, LintRule};
+use crate::services::stylometry::{self, round_to};
+use std::sync::OnceLock;
+
+/// 内置词典版本；增删词条或调整权重时递增，旧评分据此区分
+pub const DICTIONARY_VERSION: i64 = 1;
+
+/// 词典命中在 lint 结果中的章节名
+pub const AI_PHRASE_SECTION: &str = "ai_phrases";
+
+/// 内置词典中的一条短语。text 的写法与禁用句式相同：
+/// 省略号表示同一句内的任意片段，/.../ 表示正则表达式，其余按禁用词规则整词匹配
+pub struct AiPhrase {
+    pub text: &'static str,
+    pub weight: f64,
+}
+
+const fn phrase(text: &'static str, weight: f64) -> AiPhrase {
+    AiPhrase { text, weight }
+}
+
+/// 模型写作中常见的套话与句式，权重越高越能说明是模型腔
+pub const AI_PHRASES: &[AiPhrase] = &[
+    // 中文
+    phrase("值得注意的是", 1.0),
+    phrase("需要指出的是", 1.0),
+    phrase("总而言之", 1.0),
+    phrase("综上所述", 1.0),
+    phrase("总的来说", 0.8),
+    phrase("一言以蔽之", 0.8),
+    phrase("毋庸置疑", 1.0),
+    phrase("众所周知", 0.8),
+    phrase("不难发现", 0.8),
+    phrase("在当今……时代", 1.0),
+    phrase("随着……的发展", 0.8),
+    phrase("扮演着……角色", 1.0),
+    phrase("开启……新篇章", 1.0),
+    phrase("让我们一起", 1.0),
+    phrase("深入探讨", 1.0),
+    phrase("至关重要", 1.0),
+    phrase("不可或缺", 0.8),
+    phrase("底层逻辑", 0.8),
+    phrase("希望本文", 1.0),
+    phrase("不仅……更是", 0.8),
+    phrase("无论是……还是", 0.6),
+    phrase("赋能", 0.6),
+    phrase("助力", 0.6),
+    // English
+    phrase(r"/\bdelv(e|es|ed|ing) into\b/", 1.0),
+    phrase(r"/\bin today['’]s fast-paced world\b/", 1.0),
+    phrase(
+        r"/\bit['’]s (worth noting|important to note)( that)?\b/",
+        1.0,
+    ),
+    phrase(
+        r"/\bplay(s|ed|ing)? an? (crucial|pivotal|vital) role\b/",
+        1.0,
+    ),
+    phrase(r"/\bembark(s|ed|ing)? on a journey\b/", 1.0),
+    phrase(r"/\bunlock(s|ed|ing)? the (full )?potential\b/", 1.0),
+    phrase(r"/\blet['’]s dive in\b/", 1.0),
+    phrase("navigate the complexities", 1.0),
+    phrase("in the realm of", 1.0),
+    phrase("a testament to", 1.0),
+    phrase("tapestry", 1.0),
+    phrase("in conclusion", 0.8),
+    phrase("ever-evolving", 0.8),
+    phrase("game-changer", 0.8),
+    phrase("not only...but also", 0.6),
+    phrase("seamless", 0.6),
+    phrase("leverage", 0.6),
+    phrase("moreover", 0.4),
+    phrase("furthermore", 0.4),
+];
+
+/// 短语密度达到该值（每千字加权命中数）时短语分拿满
+const PHRASE_SATURATION: f64 = 4.0;
+/// 列表行占比达到该值时列表分拿满
+const LIST_SATURATION: f64 = 0.5;
+/// 加粗密度达到该值（每千字）时加粗分拿满
+const BOLD_SATURATION: f64 = 4.0;
+/// 句长变异系数不高于 UNIFORM_CV 时整齐度分拿满，不低于 VARIED_CV 时为 0
+const UNIFORM_CV: f64 = 0.2;
+const VARIED_CV: f64 = 0.6;
+
+/// 各信号在总分中的占比（合计 100）
+const PHRASE_POINTS: f64 = 60.0;
+const LIST_POINTS: f64 = 15.0;
+const UNIFORMITY_POINTS: f64 = 15.0;
+const BOLD_POINTS: f64 = 10.0;
+
+/// 编译后的内置词典，首次使用时构建
+pub fn dictionary_rules() -> &'static [LintRule] {
+    static RULES: OnceLock<Vec<LintRule>> = OnceLock::new();
+    RULES.get_or_init(|| {
+        AI_PHRASES
+            .iter()
+            .filter_map(|p| {
+                let is_pattern =
+                    p.text.starts_with('/') || ["…", "..."].iter().any(|m| p.text.contains(m));
+                let regex = if is_pattern {
+                    linter::pattern_to_regex(p.text)
+                } else {
+                    linter::word_to_regex(p.text)
+                };
+                regex.map(|regex| {
+                    LintRule::new(AI_PHRASE_SECTION, p.text, LintSeverity::Info, regex)
+                })
+            })
+            .collect()
+    })
+}
+
+/// 根据短语密度与结构信号（列表化、句长整齐、加粗）计算 AI 味评分
+pub fn score(text: &str) -> AiFlavorScore {
+    let hits = linter::lint(text, dictionary_rules());
+    let chars = text.chars().filter(|c| !c.is_whitespace()).count();
+    let per_thousand = |n: f64| {
+        if chars == 0 {
+            0.0
+        } else {
+            n * 1000.0 / chars as f64
+        }
+    };
+
+    let weighted_hits: f64 = hits
+        .iter()
+        .filter_map(|hit| AI_PHRASES.iter().find(|p| p.text == hit.rule))
+        .map(|p| p.weight)
+        .sum();
+    let phrase_density = per_thousand(weighted_hits);
+    let list_line_ratio = list_line_ratio(text);
+    let sentence_length_cv = sentence_length_cv(text);
+    let bold_density = per_thousand(text.matches("**").count() as f64 / 2.0);
+
+    let uniformity = sentence_length_cv
+        .map(|cv| ((VARIED_CV - cv) / (VARIED_CV - UNIFORM_CV)).clamp(0.0, 1.0))
+        .unwrap_or(0.0);
+    let score = PHRASE_POINTS * (phrase_density / PHRASE_SATURATION).min(1.0)
+        + LIST_POINTS * (list_line_ratio / LIST_SATURATION).min(1.0)
+        + UNIFORMITY_POINTS * uniformity
+        + BOLD_POINTS * (bold_density / BOLD_SATURATION).min(1.0);
+
+    AiFlavorScore {
+        score: round_to(score, 10.0),
+        dictionary_version: DICTIONARY_VERSION,
+        phrase_density: round_to(phrase_density, 10.0),
+        list_line_ratio: round_to(list_line_ratio, 100.0),
+        sentence_length_cv,
+        bold_density: round_to(bold_density, 10.0),
+        hits,
+    }
+}
+
+/// 以列表标记开头的行占非空行的比例
+fn list_line_ratio(text: &str) -> f64 {
+    let lines: Vec<&str> = text
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect();
+    if lines.is_empty() {
+        return 0.0;
+    }
+    let is_list = |line: &str| {
+        if ["- ", "* ", "• "].iter().any(|m| line.starts_with(m)) {
+            return true;
+        }
+        let rest = line.trim_start_matches(|c: char| c.is_ascii_digit());
+        rest.len() < line.len() && [". ", "、", ") ", "）"].iter().any(|m| rest.starts_with(m))
+    };
+    lines.iter().filter(|l| is_list(l)).count() as f64 / lines.len() as f64
+}
+
+/// 句长变异系数；不足 3 句时无法说明句式是否整齐
+fn sentence_length_cv(text: &str) -> Option<f64> {
+    let lengths = stylometry::sentence_lengths(text);
+    (lengths.len() >= 3).then(|| stylometry::distribution(&lengths).cv)
+}
@@ -0,0 +1,191 @@
+use crate::models::skill::RuleConflict;
+use crate::models::skill_spec::SkillSpec;
+use crate::services::skill_evolution::normalize_rule;
+
+/// 风格取向的对立维度：提到 positive 中的词记为 +1，提到 negative 中的词记为 -1
+struct StyleAxis {
+    name: &'static str,
+    positive: &'static [&'static str],
+    negative: &'static [&'static str],
+}
+
+const STYLE_AXES: &[StyleAxis] = &[
+    StyleAxis {
+        name: "句子长度",
+        positive: &["短句", "short sentence"],
+        negative: &["长句", "long sentence", "flowing sentence"],
+    },
+    StyleAxis {
+        name: "篇幅详略",
+        positive: &["简洁", "精炼", "concise", "brief"],
+        negative: &["详尽", "详细展开", "detailed", "elaborate"],
+    },
+    StyleAxis {
+        name: "语体",
+        positive: &["口语", "conversational", "casual"],
+        negative: &["书面语", "正式", "formal"],
+    },
+    StyleAxis {
+        name: "人称",
+        positive: &["第一人称", "first person", "first-person"],
+        negative: &["第三人称", "third person", "third-person"],
+    },
+    StyleAxis {
+        name: "表情符号",
+        positive: &["表情", "emoji"],
+        negative: &[],
+    },
+];
+
+/// 紧贴在词语前方时表示否定（“少用长句” 与 “短句为主” 取向一致）
+const ZH_NEGATIONS: &[&str] = &[
+    "不要", "不用", "不写", "避免", "少用", "减少", "禁止", "杜绝", "拒绝",
+];
+
+/// 同上，但只在分句开头才算否定（“特别”“区别”“分别”中的“别”不是）
+const ZH_CLAUSE_NEGATIONS: &[&str] = &["别"];
+
+/// 英文否定词，须是完整单词（“piano”结尾的 no 不算）
+const EN_NEGATIONS: &[&str] = &["avoid", "no", "not", "never", "don't", "less", "fewer"];
+
+/// 否定词与被修饰词之间允许出现的动词或程度词（“避免使用长句”“avoid using long sentences”）
+const NEGATION_FILLERS: &[&str] = &[
+    "使用",
+    "用",
+    "写",
+    "出现",
+    "过多的",
+    "过多",
+    "太多",
+    "太",
+    "过",
+    "using",
+    "use",
+    "writing",
+    "write",
+    "too many",
+    "too",
+    "overly",
+    "any",
+];
+
+/// 否定词与被修饰词之间最多跳过的填充词个数
+const MAX_FILLERS: usize = 2;
+
+/// 检查一个版本的结构化内容是否自相矛盾
+pub fn check_spec(spec: &SkillSpec) -> Vec<RuleConflict> {
+    let mut conflicts = forbidden_terminology_conflicts(spec);
+    conflicts.extend(opposing_principle_conflicts(spec));
+    conflicts
+}
+
+/// 同一个词既被禁用，又出现在术语表中
+fn forbidden_terminology_conflicts(spec: &SkillSpec) -> Vec<RuleConflict> {
+    let mut conflicts = Vec::new();
+    for word in &spec.blocklist.forbidden_words {
+        let key = normalize_rule(word);
+        if key.is_empty() {
+            continue;
+        }
+        for term in &spec.references.terminology {
+            if normalize_rule(term).contains(&key) {
+                conflicts.push(RuleConflict {
+                    kind: "forbidden_terminology".to_string(),
+                    section_a: "forbidden_words".to_string(),
+                    rule_a: word.clone(),
+                    section_b: "terminology".to_string(),
+                    rule_b: term.clone(),
+                    message: format!("“{}”既是禁用词，又出现在术语表中", word),
+                });
+            }
+        }
+    }
+    conflicts
+}
+
+/// 两条风格原则在同一维度上取向相反（如“短句为主”与“多用长句”）
+fn opposing_principle_conflicts(spec: &SkillSpec) -> Vec<RuleConflict> {
+    let mut conflicts = Vec::new();
+    for axis in STYLE_AXES {
+        let stances: Vec<(&String, i8)> = spec
+            .style_principles
+            .iter()
+            .filter_map(|p| stance(p, axis).map(|s| (p, s)))
+            .collect();
+        for (i, (a, stance_a)) in stances.iter().enumerate() {
+            for (b, stance_b) in &stances[i + 1..] {
+                if stance_a != stance_b {
+                    conflicts.push(RuleConflict {
+                        kind: "opposing_principles".to_string(),
+                        section_a: "style_principles".to_string(),
+                        rule_a: (*a).clone(),
+                        section_b: "style_principles".to_string(),
+                        rule_b: (*b).clone(),
+                        message: format!("两条风格原则在“{}”上要求相反", axis.name),
+                    });
+                }
+            }
+        }
+    }
+    conflicts
+}
+
+/// 一条原则在某个维度上的取向；同时出现正反两种取向或未提及时返回 None
+fn stance(principle: &str, axis: &StyleAxis) -> Option<i8> {
+    let text = principle.to_lowercase();
+    let mut result = None;
+    for (terms, base) in [(axis.positive, 1i8), (axis.negative, -1i8)] {
+        for term in terms {
+            for (pos, _) in text.match_indices(term) {
+                let value = if is_negated(&text, pos) { -base } else { base };
+                match result {
+                    None => result = Some(value),
+                    Some(existing) if existing != value => return None,
+                    _ => {}
+                }
+            }
+        }
+    }
+    result
+}
+
+/// 判断 byte 位置 pos 处的词前方是否紧贴否定词（中间最多隔几个填充词）
+fn is_negated(text: &str, pos: usize) -> bool {
+    let mut prefix = text[..pos].trim_end();
+    for _ in 0..=MAX_FILLERS {
+        if ends_with_negation(prefix) {
+            return true;
+        }
+        match NEGATION_FILLERS.iter().find(|f| ends_with_word(prefix, f)) {
+            Some(filler) => prefix = prefix[..prefix.len() - filler.len()].trim_end(),
+            None => return false,
+        }
+    }
+    false
+}
+
+fn ends_with_negation(prefix: &str) -> bool {
+    ZH_NEGATIONS.iter().any(|n| prefix.ends_with(n))
+        || ZH_CLAUSE_NEGATIONS.iter().any(|n| {
+            prefix
+                .strip_suffix(n)
+                .is_some_and(|before| before.trim_end().chars().last().is_none_or(is_clause_break))
+        })
+        || EN_NEGATIONS.iter().any(|n| ends_with_word(prefix, n))
+}
+
+/// text 是否以 word 结尾；英文词要求前面不是字母或数字
+fn ends_with_word(text: &str, word: &str) -> bool {
+    let Some(before) = text.strip_suffix(word) else {
+        return false;
+    };
+    !word.starts_with(|c: char| c.is_ascii_alphabetic())
+        || !before
+            .chars()
+            .last()
+            .is_some_and(|c| c.is_ascii_alphanumeric())
+}
+
+fn is_clause_break(c: char) -> bool {
+    "，。；：、！？,.;:!?\n（(“\"".contains(c)
+}
@@ -0,0 +1,116 @@
+use crate::services::lexical::terms;
+use crate::services::llm_error::LlmError;
+use crate::services::llm_service::{self, LlmConfig, RetryNotice, RetryPolicy};
+use crate::services::skill_evolution::fnv1a;
+use crate::services::tokens::TokenEstimator;
+use std::collections::HashMap;
+
+/// 本地哈希向量的模型标识，算法变化时需要升级版本号使旧向量失效
+pub const LOCAL_MODEL: &str = "local-hash-v1";
+/// 本地哈希向量的维度
+const LOCAL_DIM: usize = 512;
+/// 每次请求 /embeddings 的输入条数
+pub const EMBED_BATCH: usize = 32;
+/// 各家 embeddings 模型的输入上限差别很大（nomic-embed-text 只有 2048），按保守值截断
+const MAX_INPUT_TOKENS: usize = 2000;
+
+/// 向量化方式：为 embed 任务指定了配置档案时调用远端接口，否则完全离线计算
+#[derive(Clone)]
+pub enum Embedder {
+    Remote(LlmConfig),
+    Local,
+}
+
+impl Embedder {
+    pub fn new(config: Option<LlmConfig>) -> Self {
+        config.map_or(Embedder::Local, Embedder::Remote)
+    }
+
+    /// 写入 embedding.model 的标识：只有同一标识下的向量之间才能比较
+    pub fn model_key(&self) -> String {
+        match self {
+            Embedder::Remote(config) => format!("{}:{}", config.provider, config.model),
+            Embedder::Local => LOCAL_MODEL.to_string(),
+        }
+    }
+
+    /// 按输入顺序返回向量，远端调用分批发送并按策略重试
+    pub async fn embed<R>(
+        &self,
+        texts: &[String],
+        policy: &RetryPolicy,
+        mut on_retry: R,
+    ) -> Result<Vec<Vec<f32>>, LlmError>
+    where
+        R: FnMut(&RetryNotice),
+    {
+        let config = match self {
+            Embedder::Local => return Ok(texts.iter().map(|t| hash_embed(t)).collect()),
+            Embedder::Remote(config) => config,
+        };
+        let mut vectors = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(EMBED_BATCH) {
+            let inputs: Vec<String> = batch
+                .iter()
+                .map(|t| TokenEstimator::DEFAULT.truncate(t, MAX_INPUT_TOKENS))
+                .collect();
+            let batch_vectors = llm_service::with_retry(policy, &mut on_retry, || {
+                llm_service::embeddings(config, &inputs)
+            })
+            .await?;
+            vectors.extend(batch_vectors);
+        }
+        Ok(vectors)
+    }
+}
+
+/// 本地哈希向量：按 lexical::terms 切出的词项做带符号的特征哈希，
+/// 词频取对数后 L2 归一化。不理解同义词，但完全离线、无需训练
+pub fn hash_embed(text: &str) -> Vec<f32> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for term in terms(text) {
+        *counts.entry(term).or_default() += 1;
+    }
+
+    let mut vector = vec![0f32; LOCAL_DIM];
+    for (term, count) in counts {
+        let hash = fnv1a(&term);
+        let sign = if (hash >> 63) == 0 { 1.0 } else { -1.0 };
+        vector[(hash % LOCAL_DIM as u64) as usize] += sign * (1.0 + (count as f32).ln());
+    }
+
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|v| *v /= norm);
+    }
+    vector
+}
+
+/// 余弦相似度，维度不一致或任一向量为零时为 0
+pub fn cosine(a: &[f32], b: &[f32]) -> f64 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let (mut dot, mut norm_a, mut norm_b) = (0f64, 0f64, 0f64);
+    for (x, y) in a.iter().zip(b) {
+        dot += (*x as f64) * (*y as f64);
+        norm_a += (*x as f64).powi(2);
+        norm_b += (*y as f64).powi(2);
+    }
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a.sqrt() * norm_b.sqrt())
+}
+
+/// 向量存入 BLOB 的格式：逐个 f32 的小端字节
+pub fn encode_vector(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+pub fn decode_vector(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect()
+}
@@ -0,0 +1,121 @@
+use crate::models::article::{FewShotOptions, SampleExcerpt};
+use crate::models::skill::OriginalSample;
+use crate::prompts::generate::EXCERPT_HEADING;
+use crate::services::lexical::Bm25;
+use crate::services::tokens::TokenEstimator;
+
+/// 短于此字数的段落与后续段落合并成一个片段，单独一两句话体现不出语感
+const MIN_PASSAGE_CHARS: usize = 80;
+/// 同一篇样本最多选出的片段数，避免示例全部来自一篇文章
+const MAX_PER_SAMPLE: usize = 2;
+/// 单个片段至少保留的 token 数，预算再紧也不截成只剩半句
+const MIN_EXCERPT_TOKENS: usize = 60;
+
+/// 把样本正文切成候选片段：按空行分段，跳过标题行，过短的段落与后续段落合并
+pub fn split_passages(content: &str) -> Vec<String> {
+    let mut passages = Vec::new();
+    let mut current = String::new();
+    for paragraph in content.split("\n\n") {
+        let paragraph = paragraph
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .collect::<Vec<_>>()
+            .join("\n");
+        if paragraph.is_empty() {
+            continue;
+        }
+        if !current.is_empty() {
+            current.push_str("\n\n");
+        }
+        current.push_str(&paragraph);
+        if current.chars().count() >= MIN_PASSAGE_CHARS {
+            passages.push(std::mem::take(&mut current));
+        }
+    }
+    if !current.is_empty() {
+        // 结尾剩下的短段落并入上一个片段
+        match passages.last_mut() {
+            Some(last) => {
+                last.push_str("\n\n");
+                last.push_str(&current);
+            }
+            None => passages.push(current),
+        }
+    }
+    passages
+}
+
+/// 按 BM25 相关度为主题挑选样本片段，合计不超过预算。
+/// 主题与样本没有共同词项时按样本顺序挑选，示例的作用主要是语感而非内容
+pub fn select_excerpts(
+    samples: &[OriginalSample],
+    topic: &str,
+    options: &FewShotOptions,
+    estimator: &TokenEstimator,
+) -> Vec<SampleExcerpt> {
+    if options.max_excerpts == 0 || options.token_budget == 0 {
+        return Vec::new();
+    }
+
+    let candidates: Vec<(usize, String)> = samples
+        .iter()
+        .enumerate()
+        .flat_map(|(i, s)| split_passages(&s.content).into_iter().map(move |p| (i, p)))
+        .collect();
+    let texts: Vec<&str> = candidates.iter().map(|(_, p)| p.as_str()).collect();
+    let scores = Bm25::new(&texts).scores(topic);
+
+    let mut order: Vec<usize> = (0..candidates.len()).collect();
+    // 稳定排序：同分时保持样本原有顺序
+    order.sort_by(|&a, &b| scores[b].total_cmp(&scores[a]));
+
+    let per_excerpt = (options.token_budget / options.max_excerpts).max(MIN_EXCERPT_TOKENS);
+    let mut remaining = options.token_budget;
+    let mut per_sample = vec![0usize; samples.len()];
+    let mut excerpts = Vec::new();
+    for i in order {
+        if excerpts.len() >= options.max_excerpts || remaining < MIN_EXCERPT_TOKENS {
+            break;
+        }
+        let (sample_idx, passage) = &candidates[i];
+        if per_sample[*sample_idx] >= MAX_PER_SAMPLE {
+            continue;
+        }
+        let limit = per_excerpt.min(remaining);
+        let text = if estimator.estimate(passage) > limit {
+            format!("{}……", estimator.truncate(passage, limit.saturating_sub(2)))
+        } else {
+            passage.clone()
+        };
+        remaining = remaining.saturating_sub(estimator.estimate(&text));
+        per_sample[*sample_idx] += 1;
+        excerpts.push(SampleExcerpt {
+            sample_id: samples[*sample_idx].id,
+            sample_title: samples[*sample_idx].title.clone(),
+            text,
+            score: scores[i],
+        });
+    }
+    excerpts
+}
+
+/// 提示词超出上下文时的示例压缩方式：从后往前整段去掉片段，不截断单个片段
+pub fn keep_leading_excerpts(rendered: &str, budget: usize, estimator: &TokenEstimator) -> String {
+    let mut kept = String::new();
+    for (i, block) in rendered
+        .split(&format!("\n\n{}", EXCERPT_HEADING))
+        .enumerate()
+    {
+        let block = if i == 0 {
+            block.to_string()
+        } else {
+            format!("\n\n{}{}", EXCERPT_HEADING, block)
+        };
+        if estimator.estimate(&kept) + estimator.estimate(&block) > budget {
+            break;
+        }
+        kept.push_str(&block);
+    }
+    kept
+}
@@ -0,0 +1,211 @@
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+
+/// 任务被取消时返回的错误信息
+pub const JOB_CANCELLED: &str = "任务已取消";
+
+/// 保留最近结束的任务数量（供 list_jobs 展示结果）
+const FINISHED_HISTORY_LIMIT: usize = 50;
+
+/// 任务状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// 任务信息（返回给前端）
+#[derive(Debug, Clone, Serialize)]
+pub struct JobInfo {
+    pub id: u64,
+    pub kind: String,
+    pub label: String,
+    pub status: JobStatus,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+}
+
+/// 取消令牌：可被多处克隆持有，取消后所有等待方立即被唤醒
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    flag: Arc<AtomicBool>,
+    notify: Arc<Notify>,
+}
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.flag.store(true, Ordering::SeqCst);
+        self.notify.notify_waiters();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.flag.load(Ordering::SeqCst)
+    }
+
+    /// 等待直到被取消
+    pub async fn cancelled(&self) {
+        loop {
+            // 先注册等待再检查标记，避免错过 cancel 与 await 之间的通知
+            let notified = self.notify.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+struct JobEntry {
+    info: JobInfo,
+    token: CancelToken,
+}
+
+/// 任务注册表（Tauri 全局状态）：分配任务 ID、跟踪进行中的 LLM 任务并支持取消
+#[derive(Default)]
+pub struct JobRegistry {
+    next_id: AtomicU64,
+    running: Mutex<HashMap<u64, JobEntry>>,
+    finished: Mutex<VecDeque<JobInfo>>,
+}
+
+impl JobRegistry {
+    /// 登记一个新任务，返回的 JobGuard 在离开作用域时自动结束任务
+    pub fn start(&self, kind: &str, label: &str) -> JobGuard<'_> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+        let token = CancelToken::default();
+        let info = JobInfo {
+            id,
+            kind: kind.to_string(),
+            label: label.to_string(),
+            status: JobStatus::Running,
+            started_at: chrono::Local::now().to_rfc3339(),
+            finished_at: None,
+        };
+
+        if let Ok(mut running) = self.running.lock() {
+            running.insert(
+                id,
+                JobEntry {
+                    info: info.clone(),
+                    token: token.clone(),
+                },
+            );
+        }
+
+        JobGuard {
+            registry: self,
+            info,
+            token,
+            status: None,
+        }
+    }
+
+    /// 请求取消任务，任务不存在或已结束时返回 false
+    pub fn cancel(&self, id: u64) -> bool {
+        let running = match self.running.lock() {
+            Ok(r) => r,
+            Err(_) => return false,
+        };
+        match running.get(&id) {
+            Some(entry) => {
+                entry.token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 列出进行中的任务和最近结束的任务（进行中在前）
+    pub fn list(&self) -> Vec<JobInfo> {
+        let mut jobs: Vec<JobInfo> = self
+            .running
+            .lock()
+            .map(|r| r.values().map(|e| e.info.clone()).collect())
+            .unwrap_or_default();
+        jobs.sort_by_key(|j| j.id);
+
+        if let Ok(finished) = self.finished.lock() {
+            jobs.extend(finished.iter().rev().cloned());
+        }
+        jobs
+    }
+
+    fn finish(&self, id: u64, status: JobStatus) {
+        let entry = match self.running.lock() {
+            Ok(mut r) => r.remove(&id),
+            Err(_) => None,
+        };
+        let Some(entry) = entry else { return };
+
+        let mut info = entry.info;
+        info.status = status;
+        info.finished_at = Some(chrono::Local::now().to_rfc3339());
+
+        if let Ok(mut finished) = self.finished.lock() {
+            finished.push_back(info);
+            while finished.len() > FINISHED_HISTORY_LIMIT {
+                finished.pop_front();
+            }
+        }
+    }
+}
+
+/// 进行中任务的句柄
+pub struct JobGuard<'a> {
+    registry: &'a JobRegistry,
+    info: JobInfo,
+    token: CancelToken,
+    status: Option<JobStatus>,
+}
+
+impl JobGuard<'_> {
+    pub fn info(&self) -> &JobInfo {
+        &self.info
+    }
+
+    pub fn token(&self) -> &CancelToken {
+        &self.token
+    }
+
+    /// 显式记录任务结束状态（用于自行处理取消的协作式任务）
+    pub fn set_status(&mut self, status: JobStatus) {
+        self.status = Some(status);
+    }
+
+    /// 运行任务：取消时立即丢弃 future 并返回 JOB_CANCELLED
+    pub async fn run<T, E, F>(&mut self, fut: F) -> Result<T, String>
+    where
+        F: Future<Output = Result<T, E>>,
+        E: Into<String>,
+    {
+        let result = tokio::select! {
+            _ = self.token.cancelled() => Err(JOB_CANCELLED.to_string()),
+            r = fut => r.map_err(Into::into),
+        };
+
+        self.status = Some(match &result {
+            Ok(_) => JobStatus::Completed,
+            Err(_) if self.token.is_cancelled() => JobStatus::Cancelled,
+            Err(_) => JobStatus::Failed,
+        });
+        result
+    }
+}
+
+impl Drop for JobGuard<'_> {
+    fn drop(&mut self) {
+        let status = self.status.unwrap_or(if self.token.is_cancelled() {
+            JobStatus::Cancelled
+        } else {
+            JobStatus::Failed
+        });
+        self.registry.finish(self.info.id, status);
+    }
+}
@@ -0,0 +1,118 @@
+use crate::services::tokens::is_cjk;
+use std::collections::HashMap;
+
+/// BM25 的词频饱和参数
+const BM25_K1: f64 = 1.2;
+/// BM25 的文档长度归一化参数
+const BM25_B: f64 = 0.75;
+
+/// 把文本切成检索用的词项：中日韩文字按相邻二字切分（单字成段时保留单字），
+/// 其他文字按字母数字连续段切成小写单词，标点与空白只作分隔
+pub fn terms(text: &str) -> Vec<String> {
+    let mut terms = Vec::new();
+    let mut cjk_run: Vec<char> = Vec::new();
+    let mut word = String::new();
+
+    let flush_cjk = |run: &mut Vec<char>, terms: &mut Vec<String>| {
+        if run.len() == 1 {
+            terms.push(run[0].to_string());
+        } else {
+            terms.extend(run.windows(2).map(|w| w.iter().collect::<String>()));
+        }
+        run.clear();
+    };
+    let flush_word = |word: &mut String, terms: &mut Vec<String>| {
+        if !word.is_empty() {
+            terms.push(std::mem::take(word));
+        }
+    };
+
+    for c in text.chars() {
+        if is_cjk(c) && c.is_alphanumeric() {
+            flush_word(&mut word, &mut terms);
+            cjk_run.push(c);
+        } else if c.is_alphanumeric() {
+            if !cjk_run.is_empty() {
+                flush_cjk(&mut cjk_run, &mut terms);
+            }
+            word.extend(c.to_lowercase());
+        } else {
+            if !cjk_run.is_empty() {
+                flush_cjk(&mut cjk_run, &mut terms);
+            }
+            flush_word(&mut word, &mut terms);
+        }
+    }
+    if !cjk_run.is_empty() {
+        flush_cjk(&mut cjk_run, &mut terms);
+    }
+    flush_word(&mut word, &mut terms);
+    terms
+}
+
+/// 一组文档上的 BM25 索引
+pub struct Bm25 {
+    term_freqs: Vec<HashMap<String, usize>>,
+    doc_lens: Vec<usize>,
+    avg_len: f64,
+    doc_freq: HashMap<String, usize>,
+}
+
+impl Bm25 {
+    /// 按 terms 切分建立索引
+    pub fn new<S: AsRef<str>>(docs: &[S]) -> Self {
+        let mut term_freqs = Vec::with_capacity(docs.len());
+        let mut doc_lens = Vec::with_capacity(docs.len());
+        let mut doc_freq: HashMap<String, usize> = HashMap::new();
+        for doc in docs {
+            let doc_terms = terms(doc.as_ref());
+            doc_lens.push(doc_terms.len());
+            let mut freqs: HashMap<String, usize> = HashMap::new();
+            for term in doc_terms {
+                *freqs.entry(term).or_default() += 1;
+            }
+            for term in freqs.keys() {
+                *doc_freq.entry(term.clone()).or_default() += 1;
+            }
+            term_freqs.push(freqs);
+        }
+        let avg_len = if doc_lens.is_empty() {
+            0.0
+        } else {
+            doc_lens.iter().sum::<usize>() as f64 / doc_lens.len() as f64
+        };
+        Bm25 {
+            term_freqs,
+            doc_lens,
+            avg_len,
+            doc_freq,
+        }
+    }
+
+    /// 查询与每篇文档的相关度，顺序与建索引时一致；没有共同词项的文档得 0
+    pub fn scores(&self, query: &str) -> Vec<f64> {
+        let mut query_terms = terms(query);
+        query_terms.sort();
+        query_terms.dedup();
+
+        let n = self.term_freqs.len() as f64;
+        (0..self.term_freqs.len())
+            .map(|i| {
+                let norm = if self.avg_len > 0.0 {
+                    1.0 - BM25_B + BM25_B * self.doc_lens[i] as f64 / self.avg_len
+                } else {
+                    1.0
+                };
+                query_terms
+                    .iter()
+                    .filter_map(|term| {
+                        let tf = *self.term_freqs[i].get(term)? as f64;
+                        let df = self.doc_freq[term] as f64;
+                        let idf = ((n - df + 0.5) / (df + 0.5) + 1.0).ln();
+                        Some(idf * tf * (BM25_K1 + 1.0) / (tf + BM25_K1 * norm))
+                    })
+                    .sum()
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,162 @@
+use crate::models::lint::{LintIssue, LintSeverity};
+use crate::models::skill_spec::SkillSpec;
+use regex::Regex;
+
+/// 句式中的省略号占位符最多跨越的字符数（不跨句）
+const WILDCARD_SPAN: usize = 40;
+
+/// 编译后的检查规则
+pub struct LintRule {
+    pub section: String,
+    pub rule: String,
+    pub severity: LintSeverity,
+    regex: Regex,
+}
+
+impl LintRule {
+    pub fn new(section: &str, rule: &str, severity: LintSeverity, regex: Regex) -> Self {
+        LintRule {
+            section: section.to_string(),
+            rule: rule.to_string(),
+            severity,
+            regex,
+        }
+    }
+}
+
+/// 把 Skill 的禁用词和禁用句式编译为检查规则
+pub fn rules_from_spec(spec: &SkillSpec) -> Vec<LintRule> {
+    let words = spec.blocklist.forbidden_words.iter().filter_map(|word| {
+        word_to_regex(word)
+            .map(|regex| LintRule::new("forbidden_words", word, LintSeverity::Error, regex))
+    });
+    let patterns = spec
+        .blocklist
+        .forbidden_patterns
+        .iter()
+        .filter_map(|pattern| {
+            pattern_to_regex(pattern).map(|regex| {
+                LintRule::new("forbidden_patterns", pattern, LintSeverity::Warning, regex)
+            })
+        });
+    words.chain(patterns).collect()
+}
+
+/// 禁用词按字面匹配，忽略大小写；首尾是英文字母或数字时要求单词边界（"AI" 不命中 "AIM"）
+pub fn word_to_regex(word: &str) -> Option<Regex> {
+    let word = word.trim();
+    if word.is_empty() {
+        return None;
+    }
+    let boundary = |c: Option<char>| match c {
+        Some(c) if c.is_ascii_alphanumeric() => r"\b",
+        _ => "",
+    };
+    Regex::new(&format!(
+        "(?i){}{}{}",
+        boundary(word.chars().next()),
+        regex::escape(word),
+        boundary(word.chars().last())
+    ))
+    .ok()
+}
+
+/// 禁用句式：写成 /.../ 时按正则表达式处理；否则按字面匹配，
+/// 其中的省略号（…… / … / ...）和 * 作为同一句内的任意片段
+pub fn pattern_to_regex(pattern: &str) -> Option<Regex> {
+    let pattern = pattern.trim();
+    if pattern.len() > 2 && pattern.starts_with('/') && pattern.ends_with('/') {
+        if let Ok(regex) = Regex::new(&format!("(?i){}", &pattern[1..pattern.len() - 1])) {
+            return Some(regex);
+        }
+    }
+
+    let wildcard = format!("[^。！？!?\\n]{{0,{}}}?", WILDCARD_SPAN);
+    let mut source = String::from("(?i)");
+    let mut literal = String::new();
+    let mut rest = pattern;
+    while let Some(c) = rest.chars().next() {
+        let placeholder = ["……", "…", "...", "*"]
+            .into_iter()
+            .find(|p| rest.starts_with(p));
+        match placeholder {
+            Some(p) => {
+                source.push_str(&regex::escape(&literal));
+                literal.clear();
+                if !source.ends_with(&wildcard) {
+                    source.push_str(&wildcard);
+                }
+                rest = &rest[p.len()..];
+            }
+            None => {
+                literal.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    source.push_str(&regex::escape(&literal));
+
+    // 只剩占位符的句式会匹配任何文本，直接忽略
+    if source
+        .trim_start_matches("(?i)")
+        .replace(&wildcard, "")
+        .is_empty()
+    {
+        return None;
+    }
+    Regex::new(&source).ok()
+}
+
+/// 扫描文本，返回按起始位置排序的命中列表
+pub fn lint(text: &str, rules: &[LintRule]) -> Vec<LintIssue> {
+    let mut hits: Vec<(usize, usize, &LintRule)> = rules
+        .iter()
+        .flat_map(|rule| {
+            rule.regex
+                .find_iter(text)
+                .filter(|m| !m.is_empty())
+                .map(move |m| (m.start(), m.end(), rule))
+        })
+        .collect();
+    hits.sort_by_key(|(start, end, _)| (*start, *end));
+
+    // 字节偏移按顺序转换为 UTF-16 偏移
+    let mut cursor = Utf16Cursor::new(text);
+    hits.into_iter()
+        .map(|(start, end, rule)| LintIssue {
+            start: cursor.offset_at(start),
+            end: cursor.offset_at(end),
+            matched: text[start..end].to_string(),
+            section: rule.section.clone(),
+            rule: rule.rule.clone(),
+            severity: rule.severity,
+        })
+        .collect()
+}
+
+/// 字节偏移 → UTF-16 偏移，查询位置单调递增时整体为线性复杂度
+struct Utf16Cursor<'a> {
+    text: &'a str,
+    byte: usize,
+    utf16: usize,
+}
+
+impl<'a> Utf16Cursor<'a> {
+    fn new(text: &'a str) -> Self {
+        Utf16Cursor {
+            text,
+            byte: 0,
+            utf16: 0,
+        }
+    }
+
+    fn offset_at(&mut self, byte: usize) -> usize {
+        if byte < self.byte {
+            self.byte = 0;
+            self.utf16 = 0;
+        }
+        self.utf16 += self.text[self.byte..byte].encode_utf16().count();
+        self.byte = byte;
+        self.utf16
+    }
+}
@@ -0,0 +1,162 @@
+use serde::Serialize;
+use std::fmt;
+use std::time::Duration;
+
+/// LLM 调用错误分类
+/// 序列化为 { "kind": "...", ... }，前端可据此区分"API Key 无效"和"服务过载重试中"
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LlmError {
+    /// 客户端配置错误（如 HTTP 客户端创建失败）
+    Config { message: String },
+    /// 网络不可达、连接被重置等
+    Network { message: String },
+    /// 请求超时（含 408）
+    Timeout { message: String },
+    /// 401 / 403：API Key 无效或无权限
+    Auth { status: u16, message: String },
+    /// 429：触发限流，可能附带 Retry-After
+    RateLimited {
+        retry_after_secs: Option<u64>,
+        message: String,
+    },
+    /// 5xx / 529：服务端错误或过载
+    Server {
+        status: u16,
+        retry_after_secs: Option<u64>,
+        message: String,
+    },
+    /// 其它 4xx：请求参数有误（模型名错误、上下文超长等）
+    BadRequest { status: u16, message: String },
+    /// 响应无法解析或内容为空
+    InvalidResponse { message: String },
+}
+
+impl LlmError {
+    /// 按 HTTP 状态码分类
+    pub fn from_status(status: u16, body: String, retry_after: Option<Duration>) -> Self {
+        let retry_after_secs = retry_after.map(|d| d.as_secs());
+        match status {
+            401 | 403 => LlmError::Auth {
+                status,
+                message: body,
+            },
+            408 => LlmError::Timeout { message: body },
+            429 => LlmError::RateLimited {
+                retry_after_secs,
+                message: body,
+            },
+            500..=599 => LlmError::Server {
+                status,
+                retry_after_secs,
+                message: body,
+            },
+            _ => LlmError::BadRequest {
+                status,
+                message: body,
+            },
+        }
+    }
+
+    /// 错误类别标识
+    pub fn kind(&self) -> &'static str {
+        match self {
+            LlmError::Config { .. } => "config",
+            LlmError::Network { .. } => "network",
+            LlmError::Timeout { .. } => "timeout",
+            LlmError::Auth { .. } => "auth",
+            LlmError::RateLimited { .. } => "rate_limited",
+            LlmError::Server { .. } => "server",
+            LlmError::BadRequest { .. } => "bad_request",
+            LlmError::InvalidResponse { .. } => "invalid_response",
+        }
+    }
+
+    /// 是否值得重试：网络抖动、超时、限流、服务端错误
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            LlmError::Network { .. }
+                | LlmError::Timeout { .. }
+                | LlmError::RateLimited { .. }
+                | LlmError::Server { .. }
+        )
+    }
+
+    /// 是否是连不上服务（网络不可达或超时），而不是服务端拒绝了请求
+    pub fn is_unreachable(&self) -> bool {
+        matches!(self, LlmError::Network { .. } | LlmError::Timeout { .. })
+    }
+
+    /// 服务端通过 Retry-After 要求的等待时间
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            LlmError::RateLimited {
+                retry_after_secs, ..
+            }
+            | LlmError::Server {
+                retry_after_secs, ..
+            } => retry_after_secs.map(Duration::from_secs),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for LlmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // 以 [kind] 开头，前端拿到字符串错误时也能识别类别
+        write!(f, "[{}] ", self.kind())?;
+        match self {
+            LlmError::Config { message } => write!(f, "HTTP 客户端创建失败: {}", message),
+            LlmError::Network { message } => write!(f, "请求发送失败: {}", message),
+            LlmError::Timeout { message } => write!(f, "请求超时: {}", message),
+            LlmError::Auth { status, message } => {
+                write!(f, "API Key 无效或无权限 ({}): {}", status, message)
+            }
+            LlmError::RateLimited { message, .. } => {
+                write!(f, "请求过于频繁，已被限流 (429): {}", message)
+            }
+            LlmError::Server {
+                status, message, ..
+            } => write!(f, "LLM 服务暂时不可用 ({}): {}", status, message),
+            LlmError::BadRequest { status, message } => {
+                write!(f, "LLM API 错误 ({}): {}", status, message)
+            }
+            LlmError::InvalidResponse { message } => write!(f, "响应解析失败: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for LlmError {}
+
+impl From<reqwest::Error> for LlmError {
+    fn from(e: reqwest::Error) -> Self {
+        let message = e.to_string();
+        if e.is_timeout() {
+            LlmError::Timeout { message }
+        } else if e.is_decode() {
+            LlmError::InvalidResponse { message }
+        } else if e.is_builder() {
+            LlmError::Config { message }
+        } else {
+            LlmError::Network { message }
+        }
+    }
+}
+
+impl From<LlmError> for String {
+    fn from(e: LlmError) -> Self {
+        e.to_string()
+    }
+}
+
+/// 解析 Retry-After 响应头（秒数或 HTTP 日期）
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let secs = (date.timestamp() - chrono::Utc::now().timestamp()).max(0);
+    Some(Duration::from_secs(secs as u64))
+}
@@ -1,4 +1,8 @@
+use crate::services::job_registry::CancelToken;
+use crate::services::llm_error::{parse_retry_after, LlmError};
 use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::time::Duration;
 
 /// LLM Provider 枚举
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,6 +34,14 @@ impl LlmProvider {
             _ => LlmProvider::Custom,
         }
     }
+
+    /// 是否支持 response_format JSON 模式（Anthropic 与未知的自定义端点不发送该字段）
+    pub fn supports_json_mode(&self) -> bool {
+        matches!(
+            self,
+            LlmProvider::OpenAI | LlmProvider::DeepSeek | LlmProvider::Ollama
+        )
+    }
 }
 
 /// LLM 配置
@@ -54,6 +66,17 @@ struct ChatRequest {
     model: String,
     messages: Vec<ChatMessage>,
     temperature: f64,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<ResponseFormat>,
+}
+
+/// JSON 模式：{"type": "json_object"}
+#[derive(Debug, Serialize)]
+struct ResponseFormat {
+    #[serde(rename = "type")]
+    format_type: &'static str,
 }
 
 /// Chat 响应体
@@ -72,69 +95,647 @@ struct ChatResponseMessage {
     content: String,
 }
 
-/// 调用 LLM API（统一 OpenAI 兼容格式）
+/// Anthropic Messages API 版本号
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// Anthropic 要求必填 max_tokens，取一个足够容纳长文的上限
+const ANTHROPIC_MAX_TOKENS: u32 = 8192;
+
+/// Claude 3 初代模型（opus / sonnet / haiku）输出上限仅为 4096
+const ANTHROPIC_LEGACY_MAX_TOKENS: u32 = 4096;
+
+/// 按模型选择 max_tokens，超出模型输出上限会被接口以 400 拒绝
+pub(crate) fn anthropic_max_tokens(model: &str) -> u32 {
+    let model = model.to_lowercase();
+    let legacy = ["claude-3-opus", "claude-3-sonnet", "claude-3-haiku"]
+        .iter()
+        .any(|prefix| model.starts_with(prefix));
+    if legacy {
+        ANTHROPIC_LEGACY_MAX_TOKENS
+    } else {
+        ANTHROPIC_MAX_TOKENS
+    }
+}
+
+/// Messages 请求体（Anthropic 原生格式）
+#[derive(Debug, Serialize)]
+struct MessagesRequest {
+    model: String,
+    max_tokens: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system: Option<String>,
+    messages: Vec<ChatMessage>,
+    temperature: f64,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+}
+
+/// Messages 响应体：content 为内容块数组
+#[derive(Debug, Deserialize)]
+struct MessagesResponse {
+    content: Vec<ContentBlock>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ContentBlock {
+    #[serde(rename = "type")]
+    block_type: String,
+    #[serde(default)]
+    text: String,
+}
+
+/// 调用 LLM API（按 Provider 选择 OpenAI 兼容格式或 Anthropic 原生格式）
 pub async fn chat_completion(
     config: &LlmConfig,
     messages: Vec<ChatMessage>,
     temperature: f64,
-) -> Result<String, String> {
+) -> Result<String, LlmError> {
+    complete(config, messages, temperature, false).await
+}
+
+/// 要求模型输出 JSON 对象：Provider 支持时开启 response_format JSON 模式
+pub async fn chat_completion_json(
+    config: &LlmConfig,
+    messages: Vec<ChatMessage>,
+    temperature: f64,
+) -> Result<String, LlmError> {
+    complete(config, messages, temperature, true).await
+}
+
+async fn complete(
+    config: &LlmConfig,
+    messages: Vec<ChatMessage>,
+    temperature: f64,
+    json_mode: bool,
+) -> Result<String, LlmError> {
     let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(120))
+        .timeout(Duration::from_secs(120))
         .build()
-        .map_err(|e| format!("HTTP 客户端创建失败: {}", e))?;
+        .map_err(|e| LlmError::Config {
+            message: e.to_string(),
+        })?;
 
+    let provider = LlmProvider::from_str(&config.provider);
+    match provider {
+        LlmProvider::Claude => anthropic_completion(&client, config, messages, temperature).await,
+        _ => {
+            let json_mode = json_mode && provider.supports_json_mode();
+            openai_completion(&client, config, messages, temperature, json_mode).await
+        }
+    }
+}
+
+/// OpenAI 兼容接口：POST {endpoint}/chat/completions
+async fn openai_completion(
+    client: &reqwest::Client,
+    config: &LlmConfig,
+    messages: Vec<ChatMessage>,
+    temperature: f64,
+    json_mode: bool,
+) -> Result<String, LlmError> {
     let endpoint = format!("{}/chat/completions", config.endpoint.trim_end_matches('/'));
 
     let request_body = ChatRequest {
         model: config.model.clone(),
         messages,
         temperature,
+        stream: false,
+        response_format: json_mode.then_some(ResponseFormat {
+            format_type: "json_object",
+        }),
     };
 
+    let req = openai_request(client, config, &endpoint, &request_body);
+    let response = send_request(req).await?;
+
+    let chat_response: ChatResponse = response.json().await.map_err(invalid_response)?;
+
+    chat_response
+        .choices
+        .first()
+        .map(|c| c.message.content.clone())
+        .ok_or_else(empty_response)
+}
+
+/// Anthropic 原生接口：POST {endpoint}/messages
+async fn anthropic_completion(
+    client: &reqwest::Client,
+    config: &LlmConfig,
+    messages: Vec<ChatMessage>,
+    temperature: f64,
+) -> Result<String, LlmError> {
+    let endpoint = format!("{}/messages", config.endpoint.trim_end_matches('/'));
+
+    let (system, messages) = split_system_messages(messages);
+    let request_body = MessagesRequest {
+        model: config.model.clone(),
+        max_tokens: anthropic_max_tokens(&config.model),
+        system,
+        messages,
+        temperature,
+        stream: false,
+    };
+
+    let req = anthropic_request(client, config, &endpoint, &request_body);
+    let response = send_request(req).await?;
+
+    let messages_response: MessagesResponse = response.json().await.map_err(invalid_response)?;
+
+    // 只拼接文本块，忽略 tool_use / thinking 等其它类型
+    let text = messages_response
+        .content
+        .iter()
+        .filter(|b| b.block_type == "text")
+        .map(|b| b.text.as_str())
+        .collect::<String>();
+
+    if text.is_empty() {
+        return Err(empty_response());
+    }
+    Ok(text)
+}
+
+/// 构造 OpenAI 兼容请求（Bearer 认证）
+fn openai_request(
+    client: &reqwest::Client,
+    config: &LlmConfig,
+    endpoint: &str,
+    body: &impl Serialize,
+) -> reqwest::RequestBuilder {
     let mut req = client
-        .post(&endpoint)
+        .post(endpoint)
         .header("Content-Type", "application/json")
-        .json(&request_body);
+        .json(body);
 
     // 添加认证头
     if !config.api_key.is_empty() {
-        let provider = LlmProvider::from_str(&config.provider);
-        match provider {
-            LlmProvider::Claude => {
-                req = req.header("x-api-key", &config.api_key);
-                req = req.header("anthropic-version", "2023-06-01");
+        req = req.header("Authorization", format!("Bearer {}", config.api_key));
+    }
+    req
+}
+
+/// 构造 Anthropic 请求（x-api-key 认证）
+fn anthropic_request(
+    client: &reqwest::Client,
+    config: &LlmConfig,
+    endpoint: &str,
+    body: &MessagesRequest,
+) -> reqwest::RequestBuilder {
+    let mut req = client
+        .post(endpoint)
+        .header("Content-Type", "application/json")
+        .header("anthropic-version", ANTHROPIC_VERSION)
+        .json(body);
+
+    if !config.api_key.is_empty() {
+        req = req.header("x-api-key", &config.api_key);
+    }
+    req
+}
+
+/// Anthropic 不接受 role = system 的消息，需要提取到顶层 system 字段
+fn split_system_messages(messages: Vec<ChatMessage>) -> (Option<String>, Vec<ChatMessage>) {
+    let (system, rest): (Vec<ChatMessage>, Vec<ChatMessage>) =
+        messages.into_iter().partition(|m| m.role == "system");
+
+    let system = system
+        .into_iter()
+        .map(|m| m.content)
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    ((!system.is_empty()).then_some(system), rest)
+}
+
+/// 发送请求并按 HTTP 状态码分类错误
+async fn send_request(req: reqwest::RequestBuilder) -> Result<reqwest::Response, LlmError> {
+    let response = req.send().await?;
+
+    if !response.status().is_success() {
+        let status = response.status().as_u16();
+        let retry_after = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(parse_retry_after);
+        let body = response.text().await.unwrap_or_default();
+        return Err(LlmError::from_status(status, body, retry_after));
+    }
+
+    Ok(response)
+}
+
+fn invalid_response(e: impl std::fmt::Display) -> LlmError {
+    LlmError::InvalidResponse {
+        message: e.to_string(),
+    }
+}
+
+fn empty_response() -> LlmError {
+    invalid_response("LLM 返回空响应")
+}
+
+/// 流式调用结果
+#[derive(Debug, Clone)]
+pub struct StreamOutcome {
+    /// 已接收的完整文本（取消时为已生成的部分）
+    pub content: String,
+    /// 是否因取消而提前结束
+    pub cancelled: bool,
+}
+
+/// 流式调用 LLM API（SSE，stream: true）
+/// 每收到一段增量文本就回调 on_delta；cancel 被触发时立即停止接收并返回已生成的部分
+/// 流式输出不自动重试，避免已推送给前端的内容被重复输出
+pub async fn chat_completion_stream<F>(
+    config: &LlmConfig,
+    messages: Vec<ChatMessage>,
+    temperature: f64,
+    cancel: &CancelToken,
+    mut on_delta: F,
+) -> Result<StreamOutcome, LlmError>
+where
+    F: FnMut(&str),
+{
+    // 流式输出总时长不可预期，只限制连接和两次数据块之间的间隔
+    let client = reqwest::Client::builder()
+        .connect_timeout(Duration::from_secs(30))
+        .read_timeout(Duration::from_secs(120))
+        .build()
+        .map_err(|e| LlmError::Config {
+            message: e.to_string(),
+        })?;
+
+    let provider = LlmProvider::from_str(&config.provider);
+    let base = config.endpoint.trim_end_matches('/');
+    let req = match provider {
+        LlmProvider::Claude => {
+            let (system, messages) = split_system_messages(messages);
+            let body = MessagesRequest {
+                model: config.model.clone(),
+                max_tokens: anthropic_max_tokens(&config.model),
+                system,
+                messages,
+                temperature,
+                stream: true,
+            };
+            anthropic_request(&client, config, &format!("{}/messages", base), &body)
+        }
+        _ => {
+            let body = ChatRequest {
+                model: config.model.clone(),
+                messages,
+                temperature,
+                stream: true,
+                response_format: None,
+            };
+            openai_request(
+                &client,
+                config,
+                &format!("{}/chat/completions", base),
+                &body,
+            )
+        }
+    };
+
+    let mut response = tokio::select! {
+        _ = cancel.cancelled() => {
+            return Ok(StreamOutcome {
+                content: String::new(),
+                cancelled: true,
+            });
+        }
+        r = send_request(req.header("Accept", "text/event-stream")) => r?,
+    };
+
+    let mut parser = SseParser::default();
+    let mut content = String::new();
+
+    loop {
+        let chunk = tokio::select! {
+            _ = cancel.cancelled() => {
+                return Ok(StreamOutcome {
+                    content,
+                    cancelled: true,
+                });
             }
-            _ => {
-                req = req.header("Authorization", format!("Bearer {}", config.api_key));
+            chunk = response.chunk() => chunk?,
+        };
+        let Some(chunk) = chunk else { break };
+
+        for event in parser.push(&chunk) {
+            let delta = match provider {
+                LlmProvider::Claude => parse_anthropic_event(&event)?,
+                _ => parse_openai_event(&event)?,
+            };
+            match delta {
+                StreamDelta::Text(text) => {
+                    if !text.is_empty() {
+                        on_delta(&text);
+                        content.push_str(&text);
+                    }
+                }
+                StreamDelta::Skip => {}
+                StreamDelta::Done => {
+                    return Ok(StreamOutcome {
+                        content,
+                        cancelled: false,
+                    })
+                }
             }
         }
     }
 
-    let response = req
-        .send()
-        .await
-        .map_err(|e| format!("请求发送失败: {}", e))?;
+    if content.is_empty() {
+        return Err(empty_response());
+    }
+    Ok(StreamOutcome {
+        content,
+        cancelled: false,
+    })
+}
 
-    if !response.status().is_success() {
-        let status = response.status();
-        let body = response.text().await.unwrap_or_default();
-        return Err(format!("LLM API 错误 ({}): {}", status, body));
+/// 一条 SSE 事件
+#[derive(Debug, Default)]
+struct SseEvent {
+    event: Option<String>,
+    data: String,
+}
+
+/// SSE 增量解析器：数据块可能在任意字节处被切断，需缓冲到空行再解析
+#[derive(Debug, Default)]
+struct SseParser {
+    buffer: Vec<u8>,
+}
+
+impl SseParser {
+    fn push(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        self.buffer.extend_from_slice(chunk);
+        let mut events = Vec::new();
+
+        while let Some((end, sep_len)) = find_event_boundary(&self.buffer) {
+            let raw: Vec<u8> = self.buffer.drain(..end + sep_len).collect();
+            let text = String::from_utf8_lossy(&raw[..end]);
+
+            let mut event = SseEvent::default();
+            let mut data_lines = Vec::new();
+            for line in text.lines() {
+                if let Some(value) = line.strip_prefix("data:") {
+                    data_lines.push(value.strip_prefix(' ').unwrap_or(value));
+                } else if let Some(value) = line.strip_prefix("event:") {
+                    event.event = Some(value.trim().to_string());
+                }
+            }
+            if data_lines.is_empty() {
+                continue;
+            }
+            event.data = data_lines.join("\n");
+            events.push(event);
+        }
+
+        events
+    }
+}
+
+/// 查找事件分隔空行（兼容 \n\n 与 \r\n\r\n），返回 (事件结束位置, 分隔符长度)
+fn find_event_boundary(buf: &[u8]) -> Option<(usize, usize)> {
+    let lf = buf.windows(2).position(|w| w == b"\n\n").map(|p| (p, 2));
+    let crlf = buf
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .map(|p| (p, 4));
+    match (lf, crlf) {
+        (Some(a), Some(b)) => Some(if a.0 <= b.0 { a } else { b }),
+        (a, b) => a.or(b),
+    }
+}
+
+/// 单个 SSE 事件解析出的增量
+#[derive(Debug)]
+enum StreamDelta {
+    Text(String),
+    Skip,
+    Done,
+}
+
+/// OpenAI 兼容流：data: {"choices":[{"delta":{"content":"..."}}]}，以 [DONE] 结束
+fn parse_openai_event(event: &SseEvent) -> Result<StreamDelta, LlmError> {
+    if event.data.trim() == "[DONE]" {
+        return Ok(StreamDelta::Done);
+    }
+
+    let value: serde_json::Value = serde_json::from_str(&event.data).map_err(invalid_response)?;
+
+    if let Some(error) = value.get("error") {
+        return Err(stream_error(error));
+    }
+
+    let text = value["choices"][0]["delta"]["content"]
+        .as_str()
+        .unwrap_or_default();
+    Ok(StreamDelta::Text(text.to_string()))
+}
+
+/// Anthropic 流：content_block_delta 携带 text_delta，message_stop 表示结束
+fn parse_anthropic_event(event: &SseEvent) -> Result<StreamDelta, LlmError> {
+    let value: serde_json::Value = serde_json::from_str(&event.data).map_err(invalid_response)?;
+
+    let event_type = event
+        .event
+        .as_deref()
+        .or_else(|| value["type"].as_str())
+        .unwrap_or_default();
+
+    match event_type {
+        "content_block_delta" if value["delta"]["type"] == "text_delta" => Ok(StreamDelta::Text(
+            value["delta"]["text"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+        )),
+        "message_stop" => Ok(StreamDelta::Done),
+        "error" => Err(stream_error(&value["error"])),
+        _ => Ok(StreamDelta::Skip),
+    }
+}
+
+/// 流内错误事件：HTTP 状态已是 200，按错误类型归类
+fn stream_error(error: &serde_json::Value) -> LlmError {
+    let message = error.to_string();
+    let error_type = error["type"].as_str().unwrap_or_default();
+    match error_type {
+        "overloaded_error" | "api_error" | "server_error" => LlmError::Server {
+            status: 529,
+            retry_after_secs: None,
+            message,
+        },
+        "rate_limit_error" | "rate_limit_exceeded" => LlmError::RateLimited {
+            retry_after_secs: None,
+            message,
+        },
+        "authentication_error" | "permission_error" => LlmError::Auth {
+            status: 401,
+            message,
+        },
+        _ => LlmError::BadRequest {
+            status: 400,
+            message,
+        },
+    }
+}
+
+/// 重试策略：仅对可重试错误（网络、超时、限流、5xx）生效
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// 首次失败后的最大重试次数
+    pub max_retries: u32,
+    /// 第一次重试前的等待时间
+    pub initial_delay: Duration,
+    /// 单次等待上限（同样约束 Retry-After）
+    pub max_delay: Duration,
+    /// 指数退避倍数
+    pub multiplier: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 3,
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(30),
+            multiplier: 2.0,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// 第 attempt 次重试（从 1 开始）前的等待时间，优先使用服务端的 Retry-After
+    pub fn delay_for(&self, attempt: u32, error: &LlmError) -> Duration {
+        let delay = error.retry_after().unwrap_or_else(|| {
+            let factor = self.multiplier.powi(attempt.saturating_sub(1) as i32);
+            self.initial_delay.mul_f64(factor)
+        });
+        delay.min(self.max_delay)
+    }
+}
+
+/// 即将重试的通知
+#[derive(Debug, Clone, Serialize)]
+pub struct RetryNotice {
+    /// 第几次重试（从 1 开始）
+    pub attempt: u32,
+    pub max_retries: u32,
+    pub delay_ms: u64,
+    pub error: LlmError,
+}
+
+/// 按重试策略执行 op，每次重试前回调 on_retry
+pub async fn with_retry<T, F, Fut, N>(
+    policy: &RetryPolicy,
+    mut on_retry: N,
+    mut op: F,
+) -> Result<T, LlmError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, LlmError>>,
+    N: FnMut(&RetryNotice),
+{
+    let mut attempt = 0;
+    loop {
+        match op().await {
+            Ok(value) => return Ok(value),
+            Err(error) if error.is_retryable() && attempt < policy.max_retries => {
+                attempt += 1;
+                let delay = policy.delay_for(attempt, &error);
+                on_retry(&RetryNotice {
+                    attempt,
+                    max_retries: policy.max_retries,
+                    delay_ms: delay.as_millis() as u64,
+                    error,
+                });
+                tokio::time::sleep(delay).await;
+            }
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+/// 带重试的 chat_completion
+pub async fn chat_completion_with_retry<N>(
+    config: &LlmConfig,
+    messages: Vec<ChatMessage>,
+    temperature: f64,
+    policy: &RetryPolicy,
+    on_retry: N,
+) -> Result<String, LlmError>
+where
+    N: FnMut(&RetryNotice),
+{
+    with_retry(policy, on_retry, || {
+        chat_completion(config, messages.clone(), temperature)
+    })
+    .await
+}
+
+/// Embeddings 请求体（OpenAI 兼容格式，Ollama 的 /v1/embeddings 同样适用）
+#[derive(Debug, Serialize)]
+struct EmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+/// Embeddings 响应体
+#[derive(Debug, Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingData {
+    embedding: Vec<f32>,
+    #[serde(default)]
+    index: usize,
+}
+
+/// 调用 POST {endpoint}/embeddings，按输入顺序返回向量（Anthropic 没有 embeddings 接口）
+pub async fn embeddings(config: &LlmConfig, inputs: &[String]) -> Result<Vec<Vec<f32>>, LlmError> {
+    if matches!(LlmProvider::from_str(&config.provider), LlmProvider::Claude) {
+        return Err(LlmError::Config {
+            message: "Anthropic 不提供 embeddings 接口，请为向量化任务选择其他配置档案".to_string(),
+        });
     }
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(120))
+        .build()
+        .map_err(|e| LlmError::Config {
+            message: e.to_string(),
+        })?;
 
-    let chat_response: ChatResponse = response
-        .json()
+    let endpoint = format!("{}/embeddings", config.endpoint.trim_end_matches('/'));
+    let body = EmbeddingRequest {
+        model: &config.model,
+        input: inputs,
+    };
+    let response = send_request(openai_request(&client, config, &endpoint, &body)).await?;
+    let mut data = response
+        .json::<EmbeddingResponse>()
         .await
-        .map_err(|e| format!("响应解析失败: {}", e))?;
+        .map_err(invalid_response)?
+        .data;
 
-    chat_response
-        .choices
-        .first()
-        .map(|c| c.message.content.clone())
-        .ok_or_else(|| "LLM 返回空响应".to_string())
+    if data.len() != inputs.len() {
+        return Err(invalid_response(format!(
+            "embeddings 返回 {} 个向量，应为 {} 个",
+            data.len(),
+            inputs.len()
+        )));
+    }
+    data.sort_by_key(|d| d.index);
+    Ok(data.into_iter().map(|d| d.embedding).collect())
 }
 
 /// 测试连接（发送简单请求验证配置）
-pub async fn test_connection(config: &LlmConfig) -> Result<String, String> {
+pub async fn test_connection(config: &LlmConfig) -> Result<String, LlmError> {
     let messages = vec![ChatMessage {
         role: "user".to_string(),
         content: "Hello, respond with just 'OK' to confirm connection.".to_string(),
@@ -1 +1,19 @@
+pub mod ai_flavor;
+pub mod consistency;
+pub mod embedding;
+pub mod few_shot;
+pub mod job_registry;
+pub mod lexical;
+pub mod linter;
+pub mod llm_error;
 pub mod llm_service;
+pub mod prompt_budget;
+pub mod sample_import;
+pub mod skill_compaction;
+pub mod skill_diff;
+pub mod skill_evolution;
+pub mod structured_output;
+pub mod style_analysis;
+pub mod style_match;
+pub mod stylometry;
+pub mod tokens;
@@ -0,0 +1,140 @@
+use crate::models::llm::{PromptBudgetReport, SectionAdjustment};
+use crate::services::llm_service::LlmConfig;
+use crate::services::tokens::TokenEstimator;
+
+/// 默认为模型输出预留的 token 数（结构化 JSON 或一篇文章）
+pub const DEFAULT_OUTPUT_RESERVE: usize = 2048;
+
+/// 为输出预留的 token 数最多占上下文窗口的比例（小窗口的本地模型不至于没有输入空间）
+const MAX_OUTPUT_SHARE: usize = 4;
+
+/// 超出预算时如何压缩一段内容
+#[derive(Clone, Copy)]
+pub enum Shrink {
+    /// 保留开头和结尾，中间以省略说明代替
+    Truncate,
+    /// 用该段专用的摘要函数压缩（参数：原文、预算、估算器）
+    Summarize(fn(&str, usize, &TokenEstimator) -> String),
+}
+
+/// 提示词中的一段可变内容
+pub struct PromptSection {
+    pub name: &'static str,
+    pub text: String,
+    /// 越小越先被压缩
+    pub priority: u8,
+    /// 压缩后至少保留的 token 数
+    pub min_tokens: usize,
+    pub shrink: Shrink,
+}
+
+impl PromptSection {
+    pub fn new(
+        name: &'static str,
+        text: impl Into<String>,
+        priority: u8,
+        min_tokens: usize,
+        shrink: Shrink,
+    ) -> Self {
+        PromptSection {
+            name,
+            text: text.into(),
+            priority,
+            min_tokens,
+            shrink,
+        }
+    }
+}
+
+/// 让各段内容与模板（template 为各段都为空时渲染出的提示词）一起放进模型的上下文窗口：
+/// 超出时从优先级最低的段开始压缩，直到放得下或所有段都已压到下限。
+/// 返回按输入顺序排列的调整后内容，以及预算报告
+pub fn fit_sections(
+    config: &LlmConfig,
+    template: &str,
+    requested_output: usize,
+    sections: Vec<PromptSection>,
+) -> (Vec<String>, PromptBudgetReport) {
+    let estimator = TokenEstimator::for_model(&config.provider, &config.model);
+    let reserved_output = requested_output.min(estimator.context_window / MAX_OUTPUT_SHARE);
+    let limit = estimator.context_window.saturating_sub(reserved_output);
+    let overhead = estimator.estimate(template);
+
+    let mut tokens: Vec<usize> = sections
+        .iter()
+        .map(|s| estimator.estimate(&s.text))
+        .collect();
+    let mut texts: Vec<String> = sections.iter().map(|s| s.text.clone()).collect();
+    let mut adjustments = Vec::new();
+
+    let mut order: Vec<usize> = (0..sections.len()).collect();
+    order.sort_by_key(|&i| sections[i].priority);
+    for i in order {
+        let total = overhead + tokens.iter().sum::<usize>();
+        if total <= limit {
+            break;
+        }
+        let section = &sections[i];
+        let target = tokens[i]
+            .saturating_sub(total - limit)
+            .max(section.min_tokens);
+        if target >= tokens[i] {
+            continue;
+        }
+        let (shrunk, action) = match section.shrink {
+            Shrink::Truncate => (estimator.truncate_middle(&texts[i], target), "truncated"),
+            Shrink::Summarize(summarize) => {
+                (summarize(&texts[i], target, &estimator), "summarized")
+            }
+        };
+        let after = estimator.estimate(&shrunk);
+        adjustments.push(SectionAdjustment {
+            section: section.name.to_string(),
+            tokens_before: tokens[i],
+            tokens_after: after,
+            action: action.to_string(),
+        });
+        tokens[i] = after;
+        texts[i] = shrunk;
+    }
+
+    let prompt_tokens = overhead + tokens.iter().sum::<usize>();
+    let report = PromptBudgetReport {
+        model: config.model.clone(),
+        context_window: estimator.context_window,
+        reserved_output,
+        prompt_tokens,
+        fits: prompt_tokens <= limit,
+        adjustments,
+    };
+    (texts, report)
+}
+
+/// Diff 摘要的压缩方式：先去掉未改动的行，仍然超出时只列出前面的变更并注明省略的行数
+pub fn summarize_diff(diff: &str, budget: usize, estimator: &TokenEstimator) -> String {
+    let changed: Vec<&str> = diff
+        .lines()
+        .filter(|l| l.starts_with('-') || l.starts_with('+'))
+        .collect();
+
+    let note = |n: usize| format!("…（另有 {} 行变更未列出）", n);
+    let mut summary = String::new();
+    let mut used = 0;
+    for (i, line) in changed.iter().enumerate() {
+        let cost = estimator.estimate(line) + 1;
+        let left_after = changed.len() - i - 1;
+        let reserve = if left_after > 0 {
+            estimator.estimate(&note(left_after))
+        } else {
+            0
+        };
+        if used + cost + reserve > budget {
+            summary.push_str(&note(changed.len() - i));
+            return summary;
+        }
+        summary.push_str(line);
+        summary.push('\n');
+        used += cost;
+    }
+    summary
+}
@@ -0,0 +1,305 @@
+use crate::models::skill::{SkipReason, SkippedFile};
+use crate::services::skill_evolution::fnv1a;
+use regex::Regex;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 支持导入的文件扩展名（小写）
+pub const SUPPORTED_EXTENSIONS: &[&str] = &["md", "markdown", "txt", "html", "htm"];
+
+/// 从文件中提取出的样本
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtractedSample {
+    /// front-matter 或 <title> 中的标题
+    pub title: Option<String>,
+    pub content: String,
+}
+
+/// 展开路径列表：目录递归遍历（不进入以 . 开头的隐藏条目和指向目录的符号链接），
+/// 返回按路径排序的候选文件，以及未导入的路径及原因
+pub fn collect_files(paths: &[PathBuf]) -> (Vec<PathBuf>, Vec<SkippedFile>) {
+    let mut files = Vec::new();
+    let mut skipped = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            walk_dir(path, &mut files, &mut skipped);
+        } else if path.is_file() {
+            accept_file(path, &mut files, &mut skipped);
+        } else {
+            skipped.push(skip(path, SkipReason::NotFound, "路径不存在".to_string()));
+        }
+    }
+    (files, skipped)
+}
+
+fn walk_dir(dir: &Path, files: &mut Vec<PathBuf>, skipped: &mut Vec<SkippedFile>) {
+    let mut entries: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path()).collect(),
+        Err(e) => {
+            skipped.push(skip(
+                dir,
+                SkipReason::ReadFailed,
+                format!("读取目录失败: {}", e),
+            ));
+            return;
+        }
+    };
+    entries.sort();
+    for path in entries {
+        let hidden = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.starts_with('.'));
+        // 不跟随指向目录的符号链接，避免循环
+        let is_symlink = fs::symlink_metadata(&path).is_ok_and(|m| m.file_type().is_symlink());
+        if hidden {
+            skipped.push(skip(
+                &path,
+                SkipReason::Hidden,
+                "以 . 开头的隐藏文件或目录".to_string(),
+            ));
+        } else if is_symlink && path.is_dir() {
+            skipped.push(skip(
+                &path,
+                SkipReason::Symlink,
+                "指向目录的符号链接，不跟随".to_string(),
+            ));
+        } else if path.is_dir() {
+            walk_dir(&path, files, skipped);
+        } else if path.is_file() {
+            accept_file(&path, files, skipped);
+        }
+    }
+}
+
+fn accept_file(path: &Path, files: &mut Vec<PathBuf>, skipped: &mut Vec<SkippedFile>) {
+    if extension(path).is_some_and(|ext| SUPPORTED_EXTENSIONS.contains(&ext.as_str())) {
+        files.push(path.to_path_buf());
+    } else {
+        skipped.push(skip(
+            path,
+            SkipReason::UnsupportedType,
+            "不支持的文件类型（仅支持 .md / .txt / .html）".to_string(),
+        ));
+    }
+}
+
+/// 展开路径并逐个读取文件，只访问文件系统、不涉及数据库，
+/// 返回读取成功的文件及其正文，以及跳过明细
+pub fn read_samples(paths: &[PathBuf]) -> (Vec<(PathBuf, ExtractedSample)>, Vec<SkippedFile>) {
+    let (files, mut skipped) = collect_files(paths);
+    let mut samples = Vec::new();
+    for path in files {
+        match read_sample(&path) {
+            Ok(sample) => samples.push((path, sample)),
+            Err(skip) => skipped.push(skip),
+        }
+    }
+    (samples, skipped)
+}
+
+/// 读取文件并按扩展名提取正文
+pub fn read_sample(path: &Path) -> Result<ExtractedSample, SkippedFile> {
+    let bytes = fs::read(path)
+        .map_err(|e| skip(path, SkipReason::ReadFailed, format!("读取失败: {}", e)))?;
+    let raw = String::from_utf8(bytes)
+        .map_err(|_| skip(path, SkipReason::ReadFailed, "不是 UTF-8 编码".to_string()))?;
+    let sample = extract_text(extension(path).as_deref().unwrap_or(""), &raw);
+    if sample.content.is_empty() {
+        return Err(skip(
+            path,
+            SkipReason::Empty,
+            "去除元数据和标记后没有正文".to_string(),
+        ));
+    }
+    Ok(sample)
+}
+
+/// 按扩展名提取正文：Markdown 去掉 front-matter 与标记，HTML 去掉标签，纯文本原样保留
+pub fn extract_text(extension: &str, raw: &str) -> ExtractedSample {
+    let text = raw.trim_start_matches('\u{feff}').replace("\r\n", "\n");
+    match extension {
+        "md" | "markdown" => {
+            let (title, body) = split_front_matter(&text);
+            ExtractedSample {
+                title,
+                content: markdown_to_text(body),
+            }
+        }
+        "html" | "htm" => ExtractedSample {
+            title: html_title(&text),
+            content: html_to_text(&text),
+        },
+        _ => ExtractedSample {
+            title: None,
+            content: collapse_blank_lines(&text),
+        },
+    }
+}
+
+/// 样本去重用的内容哈希：忽略全部空白字符，只看文字本身
+pub fn content_hash(content: &str) -> String {
+    let compact: String = content.chars().filter(|c| !c.is_whitespace()).collect();
+    format!("{:016x}", fnv1a(&compact))
+}
+
+/// 拆出开头的 YAML（---）或 TOML（+++）front-matter，返回其中的 title 与剩余正文
+fn split_front_matter(text: &str) -> (Option<String>, &str) {
+    for fence in ["---", "+++"] {
+        let Some(rest) = text.strip_prefix(fence).and_then(|r| r.strip_prefix('\n')) else {
+            continue;
+        };
+        // 结束分隔线必须独占一行
+        let mut offset = 0;
+        for line in rest.split_inclusive('\n') {
+            if line.trim_end() == fence {
+                return (
+                    front_matter_title(&rest[..offset]),
+                    &rest[offset + line.len()..],
+                );
+            }
+            offset += line.len();
+        }
+    }
+    (None, text)
+}
+
+fn front_matter_title(meta: &str) -> Option<String> {
+    Regex::new(r#"(?m)^title\s*[:=]\s*["']?(.*?)["']?\s*$"#)
+        .ok()?
+        .captures(meta)
+        .map(|c| c[1].trim().to_string())
+        .filter(|t| !t.is_empty())
+}
+
+/// 去掉 Markdown 标记，保留标题行与段落结构；代码块不属于文风，整体移除
+fn markdown_to_text(markdown: &str) -> String {
+    let mut lines = Vec::new();
+    let mut in_fence = false;
+    for line in markdown.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if !in_fence {
+            lines.push(line);
+        }
+    }
+    let mut text = lines.join("\n");
+
+    let replacements: &[(&str, &str)] = &[
+        (r"(?s)<!--.*?-->", ""),
+        (r"!\[[^\]]*\]\([^)]*\)", ""),
+        (r"\[([^\]]+)\]\([^)]*\)", "$1"),
+        (r"\[([^\]]+)\]\[[^\]]*\]", "$1"),
+        (r"(?m)^\s*\[[^\]]+\]:\s+\S+.*$", ""),
+        (r"</?[a-zA-Z][^>]*>", ""),
+        (r"\*\*([^*\n]+)\*\*", "$1"),
+        (r"__([^_\n]+)__", "$1"),
+        (r"\*([^*\n]+)\*", "$1"),
+        (r"~~([^~\n]+)~~", "$1"),
+        (r"`([^`\n]+)`", "$1"),
+        (r"(?m)^[ \t]*>[ \t]?", ""),
+        (r"(?m)^[ \t]*([-*_][ \t]*){3,}$", ""),
+    ];
+    for (pattern, replacement) in replacements {
+        if let Ok(re) = Regex::new(pattern) {
+            text = re.replace_all(&text, *replacement).into_owned();
+        }
+    }
+    collapse_blank_lines(&text)
+}
+
+fn html_title(html: &str) -> Option<String> {
+    Regex::new(r"(?is)<title[^>]*>(.*?)</title>")
+        .ok()?
+        .captures(html)
+        .map(|c| decode_entities(c[1].trim()))
+        .filter(|t| !t.is_empty())
+}
+
+/// 去掉 HTML 标签：脚本、样式和 <head> 整体移除，块级元素换行，再解码常见实体
+fn html_to_text(html: &str) -> String {
+    let mut text = html.to_string();
+    let replacements: &[(&str, &str)] = &[
+        (r"(?is)<!--.*?-->", ""),
+        (r"(?is)<head[^>]*>.*?</head>", ""),
+        (r"(?is)<script[^>]*>.*?</script>", ""),
+        (r"(?is)<style[^>]*>.*?</style>", ""),
+        (r"(?is)<noscript[^>]*>.*?</noscript>", ""),
+        (r"(?i)<br\s*/?>", "\n"),
+        (
+            r"(?i)</(p|div|h[1-6]|li|blockquote|section|article|header|footer|tr|pre)\s*>",
+            "\n\n",
+        ),
+        (r"(?s)<[^>]+>", ""),
+    ];
+    for (pattern, replacement) in replacements {
+        if let Ok(re) = Regex::new(pattern) {
+            text = re.replace_all(&text, *replacement).into_owned();
+        }
+    }
+    let lines: Vec<String> = decode_entities(&text)
+        .lines()
+        .map(|l| l.trim().to_string())
+        .collect();
+    collapse_blank_lines(&lines.join("\n"))
+}
+
+/// 解码命名实体（常见几种）与数字实体
+fn decode_entities(text: &str) -> String {
+    let Ok(numeric) = Regex::new(r"&#(x[0-9a-fA-F]+|[0-9]+);") else {
+        return text.to_string();
+    };
+    let decoded = numeric.replace_all(text, |c: &regex::Captures| {
+        let code = &c[1];
+        let value = match code.strip_prefix('x') {
+            Some(hex) => u32::from_str_radix(hex, 16).ok(),
+            None => code.parse().ok(),
+        };
+        value
+            .and_then(char::from_u32)
+            .map(String::from)
+            .unwrap_or_default()
+    });
+    decoded
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// 去掉行尾空白，连续空行合并为一个，整体去掉首尾空白
+fn collapse_blank_lines(text: &str) -> String {
+    let mut result = String::new();
+    let mut blank = 0;
+    for line in text.lines().map(str::trim_end) {
+        if line.trim().is_empty() {
+            blank += 1;
+            continue;
+        }
+        if !result.is_empty() {
+            result.push_str(if blank > 0 { "\n\n" } else { "\n" });
+        }
+        result.push_str(line);
+        blank = 0;
+    }
+    result
+}
+
+fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+}
+
+fn skip(path: &Path, reason: SkipReason, detail: String) -> SkippedFile {
+    SkippedFile {
+        path: path.display().to_string(),
+        reason,
+        detail,
+    }
+}
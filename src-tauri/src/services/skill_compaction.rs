@@ -0,0 +1,220 @@
+use crate::models::skill::{MergedRule, TrimmedRule};
+use crate::models::skill_spec::SkillSpec;
+use crate::services::linter::word_to_regex;
+use crate::services::skill_evolution::normalize_rule;
+use crate::services::tokens::TokenEstimator;
+use similar::TextDiff;
+
+/// 未指定预算时，渲染后的 Skill Markdown 允许占用的 token 数
+pub const DEFAULT_TOKEN_BUDGET: usize = 1500;
+
+/// 字符级相似度达到该阈值的两条规则视为近似重复
+const NEAR_DUPLICATE_SIMILARITY: f32 = 0.8;
+
+/// 短于此字数的规则（多为术语）只按完全相同合并，差一两个字就是另一个词
+const MIN_SIMILARITY_CHARS: usize = 6;
+
+/// 规则开头的中文否定词：只有一方带否定时两条规则意思相反，不能合并
+const ZH_NEGATION_PREFIXES: &[&str] = &["不", "别", "勿", "没有", "无需", "避免", "禁止", "杜绝"];
+
+/// 规则首词为这些英文否定词时视为否定
+const EN_NEGATION_WORDS: &[&str] = &["no", "not", "never", "avoid", "don't", "dont"];
+
+/// 确定性去重：合并各章节中的近似重复规则，并折叠冗余的禁用词
+/// （已禁用“赋能”时，“全面赋能”不必再单独列出）
+pub fn dedup_spec(spec: &mut SkillSpec) -> Vec<MergedRule> {
+    let mut merged = Vec::new();
+    for (section, list) in [
+        ("style_principles", &mut spec.style_principles),
+        ("forbidden_patterns", &mut spec.blocklist.forbidden_patterns),
+        (
+            "forbidden_structures",
+            &mut spec.blocklist.forbidden_structures,
+        ),
+        ("terminology", &mut spec.references.terminology),
+        ("sample_features", &mut spec.references.sample_features),
+    ] {
+        merged.extend(merge_near_duplicates(section, list));
+    }
+    merged.extend(collapse_forbidden_words(
+        &mut spec.blocklist.forbidden_words,
+    ));
+    merged
+}
+
+/// 近似重复的规则只保留措辞更完整（更长）的一条，位置沿用先出现的那条
+fn merge_near_duplicates(section: &str, list: &mut Vec<String>) -> Vec<MergedRule> {
+    let mut kept: Vec<(String, Vec<String>)> = Vec::new();
+    for item in list.drain(..) {
+        let key = normalize_rule(&item);
+        let existing = kept.iter_mut().find(|(k, _)| same_rule(k, &item));
+        match existing {
+            Some((text, merged)) => {
+                if key.chars().count() > normalize_rule(text).chars().count() {
+                    merged.push(std::mem::replace(text, item));
+                } else {
+                    merged.push(item);
+                }
+            }
+            None => kept.push((item, Vec::new())),
+        }
+    }
+
+    let mut report = Vec::new();
+    for (text, merged) in kept {
+        if !merged.is_empty() {
+            report.push(MergedRule {
+                section: section.to_string(),
+                kept: text.clone(),
+                merged,
+            });
+        }
+        list.push(text);
+    }
+    report
+}
+
+/// 两条规则是否表达同一要求：归一化后完全相同、较短的一条恰好是另一条中的一个分句，
+/// 或（不太短时）字符相似度达到阈值；只有一方带否定前缀时一律不合并
+fn same_rule(a: &str, b: &str) -> bool {
+    if is_negated(a) != is_negated(b) {
+        return false;
+    }
+    let (a, b) = (normalize_rule(a), normalize_rule(b));
+    if a.is_empty() || b.is_empty() {
+        return a == b;
+    }
+    let (short, long) = if a.chars().count() <= b.chars().count() {
+        (&a, &b)
+    } else {
+        (&b, &a)
+    };
+    *short == *long
+        || long
+            .split(|c: char| "，,；;、。".contains(c))
+            .any(|clause| clause == short.as_str())
+        || (short.chars().count() >= MIN_SIMILARITY_CHARS
+            && TextDiff::from_chars(a.as_str(), b.as_str()).ratio() >= NEAR_DUPLICATE_SIMILARITY)
+}
+
+/// 规则是否以否定词开头（英文按首个单词判断，“notable” 不算否定）
+fn is_negated(text: &str) -> bool {
+    let text = text.trim().to_lowercase();
+    if ZH_NEGATION_PREFIXES.iter().any(|p| text.starts_with(p)) {
+        return true;
+    }
+    let first_word: String = text
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '\'')
+        .collect();
+    EN_NEGATION_WORDS.contains(&first_word.as_str()) || text.starts_with("do not ")
+}
+
+/// 禁用词：重复项以及已被另一个禁用词命中的词组都是冗余的，只保留最短的那个。
+/// 是否命中与本地检查一致（英文按整词），“RAG” 不会吞掉 “GraphRAG”
+fn collapse_forbidden_words(words: &mut Vec<String>) -> Vec<MergedRule> {
+    let mut by_length: Vec<String> = words.clone();
+    by_length.sort_by_key(|w| normalize_rule(w).chars().count());
+
+    let mut kept: Vec<(String, Vec<String>)> = Vec::new();
+    for word in by_length {
+        match kept.iter_mut().find(|(k, _)| {
+            is_negated(k) == is_negated(&word)
+                && word_to_regex(k).is_some_and(|re| re.is_match(&word))
+        }) {
+            Some((_, merged)) => merged.push(word),
+            None => kept.push((word, Vec::new())),
+        }
+    }
+
+    // 保持原有顺序，完全相同的重复项只留第一个
+    let mut remaining: Vec<&String> = kept.iter().map(|(k, _)| k).collect();
+    words.retain(|w| match remaining.iter().position(|k| *k == w) {
+        Some(i) => {
+            remaining.remove(i);
+            true
+        }
+        None => false,
+    });
+    kept.into_iter()
+        .filter(|(_, merged)| !merged.is_empty())
+        .map(|(kept, merged)| MergedRule {
+            section: "forbidden_words".to_string(),
+            kept,
+            merged,
+        })
+        .collect()
+}
+
+/// 渲染结果超出 token 预算时，从优先级最低的章节末尾逐条移除规则，直到满足预算。
+/// 调用方应先按置信度排序，末尾即最不可信的规则；至少保留一条风格原则。
+/// 预算按实际生成所用模型的分词器估算
+pub fn enforce_budget<F>(
+    spec: &mut SkillSpec,
+    budget: usize,
+    estimator: &TokenEstimator,
+    render: F,
+) -> Vec<TrimmedRule>
+where
+    F: Fn(&SkillSpec) -> String,
+{
+    let mut trimmed = Vec::new();
+    while estimator.estimate(&render(spec)) > budget {
+        let lists = [
+            ("sample_features", &mut spec.references.sample_features, 0),
+            ("terminology", &mut spec.references.terminology, 0),
+            (
+                "forbidden_structures",
+                &mut spec.blocklist.forbidden_structures,
+                0,
+            ),
+            (
+                "forbidden_patterns",
+                &mut spec.blocklist.forbidden_patterns,
+                0,
+            ),
+            ("style_principles", &mut spec.style_principles, 1),
+            ("forbidden_words", &mut spec.blocklist.forbidden_words, 0),
+        ];
+        let Some((section, list, _)) = lists
+            .into_iter()
+            .find(|(_, list, min_len)| list.len() > *min_len)
+        else {
+            break;
+        };
+        if let Some(text) = list.pop() {
+            trimmed.push(TrimmedRule {
+                section: section.to_string(),
+                text,
+            });
+        }
+    }
+    trimmed
+}
+
+/// 生成压缩版本的变更说明，例如 "压缩 Skill：合并 3 条近似规则、按预算移除 1 条规则（1820 → 1490 tokens）"
+pub fn build_compaction_summary(
+    merged: &[MergedRule],
+    trimmed: &[TrimmedRule],
+    llm_consolidated: bool,
+    tokens_before: usize,
+    tokens_after: usize,
+) -> String {
+    let mut parts = Vec::new();
+    let merged_count: usize = merged.iter().map(|m| m.merged.len()).sum();
+    if merged_count > 0 {
+        parts.push(format!("合并 {} 条近似规则", merged_count));
+    }
+    if !trimmed.is_empty() {
+        parts.push(format!("按预算移除 {} 条规则", trimmed.len()));
+    }
+    if llm_consolidated {
+        parts.push("经 LLM 整理".to_string());
+    }
+    format!(
+        "压缩 Skill：{}（{} → {} tokens）",
+        parts.join("、"),
+        tokens_before,
+        tokens_after
+    )
+}
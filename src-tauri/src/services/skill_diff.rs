@@ -0,0 +1,117 @@
+use crate::models::skill::{ModifiedRule, SectionChanges};
+use crate::models::skill_spec::SkillSpec;
+use crate::services::skill_evolution::normalize_rule;
+use similar::TextDiff;
+
+/// 删除与新增的规则相似度达到该阈值时视为同一条规则被修改
+const MODIFIED_SIMILARITY: f32 = 0.5;
+
+/// 按章节比较两个版本的 SkillSpec，只返回有变化的章节
+pub fn compare_specs(from: &SkillSpec, to: &SkillSpec) -> Vec<SectionChanges> {
+    let fields = [
+        ("role.identity", &from.role.identity, &to.role.identity),
+        ("role.tone", &from.role.tone, &to.role.tone),
+        ("role.audience", &from.role.audience, &to.role.audience),
+    ];
+    let lists = [
+        (
+            "style_principles",
+            &from.style_principles,
+            &to.style_principles,
+        ),
+        (
+            "forbidden_words",
+            &from.blocklist.forbidden_words,
+            &to.blocklist.forbidden_words,
+        ),
+        (
+            "forbidden_patterns",
+            &from.blocklist.forbidden_patterns,
+            &to.blocklist.forbidden_patterns,
+        ),
+        (
+            "forbidden_structures",
+            &from.blocklist.forbidden_structures,
+            &to.blocklist.forbidden_structures,
+        ),
+        (
+            "terminology",
+            &from.references.terminology,
+            &to.references.terminology,
+        ),
+        (
+            "sample_features",
+            &from.references.sample_features,
+            &to.references.sample_features,
+        ),
+    ];
+
+    fields
+        .into_iter()
+        .map(|(section, old, new)| compare_field(section, old, new))
+        .chain(
+            lists
+                .into_iter()
+                .map(|(section, old, new)| compare_lists(section, old, new)),
+        )
+        .filter(|changes| !changes.is_empty())
+        .collect()
+}
+
+/// 单值字段：空 → 有值为新增，有值 → 空为删除，其余不同为修改
+fn compare_field(section: &str, old: &str, new: &str) -> SectionChanges {
+    let mut changes = SectionChanges::new(section);
+    match (old.trim().is_empty(), new.trim().is_empty()) {
+        (true, false) => changes.added.push(new.to_string()),
+        (false, true) => changes.removed.push(old.to_string()),
+        (false, false) if normalize_rule(old) != normalize_rule(new) => {
+            changes.modified.push(ModifiedRule {
+                from: old.to_string(),
+                to: new.to_string(),
+            })
+        }
+        _ => {}
+    }
+    changes
+}
+
+/// 列表章节：归一化后相同的条目视为未变，剩余的删除/新增条目按相似度配对为修改
+fn compare_lists(section: &str, old: &[String], new: &[String]) -> SectionChanges {
+    let mut changes = SectionChanges::new(section);
+
+    let old_keys: Vec<String> = old.iter().map(|s| normalize_rule(s)).collect();
+    let new_keys: Vec<String> = new.iter().map(|s| normalize_rule(s)).collect();
+    let mut added: Vec<&String> = new
+        .iter()
+        .zip(&new_keys)
+        .filter(|(_, key)| !old_keys.contains(key))
+        .map(|(item, _)| item)
+        .collect();
+
+    for (item, key) in old.iter().zip(&old_keys) {
+        if new_keys.contains(key) {
+            continue;
+        }
+        let best = added
+            .iter()
+            .enumerate()
+            .map(|(i, candidate)| (i, similarity(item, candidate)))
+            .filter(|(_, ratio)| *ratio >= MODIFIED_SIMILARITY)
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        match best {
+            Some((i, _)) => changes.modified.push(ModifiedRule {
+                from: item.clone(),
+                to: added.remove(i).clone(),
+            }),
+            None => changes.removed.push(item.clone()),
+        }
+    }
+
+    changes.added = added.into_iter().cloned().collect();
+    changes
+}
+
+/// 字符级相似度（0.0 ~ 1.0）
+fn similarity(a: &str, b: &str) -> f32 {
+    TextDiff::from_chars(a, b).ratio()
+}
@@ -0,0 +1,144 @@
+use crate::models::analysis::NewRules;
+use crate::models::skill::CandidateRule;
+use crate::models::skill_spec::{RuleSection, SkillSpec};
+use serde::Serialize;
+
+/// 置信度低于该值的规则不再注入生成提示词
+pub const MIN_RULE_CONFIDENCE: f64 = 0.5;
+
+/// 一次合并实际新增的规则（已去除与现有规则重复的条目）
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct MergeReport {
+    pub added_principles: Vec<String>,
+    pub added_forbidden_words: Vec<String>,
+    pub added_forbidden_patterns: Vec<String>,
+}
+
+impl MergeReport {
+    pub fn is_empty(&self) -> bool {
+        self.added_principles.is_empty()
+            && self.added_forbidden_words.is_empty()
+            && self.added_forbidden_patterns.is_empty()
+    }
+}
+
+/// 规则去重用的归一化形式：忽略大小写、空白和首尾标点
+pub fn normalize_rule(text: &str) -> String {
+    text.chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(|c| c.to_lowercase())
+        .collect::<String>()
+        .trim_matches(|c: char| c.is_ascii_punctuation() || "。，、；：！？…—“”‘’「」".contains(c))
+        .to_string()
+}
+
+/// 规则的稳定 ID：章节前缀 + 归一化文本的 FNV-1a 哈希，
+/// 同一条规则无论措辞中的空白、标点如何变化，ID 都不变
+pub fn rule_id(section: RuleSection, text: &str) -> String {
+    format!("{}-{:016x}", section.as_str(), fnv1a(&normalize_rule(text)))
+}
+
+/// 64 位 FNV-1a 哈希（跨进程、跨版本稳定，不依赖标准库 Hasher 的实现细节）
+pub fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// 计算 Diff 分析建议中尚未存在于 SkillSpec 的候选规则（按章节顺序，已去重）
+pub fn candidate_rules(spec: &SkillSpec, rules: &NewRules) -> Vec<CandidateRule> {
+    let mut candidates: Vec<CandidateRule> = Vec::new();
+    for section in RuleSection::ALL {
+        let existing = spec.rules(section);
+        for item in rules.additions(section) {
+            let item = item.trim();
+            let key = normalize_rule(item);
+            if key.is_empty() || existing.iter().any(|t| normalize_rule(t) == key) {
+                continue;
+            }
+            let id = rule_id(section, item);
+            if candidates.iter().any(|c| c.id == id) {
+                continue;
+            }
+            candidates.push(CandidateRule {
+                id,
+                section,
+                text: item.to_string(),
+            });
+        }
+    }
+    candidates
+}
+
+/// 将候选规则追加到 SkillSpec 对应章节，返回合并报告
+pub fn apply_candidates(spec: &mut SkillSpec, candidates: &[CandidateRule]) -> MergeReport {
+    let mut report = MergeReport::default();
+    for candidate in candidates {
+        spec.rules_mut(candidate.section)
+            .push(candidate.text.clone());
+        let added = match candidate.section {
+            RuleSection::StylePrinciples => &mut report.added_principles,
+            RuleSection::ForbiddenWords => &mut report.added_forbidden_words,
+            RuleSection::ForbiddenPatterns => &mut report.added_forbidden_patterns,
+        };
+        added.push(candidate.text.clone());
+    }
+    report
+}
+
+/// 生成版本变更说明，例如 "应用 Diff #3：新增 2 条风格原则、1 个禁用词（减少套话）"
+pub fn build_change_summary(
+    diff_record_id: i64,
+    report: &MergeReport,
+    analysis_summary: &str,
+) -> String {
+    let mut parts = Vec::new();
+    if !report.added_principles.is_empty() {
+        parts.push(format!("{} 条风格原则", report.added_principles.len()));
+    }
+    if !report.added_forbidden_words.is_empty() {
+        parts.push(format!("{} 个禁用词", report.added_forbidden_words.len()));
+    }
+    if !report.added_forbidden_patterns.is_empty() {
+        parts.push(format!(
+            "{} 个禁用句式",
+            report.added_forbidden_patterns.len()
+        ));
+    }
+
+    let mut summary = format!("应用 Diff #{}：新增 {}", diff_record_id, parts.join("、"));
+    let analysis_summary = analysis_summary.trim();
+    if !analysis_summary.is_empty() {
+        summary.push_str(&format!("（{}）", analysis_summary));
+    }
+    summary
+}
+
+/// 规则置信度：支持次数与被违背次数的平滑比例。
+/// 新规则为 2/3；被违背一次降到 1/2 仍保留，违背次数多于支持次数后才会被过滤
+pub fn rule_confidence(support_count: i64, contradiction_count: i64) -> f64 {
+    (support_count + 1) as f64 / (support_count + contradiction_count + 2) as f64
+}
+
+/// 按置信度从高到低重排各章节规则，并过滤低于 min_confidence 的规则；
+/// 没有置信度记录的规则按新规则处理，同置信度保持原有顺序
+pub fn rank_rules<F>(spec: &SkillSpec, min_confidence: f64, confidence: F) -> SkillSpec
+where
+    F: Fn(RuleSection, &str) -> Option<f64>,
+{
+    let mut ranked = spec.clone();
+    for section in RuleSection::ALL {
+        let mut scored: Vec<(f64, String)> = spec
+            .rules(section)
+            .iter()
+            .map(|text| {
+                let score = confidence(section, text).unwrap_or_else(|| rule_confidence(1, 0));
+                (score, text.clone())
+            })
+            .filter(|(score, _)| *score >= min_confidence)
+            .collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        *ranked.rules_mut(section) = scored.into_iter().map(|(_, text)| text).collect();
+    }
+    ranked
+}
@@ -0,0 +1,149 @@
+use crate::models::analysis::DiffAnalysis;
+use crate::models::skill_spec::SkillSpec;
+use crate::prompts;
+use crate::services::llm_error::LlmError;
+use crate::services::llm_service::{self, ChatMessage, LlmConfig, RetryNotice, RetryPolicy};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// 输出无法通过校验时，最多追加的修复轮数
+pub const MAX_REPAIR_ATTEMPTS: usize = 2;
+
+/// 可由 LLM 以 JSON 形式输出并在本地校验的结构
+pub trait StructuredOutput: DeserializeOwned + Serialize {
+    /// 解析后的清理（去除空白条目等），不应拒绝合法输出
+    fn sanitize(&mut self) {}
+
+    /// 业务校验，失败信息会原样反馈给模型用于修复
+    fn validate(&self) -> Result<(), String>;
+}
+
+impl StructuredOutput for SkillSpec {
+    fn sanitize(&mut self) {
+        SkillSpec::sanitize(self)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        SkillSpec::validate(self)
+    }
+}
+
+impl StructuredOutput for DiffAnalysis {
+    fn sanitize(&mut self) {
+        DiffAnalysis::sanitize(self)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        DiffAnalysis::validate(self)
+    }
+}
+
+/// 从模型输出中提取 JSON 对象：去除 markdown 代码块标记和前后的说明文字
+pub fn extract_json(text: &str) -> Option<&str> {
+    let text = strip_code_fence(text.trim());
+    let start = text.find('{')?;
+
+    // 按括号配对找到与第一个 { 对应的 }，跳过字符串内的括号
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in text[start..].char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(&text[start..start + i + 1]);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// 去除 ```json ... ``` 包裹（若存在）
+fn strip_code_fence(text: &str) -> &str {
+    let Some(fence_start) = text.find("```") else {
+        return text;
+    };
+    let after = &text[fence_start + 3..];
+    // 跳过语言标记所在行
+    let body = match after.find('\n') {
+        Some(pos) => &after[pos + 1..],
+        None => after,
+    };
+    match body.find("```") {
+        Some(end) => &body[..end],
+        None => body,
+    }
+}
+
+/// 解析并校验模型输出
+pub fn parse_structured<T: StructuredOutput>(text: &str) -> Result<T, String> {
+    let json = extract_json(text).ok_or_else(|| "输出中未找到 JSON 对象".to_string())?;
+    let mut value: T =
+        serde_json::from_str(json).map_err(|e| format!("JSON 结构不符合要求: {}", e))?;
+    value.sanitize();
+    value.validate()?;
+    Ok(value)
+}
+
+/// 调用 LLM 获取结构化输出：优先使用 JSON 模式，校验失败时把错误反馈给模型重试，
+/// 最多追加 MAX_REPAIR_ATTEMPTS 轮。返回解析后的结构及其规范化 JSON 文本
+pub async fn chat_structured<T, N>(
+    config: &LlmConfig,
+    mut messages: Vec<ChatMessage>,
+    temperature: f64,
+    policy: &RetryPolicy,
+    mut on_retry: N,
+) -> Result<(T, String), LlmError>
+where
+    T: StructuredOutput,
+    N: FnMut(&RetryNotice),
+{
+    let mut attempt = 0;
+    loop {
+        let output = llm_service::with_retry(policy, &mut on_retry, || {
+            llm_service::chat_completion_json(config, messages.clone(), temperature)
+        })
+        .await?;
+
+        match parse_structured::<T>(&output) {
+            Ok(value) => {
+                let json = serde_json::to_string_pretty(&value).map_err(|e| {
+                    LlmError::InvalidResponse {
+                        message: e.to_string(),
+                    }
+                })?;
+                return Ok((value, json));
+            }
+            Err(error) if attempt < MAX_REPAIR_ATTEMPTS => {
+                attempt += 1;
+                messages.push(ChatMessage {
+                    role: "assistant".to_string(),
+                    content: output,
+                });
+                messages.push(ChatMessage {
+                    role: "user".to_string(),
+                    content: prompts::repair_json::build_repair_prompt(&error),
+                });
+            }
+            Err(error) => {
+                return Err(LlmError::InvalidResponse {
+                    message: format!("模型输出经 {} 次修复仍无效: {}", attempt, error),
+                })
+            }
+        }
+    }
+}
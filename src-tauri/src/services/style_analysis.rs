@@ -0,0 +1,124 @@
+use crate::models::skill::AnalysisProgress;
+use crate::models::skill_spec::SkillSpec;
+use crate::prompts;
+use crate::services::llm_service::{ChatMessage, LlmConfig, RetryNotice, RetryPolicy};
+use crate::services::tokens::{batch_by_tokens, TokenEstimator};
+use crate::services::{skill_compaction, structured_output, stylometry};
+use std::ops::Range;
+
+/// 每批样本（或每轮合并的分析结果）允许占用的 token 数，不含提示词本身
+pub const ANALYSIS_BATCH_TOKENS: usize = 6000;
+
+/// 按模型上下文确定每批预算：样本最多占窗口的一半，其余留给提示词模板与输出
+pub fn batch_tokens_for(config: &LlmConfig) -> usize {
+    let window = TokenEstimator::for_model(&config.provider, &config.model).context_window;
+    ANALYSIS_BATCH_TOKENS.min(window / 2)
+}
+
+/// 分批分析样本（map），再逐轮合并各批结果（reduce），直到只剩一份 Skill。
+/// 只有一批时与一次性分析等价，不会额外调用合并
+pub async fn map_reduce_analysis<R, P>(
+    config: &LlmConfig,
+    samples: &[String],
+    batch_tokens: usize,
+    policy: &RetryPolicy,
+    mut on_retry: R,
+    mut on_progress: P,
+) -> Result<(SkillSpec, String), String>
+where
+    R: FnMut(&RetryNotice),
+    P: FnMut(AnalysisProgress),
+{
+    // 按目标模型的分词粒度估算；单篇样本超出预算时只保留开头部分
+    let estimator = TokenEstimator::for_model(&config.provider, &config.model);
+    let samples: Vec<String> = samples
+        .iter()
+        .map(|s| estimator.truncate(s, batch_tokens))
+        .collect();
+    let batches = batch_by_tokens(&samples, batch_tokens, &estimator);
+
+    let mut partials: Vec<(SkillSpec, String)> = Vec::new();
+    for (i, range) in batches.iter().enumerate() {
+        let batch = &samples[range.clone()];
+        let prompt =
+            prompts::analyze_style::build_analyze_prompt(batch, &stylometry::fingerprint(batch));
+        partials.push(request_spec(config, prompt, policy, &mut on_retry).await?);
+        on_progress(progress("map", 0, i + 1, batches.len()));
+    }
+
+    let fingerprint = stylometry::fingerprint(&samples);
+    let mut round = 0;
+    while partials.len() > 1 {
+        round += 1;
+        let groups = merge_groups(&partials, batch_tokens, &estimator);
+        let mut merged = Vec::with_capacity(groups.len());
+        let mut parts = partials.into_iter();
+        for (i, range) in groups.iter().enumerate() {
+            let group: Vec<(SkillSpec, String)> = parts.by_ref().take(range.len()).collect();
+            if group.len() == 1 {
+                merged.extend(group);
+            } else {
+                let jsons: Vec<String> = group.into_iter().map(|(_, json)| json).collect();
+                let prompt = prompts::merge_specs::build_merge_prompt(&jsons, &fingerprint);
+                merged.push(request_spec(config, prompt, policy, &mut on_retry).await?);
+            }
+            on_progress(progress("reduce", round, i + 1, groups.len()));
+        }
+        partials = merged;
+    }
+
+    let (mut spec, json) = partials
+        .pop()
+        .ok_or_else(|| "请提供至少一篇样本文章".to_string())?;
+    if round == 0 {
+        return Ok((spec, json));
+    }
+    // 合并结果可能仍有近似重复的规则，本地再去重一次
+    skill_compaction::dedup_spec(&mut spec);
+    let json = spec.to_content_json()?;
+    Ok((spec, json))
+}
+
+/// 每轮合并的分组；分析结果单独就超出预算、无法按预算合并时，退化为两两合并，保证轮次收敛
+fn merge_groups(
+    partials: &[(SkillSpec, String)],
+    budget: usize,
+    estimator: &TokenEstimator,
+) -> Vec<Range<usize>> {
+    let jsons: Vec<&str> = partials.iter().map(|(_, json)| json.as_str()).collect();
+    let groups = batch_by_tokens(&jsons, budget, estimator);
+    if groups.len() < partials.len() {
+        return groups;
+    }
+    (0..partials.len())
+        .step_by(2)
+        .map(|start| start..(start + 2).min(partials.len()))
+        .collect()
+}
+
+async fn request_spec<R>(
+    config: &LlmConfig,
+    prompt: String,
+    policy: &RetryPolicy,
+    on_retry: &mut R,
+) -> Result<(SkillSpec, String), String>
+where
+    R: FnMut(&RetryNotice),
+{
+    let messages = vec![ChatMessage {
+        role: "user".to_string(),
+        content: prompt,
+    }];
+    structured_output::chat_structured::<SkillSpec, _>(config, messages, 0.3, policy, on_retry)
+        .await
+        .map_err(Into::into)
+}
+
+fn progress(stage: &str, round: usize, completed: usize, total: usize) -> AnalysisProgress {
+    AnalysisProgress {
+        stage: stage.to_string(),
+        round,
+        completed,
+        total,
+    }
+}
@@ -0,0 +1,239 @@
+use crate::models::fingerprint::{FeatureDeviation, StyleFingerprint, StyleMatch};
+use crate::services::stylometry::round_to;
+
+/// 数量类特征相差该倍数时距离记为 1
+const MAX_RATIO: f64 = 4.0;
+
+/// 数量类特征的平滑项，避免样本中为 0 的特征导致除零或无穷大的倍数
+const SMOOTHING: f64 = 1.0;
+
+/// 特征的比较方式
+enum Kind {
+    /// 长度、频次等：按对数倍数比较，附带（更大时, 更小时）的描述词
+    Magnitude(&'static str, &'static str),
+    /// 0–1 的比例：按绝对差比较
+    Proportion,
+    /// 变异系数：按绝对差比较，说明文字描述起伏大小
+    Spread,
+}
+
+struct Feature {
+    key: &'static str,
+    label: &'static str,
+    weight: f64,
+    kind: Kind,
+    value: fn(&StyleFingerprint) -> f64,
+}
+
+const FEATURES: &[Feature] = &[
+    Feature {
+        key: "sentence_length.mean",
+        label: "句子",
+        weight: 2.0,
+        kind: Kind::Magnitude("长", "短"),
+        value: |fp| fp.sentence_length.mean,
+    },
+    Feature {
+        key: "sentence_length.cv",
+        label: "句长起伏",
+        weight: 1.0,
+        kind: Kind::Spread,
+        value: |fp| fp.sentence_length.cv,
+    },
+    Feature {
+        key: "paragraph_length.mean",
+        label: "段落",
+        weight: 1.0,
+        kind: Kind::Magnitude("长", "短"),
+        value: |fp| fp.paragraph_length.mean,
+    },
+    Feature {
+        key: "punctuation.full_width_ratio",
+        label: "全角标点占比",
+        weight: 1.0,
+        kind: Kind::Proportion,
+        value: |fp| fp.punctuation.full_width_ratio,
+    },
+    Feature {
+        key: "punctuation.comma_per_k",
+        label: "逗号",
+        weight: 0.5,
+        kind: Kind::Magnitude("多", "少"),
+        value: |fp| fp.punctuation.comma_per_k,
+    },
+    Feature {
+        key: "punctuation.question_per_k",
+        label: "问号",
+        weight: 0.5,
+        kind: Kind::Magnitude("多", "少"),
+        value: |fp| fp.punctuation.question_per_k,
+    },
+    Feature {
+        key: "punctuation.exclamation_per_k",
+        label: "感叹号",
+        weight: 0.5,
+        kind: Kind::Magnitude("多", "少"),
+        value: |fp| fp.punctuation.exclamation_per_k,
+    },
+    Feature {
+        key: "punctuation.em_dash_per_k",
+        label: "破折号",
+        weight: 0.5,
+        kind: Kind::Magnitude("多", "少"),
+        value: |fp| fp.punctuation.em_dash_per_k,
+    },
+    Feature {
+        key: "punctuation.ellipsis_per_k",
+        label: "省略号",
+        weight: 0.5,
+        kind: Kind::Magnitude("多", "少"),
+        value: |fp| fp.punctuation.ellipsis_per_k,
+    },
+    Feature {
+        key: "punctuation.quote_per_k",
+        label: "引号",
+        weight: 0.5,
+        kind: Kind::Magnitude("多", "少"),
+        value: |fp| fp.punctuation.quote_per_k,
+    },
+    Feature {
+        key: "cjk_ratio",
+        label: "中文字符占比",
+        weight: 1.0,
+        kind: Kind::Proportion,
+        value: |fp| fp.cjk_ratio,
+    },
+    Feature {
+        key: "latin_words_per_k",
+        label: "英文单词",
+        weight: 0.5,
+        kind: Kind::Magnitude("多", "少"),
+        value: |fp| fp.latin_words_per_k,
+    },
+];
+
+/// 虚词分布（余弦距离）在总分中的权重
+const FUNCTION_WORDS_WEIGHT: f64 = 1.0;
+
+/// 比较待评估文本的指纹与 Skill 样本指纹，返回逐项偏差与总体相似度
+pub fn compare(expected: &StyleFingerprint, actual: &StyleFingerprint) -> StyleMatch {
+    let mut deviations: Vec<(FeatureDeviation, f64)> = FEATURES
+        .iter()
+        .map(|f| {
+            let (e, a) = ((f.value)(expected), (f.value)(actual));
+            let (distance, message) = match f.kind {
+                Kind::Magnitude(more, less) => magnitude_deviation(f.label, more, less, e, a),
+                Kind::Proportion => proportion_deviation(f.label, e, a),
+                Kind::Spread => spread_deviation(f.label, e, a),
+            };
+            (deviation(f.key, f.label, e, a, distance, message), f.weight)
+        })
+        .collect();
+
+    let fw_distance = 1.0 - cosine(expected, actual);
+    deviations.push((
+        deviation(
+            "function_words",
+            "虚词分布",
+            1.0,
+            1.0 - fw_distance,
+            fw_distance,
+            format!("虚词用法与你的相似度 {:.0}%", (1.0 - fw_distance) * 100.0),
+        ),
+        FUNCTION_WORDS_WEIGHT,
+    ));
+
+    let total_weight: f64 = deviations.iter().map(|(_, w)| w).sum();
+    let weighted: f64 = deviations.iter().map(|(d, w)| d.distance * w).sum();
+    let mut deviations: Vec<FeatureDeviation> = deviations.into_iter().map(|(d, _)| d).collect();
+    deviations.sort_by(|a, b| b.distance.total_cmp(&a.distance));
+
+    StyleMatch {
+        similarity: round_to(100.0 * (1.0 - weighted / total_weight), 10.0),
+        deviations,
+    }
+}
+
+fn deviation(
+    key: &str,
+    label: &str,
+    expected: f64,
+    actual: f64,
+    distance: f64,
+    message: String,
+) -> FeatureDeviation {
+    FeatureDeviation {
+        feature: key.to_string(),
+        label: label.to_string(),
+        expected,
+        actual,
+        distance: round_to(distance, 100.0),
+        message,
+    }
+}
+
+/// 按平滑后的倍数计算距离；说明文字使用未平滑的倍数（"句子比你的长 40%"、"感叹号是你的 3.0 倍"）
+fn magnitude_deviation(label: &str, more: &str, less: &str, e: f64, a: f64) -> (f64, String) {
+    let smoothed = (a + SMOOTHING) / (e + SMOOTHING);
+    let distance = (smoothed.ln().abs() / MAX_RATIO.ln()).min(1.0);
+
+    let message = if e <= 0.0 {
+        if a <= 0.0 {
+            format!("{}与你的一致", label)
+        } else {
+            format!("{}{}了（你的样本中几乎没有）", label, more)
+        }
+    } else {
+        let ratio = a / e;
+        if ratio >= 2.0 {
+            format!("{}是你的 {:.1} 倍", label, ratio)
+        } else if ratio >= 1.0 {
+            format!("{}比你的{} {:.0}%", label, more, (ratio - 1.0) * 100.0)
+        } else {
+            format!("{}比你的{} {:.0}%", label, less, (1.0 - ratio) * 100.0)
+        }
+    };
+    (distance, message)
+}
+
+fn proportion_deviation(label: &str, e: f64, a: f64) -> (f64, String) {
+    let distance = (a - e).abs().min(1.0);
+    let message = format!("{} {:.0}%，你的是 {:.0}%", label, a * 100.0, e * 100.0);
+    (distance, message)
+}
+
+fn spread_deviation(label: &str, e: f64, a: f64) -> (f64, String) {
+    let distance = (a - e).abs().min(1.0);
+    let message = if (a - e).abs() < 0.05 {
+        format!("{}与你的接近", label)
+    } else if a > e {
+        format!("{}比你的大（句子长短更不均匀）", label)
+    } else {
+        format!("{}比你的小（句子长短更整齐）", label)
+    };
+    (distance, message)
+}
+
+/// 虚词频次向量的余弦相似度；两边都没有虚词时视为一致
+fn cosine(expected: &StyleFingerprint, actual: &StyleFingerprint) -> f64 {
+    let dot: f64 = expected
+        .function_words
+        .iter()
+        .filter_map(|(w, e)| actual.function_words.get(w).map(|a| e * a))
+        .sum();
+    let norm = |fp: &StyleFingerprint| {
+        fp.function_words
+            .values()
+            .map(|v| v * v)
+            .sum::<f64>()
+            .sqrt()
+    };
+    let (ne, na) = (norm(expected), norm(actual));
+    if ne == 0.0 && na == 0.0 {
+        1.0
+    } else if ne == 0.0 || na == 0.0 {
+        0.0
+    } else {
+        (dot / (ne * na)).clamp(0.0, 1.0)
+    }
+}
@@ -0,0 +1,201 @@
+use crate::models::fingerprint::{LengthDistribution, PunctuationProfile, StyleFingerprint};
+use crate::services::tokens::is_cjk;
+use std::collections::BTreeMap;
+
+/// 统计的中文虚词（按子串计数）
+const ZH_FUNCTION_WORDS: &[&str] = &[
+    "的", "了", "着", "过", "就", "也", "都", "还", "而", "但", "却", "其实", "所以", "因为",
+    "然后", "于是", "可能", "吧", "呢", "吗", "啊",
+];
+
+/// 统计的英文虚词（按整词计数，忽略大小写）
+const EN_FUNCTION_WORDS: &[&str] = &[
+    "the", "a", "an", "of", "and", "but", "so", "i", "we", "you", "just", "really", "actually",
+];
+
+const FULL_WIDTH_PUNCTUATION: &str = "，。！？；：、“”‘’（）《》「」—…";
+const HALF_WIDTH_PUNCTUATION: &str = ",.!?;:\"()";
+
+/// 从一组原创样本中计算文风指纹
+pub fn fingerprint(samples: &[String]) -> StyleFingerprint {
+    let text = samples.join("\n\n");
+    let char_count = text.chars().filter(|c| !c.is_whitespace()).count();
+    let per_k = |n: usize| {
+        if char_count == 0 {
+            0.0
+        } else {
+            round_to(n as f64 * 1000.0 / char_count as f64, 100.0)
+        }
+    };
+
+    let sentences: Vec<usize> = samples.iter().flat_map(|s| sentence_lengths(s)).collect();
+    let paragraphs: Vec<usize> = samples.iter().flat_map(|s| paragraph_lengths(s)).collect();
+
+    let (full, half) = text.chars().fold((0usize, 0usize), |(full, half), c| {
+        if FULL_WIDTH_PUNCTUATION.contains(c) {
+            (full + 1, half)
+        } else if HALF_WIDTH_PUNCTUATION.contains(c) {
+            (full, half + 1)
+        } else {
+            (full, half)
+        }
+    });
+    let count_chars = |set: &str| text.chars().filter(|c| set.contains(*c)).count();
+    let punctuation = PunctuationProfile {
+        full_width_ratio: ratio(full, full + half),
+        comma_per_k: per_k(count_chars("，,、")),
+        period_per_k: per_k(text.matches('。').count() + text.matches(". ").count()),
+        question_per_k: per_k(count_chars("？?")),
+        exclamation_per_k: per_k(count_chars("！!")),
+        em_dash_per_k: per_k(count_runs(&text, '—')),
+        ellipsis_per_k: per_k(count_runs(&text, '…') + text.matches("...").count()),
+        quote_per_k: per_k(count_chars("“「\"") - text.matches('"').count() / 2),
+    };
+
+    let latin_words: Vec<String> = text
+        .split(|c: char| !c.is_ascii_alphabetic() && c != '\'')
+        .filter(|w| w.chars().any(|c| c.is_ascii_alphabetic()))
+        .map(|w| w.to_lowercase())
+        .collect();
+    let mut function_words = BTreeMap::new();
+    for word in ZH_FUNCTION_WORDS {
+        let count = text.matches(word).count();
+        if count > 0 {
+            function_words.insert(word.to_string(), per_k(count));
+        }
+    }
+    for word in EN_FUNCTION_WORDS {
+        let count = latin_words.iter().filter(|w| w == word).count();
+        if count > 0 {
+            function_words.insert(word.to_string(), per_k(count));
+        }
+    }
+
+    let cjk = text
+        .chars()
+        .filter(|c| is_cjk(*c) && c.is_alphanumeric())
+        .count();
+    let latin = text.chars().filter(|c| c.is_ascii_alphabetic()).count();
+
+    StyleFingerprint {
+        sample_count: samples.len(),
+        char_count,
+        sentence_length: distribution(&sentences),
+        paragraph_length: distribution(&paragraphs),
+        punctuation,
+        function_words,
+        cjk_ratio: ratio(cjk, cjk + latin),
+        latin_words_per_k: per_k(latin_words.len()),
+    }
+}
+
+/// 每个句子的长度（非空白字符数）。以句末标点或换行断句，标题行不计入；
+/// 英文句点只在其后是空白或文末时断句，避免拆开小数和缩写中的点
+pub fn sentence_lengths(text: &str) -> Vec<usize> {
+    let mut lengths = Vec::new();
+    for line in text.lines().map(str::trim) {
+        if line.starts_with('#') {
+            continue;
+        }
+        let chars: Vec<char> = line.chars().filter(|c| *c != '\r').collect();
+        let mut current = 0usize;
+        // 已遇到句末标点，等到下一个正文字符出现时断句（句末的引号、连续标点归入本句）
+        let mut pending = false;
+        for (i, &c) in chars.iter().enumerate() {
+            if c.is_whitespace() {
+                continue;
+            }
+            let closes = matches!(
+                c,
+                '。' | '！' | '？' | '!' | '?' | '；' | ';' | '”' | '」' | '"'
+            );
+            if pending && !closes {
+                if current > 1 {
+                    lengths.push(current);
+                }
+                current = 0;
+                pending = false;
+            }
+            current += 1;
+            pending |= match c {
+                '。' | '！' | '？' | '!' | '?' | '；' | ';' => true,
+                '.' => !matches!(chars.get(i + 1), Some(n) if !n.is_whitespace()),
+                _ => false,
+            };
+        }
+        if current > 1 {
+            lengths.push(current);
+        }
+    }
+    lengths
+}
+
+/// 每个段落的长度（非空白字符数），段落以空行分隔，标题行不计入
+pub fn paragraph_lengths(text: &str) -> Vec<usize> {
+    text.split("\n\n")
+        .map(|p| {
+            p.lines()
+                .filter(|l| !l.trim_start().starts_with('#'))
+                .flat_map(str::chars)
+                .filter(|c| !c.is_whitespace())
+                .count()
+        })
+        .filter(|n| *n > 0)
+        .collect()
+}
+
+/// 计算长度分布；百分位取最近秩
+pub fn distribution(lengths: &[usize]) -> LengthDistribution {
+    if lengths.is_empty() {
+        return LengthDistribution::default();
+    }
+    let mut sorted = lengths.to_vec();
+    sorted.sort_unstable();
+    let n = sorted.len();
+    let percentile = |p: f64| sorted[((p * n as f64).ceil() as usize).clamp(1, n) - 1] as f64;
+    let mean = sorted.iter().sum::<usize>() as f64 / n as f64;
+    let variance = sorted
+        .iter()
+        .map(|&len| (len as f64 - mean).powi(2))
+        .sum::<f64>()
+        / n as f64;
+    let median = if n % 2 == 1 {
+        sorted[n / 2] as f64
+    } else {
+        (sorted[n / 2 - 1] + sorted[n / 2]) as f64 / 2.0
+    };
+    LengthDistribution {
+        count: n,
+        mean: round_to(mean, 10.0),
+        median,
+        p10: percentile(0.1),
+        p90: percentile(0.9),
+        cv: round_to(variance.sqrt() / mean, 100.0),
+    }
+}
+
+/// 按 1/scale 的精度四舍五入（scale = 10.0 保留一位小数）
+pub fn round_to(value: f64, scale: f64) -> f64 {
+    (value * scale).round() / scale
+}
+
+fn ratio(part: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        round_to(part as f64 / total as f64, 100.0)
+    }
+}
+
+/// 连续出现的同一字符记为一次（“——” 是一个破折号）
+fn count_runs(text: &str, target: char) -> usize {
+    let mut count = 0;
+    let mut previous = None;
+    for c in text.chars() {
+        if c == target && previous != Some(target) {
+            count += 1;
+        }
+        previous = Some(c);
+    }
+    count
+}
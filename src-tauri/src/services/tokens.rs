@@ -0,0 +1,192 @@
+use crate::services::llm_service::LlmProvider;
+use std::ops::Range;
+
+/// 粗略估算文本的 token 数：中日韩字符约 1 token/字，其余字符约 4 字符/token
+pub fn estimate_tokens(text: &str) -> usize {
+    TokenEstimator::DEFAULT.estimate(text)
+}
+
+/// Ollama 的 OpenAI 兼容接口无法指定 num_ctx，服务端默认只开这么大的上下文
+const OLLAMA_DEFAULT_CONTEXT: usize = 4096;
+
+/// 某个模型的 token 估算参数：不同分词器对中文的切分粒度差别很大
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TokenEstimator {
+    /// 每个中日韩字符约占的 token 数
+    pub cjk_tokens_per_char: f64,
+    /// 每个 token 约对应的其他字符数
+    pub chars_per_token: f64,
+    /// 上下文窗口（输入与输出合计）
+    pub context_window: usize,
+}
+
+impl TokenEstimator {
+    pub const DEFAULT: TokenEstimator = TokenEstimator::new(1.0, 4.0, 8192);
+
+    const fn new(cjk_tokens_per_char: f64, chars_per_token: f64, context_window: usize) -> Self {
+        TokenEstimator {
+            cjk_tokens_per_char,
+            chars_per_token,
+            context_window,
+        }
+    }
+
+    /// 按模型名（不区分大小写的子串，o 系列按名称开头）选择估算参数，未知模型按服务商取默认值
+    pub fn for_model(provider: &str, model: &str) -> Self {
+        let model = model.to_lowercase();
+        let gpt_4o = TokenEstimator::new(0.8, 4.0, 128_000);
+        let known: &[(&[&str], TokenEstimator)] = &[
+            (&["gpt-4o", "gpt-4.1", "gpt-5"], gpt_4o),
+            (&["gpt-4-turbo"], TokenEstimator::new(1.1, 4.0, 128_000)),
+            (&["gpt-4"], TokenEstimator::new(1.1, 4.0, 8192)),
+            (&["gpt-3.5"], TokenEstimator::new(1.1, 4.0, 16_385)),
+            (&["claude"], TokenEstimator::new(1.3, 3.5, 200_000)),
+            (&["deepseek"], TokenEstimator::new(0.6, 4.0, 64_000)),
+            (&["qwen"], TokenEstimator::new(0.7, 4.0, 32_768)),
+            (&["glm"], TokenEstimator::new(0.7, 4.0, 128_000)),
+            (
+                &["mistral", "mixtral"],
+                TokenEstimator::new(1.3, 3.8, 32_768),
+            ),
+            (&["llama"], TokenEstimator::new(1.3, 4.0, 8192)),
+            (&["gemma"], TokenEstimator::new(1.0, 4.0, 8192)),
+        ];
+        let by_provider = match LlmProvider::from_str(provider) {
+            LlmProvider::OpenAI => TokenEstimator::new(0.8, 4.0, 128_000),
+            LlmProvider::Claude => TokenEstimator::new(1.3, 3.5, 200_000),
+            LlmProvider::DeepSeek => TokenEstimator::new(0.6, 4.0, 64_000),
+            LlmProvider::Ollama | LlmProvider::Custom => TokenEstimator::DEFAULT,
+        };
+        let mut estimator = if is_openai_reasoning_model(&model) {
+            gpt_4o
+        } else {
+            known
+                .iter()
+                .find(|(names, _)| names.iter().any(|n| model.contains(n)))
+                .map_or(by_provider, |(_, e)| *e)
+        };
+        if matches!(LlmProvider::from_str(provider), LlmProvider::Ollama) {
+            estimator.context_window = estimator.context_window.min(OLLAMA_DEFAULT_CONTEXT);
+        }
+        estimator
+    }
+
+    /// 估算文本的 token 数
+    pub fn estimate(&self, text: &str) -> usize {
+        let (cjk, other) = count_chars(text);
+        self.tokens_for(cjk, other)
+    }
+
+    fn tokens_for(&self, cjk: usize, other: usize) -> usize {
+        (cjk as f64 * self.cjk_tokens_per_char + other as f64 / self.chars_per_token).ceil()
+            as usize
+    }
+
+    /// 截断文本使其估算 token 数不超过预算（按字符截断，保留开头部分）
+    pub fn truncate(&self, text: &str, budget: usize) -> String {
+        text[..self.prefix_len(text.char_indices(), budget)].to_string()
+    }
+
+    /// 保留开头约 2/3、结尾约 1/3，中间以省略说明代替，使整体不超过预算
+    pub fn truncate_middle(&self, text: &str, budget: usize) -> String {
+        if self.estimate(text) <= budget {
+            return text.to_string();
+        }
+        let omitted = |n: usize| format!("\n\n（中间省略约 {} 字）\n\n", n);
+        let marker_tokens = self.estimate(&omitted(text.chars().count()));
+        let available = budget.saturating_sub(marker_tokens);
+        let head_end = self.prefix_len(text.char_indices(), available * 2 / 3);
+        let tail_budget = available - available * 2 / 3;
+        // 从末尾往前数，得到结尾部分的起始位置
+        let tail_len = self.prefix_len(
+            text.char_indices()
+                .rev()
+                .map(|(i, c)| (text.len() - i - c.len_utf8(), c)),
+            tail_budget,
+        );
+        let tail_start = (text.len() - tail_len).max(head_end);
+        let omitted_chars = text[head_end..tail_start].chars().count();
+        format!(
+            "{}{}{}",
+            &text[..head_end],
+            omitted(omitted_chars),
+            &text[tail_start..]
+        )
+    }
+
+    /// 依次累加字符，返回不超过预算的最长前缀的字节长度
+    fn prefix_len(&self, chars: impl Iterator<Item = (usize, char)>, budget: usize) -> usize {
+        let (mut cjk, mut other) = (0usize, 0usize);
+        let mut end = 0;
+        for (i, c) in chars {
+            if is_cjk(c) {
+                cjk += 1;
+            } else {
+                other += 1;
+            }
+            if self.tokens_for(cjk, other) > budget {
+                break;
+            }
+            end = i + c.len_utf8();
+        }
+        end
+    }
+}
+
+/// o1 / o3 / o4 系列名称很短，只认名称开头（允许带 `openai/` 等路由前缀），
+/// 避免名称中恰好含有这些片段的其他模型被误判
+fn is_openai_reasoning_model(model: &str) -> bool {
+    let name = model.rsplit('/').next().unwrap_or(model);
+    ["o1", "o3", "o4"].iter().any(|series| {
+        name.strip_prefix(series)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('-'))
+    })
+}
+
+fn count_chars(text: &str) -> (usize, usize) {
+    text.chars().fold((0usize, 0usize), |(cjk, other), c| {
+        if is_cjk(c) {
+            (cjk + 1, other)
+        } else {
+            (cjk, other + 1)
+        }
+    })
+}
+
+/// 中日韩文字及全角标点
+pub fn is_cjk(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x3000..=0x303F     // CJK 标点
+            | 0x3040..=0x30FF // 日文假名
+            | 0x3400..=0x4DBF // CJK 扩展 A
+            | 0x4E00..=0x9FFF // CJK 统一汉字
+            | 0xAC00..=0xD7AF // 韩文音节
+            | 0xFF00..=0xFFEF // 全角字符
+    )
+}
+
+/// 按顺序把若干文本贪心地分成连续的批次，每批按目标模型估算的 token 数不超过预算；
+/// 单个超出预算的文本独占一批
+pub fn batch_by_tokens<S: AsRef<str>>(
+    items: &[S],
+    budget: usize,
+    estimator: &TokenEstimator,
+) -> Vec<Range<usize>> {
+    let mut batches = Vec::new();
+    let mut start = 0;
+    let mut used = 0;
+    for (i, item) in items.iter().enumerate() {
+        let tokens = estimator.estimate(item.as_ref());
+        if i > start && used + tokens > budget {
+            batches.push(start..i);
+            start = i;
+            used = 0;
+        }
+        used += tokens;
+    }
+    if start < items.len() {
+        batches.push(start..items.len());
+    }
+    batches
+}
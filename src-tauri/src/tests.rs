@@ -3,9 +3,25 @@
 //! 使用内存 SQLite 数据库，覆盖以下功能：
 //! - 数据库初始化与 Schema 创建
 //! - Skill CRUD（创建/读取/更新/删除）
-//! - 版本管理（创建版本、进化版本、版本查询）
+//! - 版本管理（创建版本、进化版本、版本查询、版本回滚、版本对比）
 //! - 导出功能（Markdown / JSON）
 //! - Diff 计算（文本差异计算）
+//! - LLM 配置档案与任务路由
+//! - LLM Service（本地 Mock HTTP 服务器模拟 OpenAI / Anthropic 接口）
+//! - 结构化输出（JSON 提取、校验与修复）
+//! - SkillSpec 解析、校验与 Markdown 渲染
+//! - Diff 规则合并、进化预览与逐条批准
+//! - 规则出处（skill_rule）与置信度
+//! - Skill 压缩（去重与 token 预算）
+//! - 规则一致性检查
+//! - 本地禁用规则检查（lint）
+//! - AI 味评分（内置套话词典与结构信号）
+//! - 样本量化文风指纹（stylometry）与风格匹配评分
+//! - 原创样本管理与批量导入
+//! - 大样本集的分批（map-reduce）风格分析
+//! - 按模型估算 token 与提示词上下文预算
+//! - 按主题检索原创样本片段作为生成示例（BM25）
+//! - 向量索引与语义检索（远端 embeddings 与本地哈希向量）
 
 #[cfg(test)]
 mod tests {
@@ -53,6 +69,10 @@ mod tests {
             tables.contains(&"original_sample".to_string()),
             "缺少 original_sample 表"
         );
+        assert!(
+            tables.contains(&"skill_rule".to_string()),
+            "缺少 skill_rule 表"
+        );
     }
 
     #[test]
@@ -794,13 +814,14 @@ mod tests {
             )
             .unwrap();
 
-        // 模拟导出逻辑中的 fallback
-        let parsed = serde_json::from_str::<serde_json::Value>(&content_json)
-            .unwrap_or(serde_json::Value::Null);
-        assert_eq!(
-            parsed,
-            serde_json::Value::Null,
-            "无效 JSON 应 fallback 为 Null"
+        // 导出时损坏的结构化内容应被拒绝，而不是静默变成 null
+        assert!(
+            SkillSpec::from_content_json(&content_json).is_err(),
+            "无效 JSON 应返回错误"
+        );
+        assert!(
+            crate::commands::skill::load_current_spec(&conn, skill_id).is_err(),
+            "读取当前版本结构化内容应报错"
         );
     }
 
@@ -1078,6 +1099,99 @@ mod tests {
         assert_eq!(model, "gpt-4o", "未修改的 model 应保持默认值");
     }
 
+    // ---------- LLM 配置档案与任务路由测试 ----------
+
+    use crate::commands::llm::load_llm_config;
+    use crate::models::llm::LlmTask;
+
+    /// 辅助：插入配置档案并返回 ID
+    fn insert_profile(conn: &Connection, name: &str, provider: &str, model: &str) -> i64 {
+        conn.execute(
+            "INSERT INTO llm_profile (name, provider, endpoint, model) VALUES (?1, ?2, 'http://localhost:11434/v1', ?3)",
+            rusqlite::params![name, provider, model],
+        )
+        .unwrap();
+        conn.last_insert_rowid()
+    }
+
+    fn route_task(conn: &Connection, task: LlmTask, profile_id: Option<i64>) {
+        conn.execute(
+            "INSERT INTO llm_task_route (task, profile_id) VALUES (?1, ?2)
+             ON CONFLICT(task) DO UPDATE SET profile_id = excluded.profile_id",
+            rusqlite::params![task.as_str(), profile_id],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_unrouted_task_uses_default_config() {
+        let conn = setup_db();
+        let config = load_llm_config(&conn, LlmTask::Generate).unwrap();
+        assert_eq!(config.provider, "openai");
+        assert_eq!(config.model, "gpt-4o");
+    }
+
+    #[test]
+    fn test_task_routed_to_profile() {
+        let conn = setup_db();
+        let local = insert_profile(&conn, "本地 Ollama", "ollama", "qwen2.5:7b");
+        route_task(&conn, LlmTask::DiffAnalyze, Some(local));
+
+        let diff_config = load_llm_config(&conn, LlmTask::DiffAnalyze).unwrap();
+        assert_eq!(diff_config.provider, "ollama");
+        assert_eq!(diff_config.model, "qwen2.5:7b");
+
+        // 其它任务不受影响
+        let gen_config = load_llm_config(&conn, LlmTask::Generate).unwrap();
+        assert_eq!(gen_config.model, "gpt-4o");
+    }
+
+    #[test]
+    fn test_route_reset_to_default() {
+        let conn = setup_db();
+        let local = insert_profile(&conn, "本地", "ollama", "llama3");
+        route_task(&conn, LlmTask::AnalyzeStyle, Some(local));
+        route_task(&conn, LlmTask::AnalyzeStyle, None);
+
+        let config = load_llm_config(&conn, LlmTask::AnalyzeStyle).unwrap();
+        assert_eq!(config.model, "gpt-4o", "清空路由后应回落到默认配置");
+    }
+
+    #[test]
+    fn test_delete_profile_falls_back_to_default() {
+        let conn = setup_db();
+        let hosted = insert_profile(&conn, "正式稿", "claude", "claude-sonnet-4-20250514");
+        route_task(&conn, LlmTask::Generate, Some(hosted));
+
+        conn.execute("DELETE FROM llm_profile WHERE id = ?1", [hosted])
+            .unwrap();
+
+        let profile_id: Option<i64> = conn
+            .query_row(
+                "SELECT profile_id FROM llm_task_route WHERE task = 'generate'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(
+            profile_id, None,
+            "删除档案应将路由置空（ON DELETE SET NULL）"
+        );
+
+        let config = load_llm_config(&conn, LlmTask::Generate).unwrap();
+        assert_eq!(config.provider, "openai");
+    }
+
+    #[test]
+    fn test_task_serde_names() {
+        assert_eq!(
+            serde_json::to_value(LlmTask::DiffAnalyze).unwrap(),
+            "diff_analyze"
+        );
+        let task: LlmTask = serde_json::from_value(serde_json::json!("analyze_style")).unwrap();
+        assert_eq!(task, LlmTask::AnalyzeStyle);
+    }
+
     // ---------- Diff Record 数据库测试 ----------
 
     #[test]
@@ -1124,4 +1238,2906 @@ mod tests {
             .unwrap();
         assert_eq!(count, 0, "删除文章应级联删除 diff_record");
     }
+
+    // ================================================================
+    // ========== LLM Service 测试（本地 Mock HTTP 服务器） ==========
+    // ================================================================
+
+    use crate::services::llm_service::{self, ChatMessage, LlmConfig};
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+
+    /// Mock 服务器收到的请求
+    struct CapturedRequest {
+        request_line: String,
+        headers: Vec<(String, String)>,
+        body: String,
+    }
+
+    impl CapturedRequest {
+        fn header(&self, name: &str) -> Option<&str> {
+            self.headers
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(name))
+                .map(|(_, v)| v.as_str())
+        }
+
+        fn json(&self) -> serde_json::Value {
+            serde_json::from_str(&self.body).expect("请求体应为 JSON")
+        }
+    }
+
+    /// 启动本地 Mock HTTP 服务器，按顺序返回预设响应 (status, content_type, body)
+    /// 返回服务器地址和已捕获请求的接收端
+    fn spawn_mock_server(
+        responses: Vec<(u16, &'static str, String)>,
+    ) -> (String, mpsc::Receiver<CapturedRequest>) {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("绑定端口失败");
+        let addr = format!("http://{}", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel();
+
+        std::thread::spawn(move || {
+            for (status, content_type, body) in responses {
+                let (mut stream, _) = match listener.accept() {
+                    Ok(s) => s,
+                    Err(_) => return,
+                };
+                let request = read_http_request(&mut stream);
+                let _ = tx.send(request);
+
+                let response = format!(
+                    "HTTP/1.1 {} Mock\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    content_type,
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes());
+                let _ = stream.flush();
+            }
+        });
+
+        (addr, rx)
+    }
+
+    /// 读取完整 HTTP 请求（请求头 + Content-Length 指定的请求体）
+    fn read_http_request(stream: &mut std::net::TcpStream) -> CapturedRequest {
+        let mut buf = Vec::new();
+        let mut chunk = [0u8; 4096];
+        let header_end = loop {
+            let n = stream.read(&mut chunk).unwrap_or(0);
+            if n == 0 {
+                break buf.len();
+            }
+            buf.extend_from_slice(&chunk[..n]);
+            if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                break pos + 4;
+            }
+        };
+
+        let head = String::from_utf8_lossy(&buf[..header_end]).to_string();
+        let mut lines = head.split("\r\n");
+        let request_line = lines.next().unwrap_or_default().to_string();
+        let headers: Vec<(String, String)> = lines
+            .filter_map(|l| l.split_once(':'))
+            .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+            .collect();
+
+        let content_length = headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case("content-length"))
+            .and_then(|(_, v)| v.parse::<usize>().ok())
+            .unwrap_or(0);
+
+        while buf.len() < header_end + content_length {
+            let n = stream.read(&mut chunk).unwrap_or(0);
+            if n == 0 {
+                break;
+            }
+            buf.extend_from_slice(&chunk[..n]);
+        }
+
+        CapturedRequest {
+            request_line,
+            headers,
+            body: String::from_utf8_lossy(&buf[header_end..]).to_string(),
+        }
+    }
+
+    fn mock_config(provider: &str, endpoint: &str) -> LlmConfig {
+        LlmConfig {
+            provider: provider.to_string(),
+            endpoint: endpoint.to_string(),
+            api_key: "test-key".to_string(),
+            model: "test-model".to_string(),
+        }
+    }
+
+    fn user_message(content: &str) -> ChatMessage {
+        ChatMessage {
+            role: "user".to_string(),
+            content: content.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_openai_chat_completion() {
+        let (addr, rx) = spawn_mock_server(vec![(
+            200,
+            "application/json",
+            r#"{"choices":[{"message":{"role":"assistant","content":"你好"}}]}"#.to_string(),
+        )]);
+        let config = mock_config("openai", &format!("{}/v1/", addr));
+
+        let result = llm_service::chat_completion(&config, vec![user_message("hi")], 0.5)
+            .await
+            .expect("OpenAI 兼容请求应成功");
+        assert_eq!(result, "你好");
+
+        let req = rx.recv().unwrap();
+        assert_eq!(req.request_line, "POST /v1/chat/completions HTTP/1.1");
+        assert_eq!(req.header("authorization"), Some("Bearer test-key"));
+        let body = req.json();
+        assert_eq!(body["model"], "test-model");
+        assert_eq!(body["messages"][0]["content"], "hi");
+    }
+
+    #[tokio::test]
+    async fn test_claude_uses_messages_api() {
+        let (addr, rx) = spawn_mock_server(vec![(
+            200,
+            "application/json",
+            r#"{"id":"msg_1","type":"message","role":"assistant","content":[{"type":"text","text":"第一段"},{"type":"text","text":"第二段"}],"stop_reason":"end_turn"}"#.to_string(),
+        )]);
+        let config = mock_config("claude", &format!("{}/v1", addr));
+
+        let messages = vec![
+            ChatMessage {
+                role: "system".to_string(),
+                content: "你是写作助手".to_string(),
+            },
+            user_message("写一句话"),
+        ];
+        let result = llm_service::chat_completion(&config, messages, 0.7)
+            .await
+            .expect("Anthropic 请求应成功");
+        assert_eq!(result, "第一段第二段", "应拼接所有文本内容块");
+
+        let req = rx.recv().unwrap();
+        assert_eq!(req.request_line, "POST /v1/messages HTTP/1.1");
+        assert_eq!(req.header("x-api-key"), Some("test-key"));
+        assert_eq!(req.header("anthropic-version"), Some("2023-06-01"));
+        assert!(
+            req.header("authorization").is_none(),
+            "Claude 不应发送 Bearer 头"
+        );
+
+        let body = req.json();
+        assert_eq!(
+            body["system"], "你是写作助手",
+            "system 消息应提取为顶层字段"
+        );
+        assert!(
+            body["max_tokens"].as_u64().unwrap() > 0,
+            "max_tokens 为必填"
+        );
+        let sent = body["messages"].as_array().unwrap();
+        assert_eq!(sent.len(), 1, "messages 中不应再包含 system 消息");
+        assert_eq!(sent[0]["role"], "user");
+    }
+
+    #[tokio::test]
+    async fn test_claude_without_system_omits_field() {
+        let (addr, rx) = spawn_mock_server(vec![(
+            200,
+            "application/json",
+            r#"{"content":[{"type":"text","text":"OK"}]}"#.to_string(),
+        )]);
+        let config = mock_config("claude", &addr);
+
+        let result = llm_service::test_connection(&config).await.unwrap();
+        assert_eq!(result, "OK");
+
+        let body = rx.recv().unwrap().json();
+        assert!(
+            body.get("system").is_none(),
+            "无 system 消息时不应发送该字段"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_claude_max_tokens_follows_model_limit() {
+        let (addr, rx) = spawn_mock_server(vec![(
+            200,
+            "application/json",
+            r#"{"content":[{"type":"text","text":"OK"}]}"#.to_string(),
+        )]);
+        let mut config = mock_config("claude", &addr);
+        config.model = "claude-3-haiku-20240307".to_string();
+
+        llm_service::chat_completion(&config, vec![user_message("hi")], 0.0)
+            .await
+            .expect("Anthropic 请求应成功");
+        let body = rx.recv().unwrap().json();
+        assert_eq!(
+            body["max_tokens"], 4096,
+            "Claude 3 初代模型的输出上限为 4096"
+        );
+
+        assert_eq!(llm_service::anthropic_max_tokens("claude-3-opus-20240229"), 4096);
+        assert_eq!(llm_service::anthropic_max_tokens("claude-3-sonnet-20240229"), 4096);
+        assert_eq!(
+            llm_service::anthropic_max_tokens("claude-3-5-sonnet-20241022"),
+            8192,
+            "3.5 及之后的模型可使用更高上限"
+        );
+        assert_eq!(llm_service::anthropic_max_tokens("claude-3-5-haiku-latest"), 8192);
+        assert_eq!(llm_service::anthropic_max_tokens("claude-sonnet-4-20250514"), 8192);
+    }
+
+    #[tokio::test]
+    async fn test_claude_ignores_non_text_blocks() {
+        let (addr, _rx) = spawn_mock_server(vec![(
+            200,
+            "application/json",
+            r#"{"content":[{"type":"thinking","thinking":"..."},{"type":"text","text":"正文"}]}"#
+                .to_string(),
+        )]);
+        let config = mock_config("claude", &addr);
+
+        let result = llm_service::chat_completion(&config, vec![user_message("hi")], 0.0)
+            .await
+            .unwrap();
+        assert_eq!(result, "正文");
+    }
+
+    #[tokio::test]
+    async fn test_claude_empty_content_is_error() {
+        let (addr, _rx) = spawn_mock_server(vec![(
+            200,
+            "application/json",
+            r#"{"content":[]}"#.to_string(),
+        )]);
+        let config = mock_config("claude", &addr);
+
+        let result = llm_service::chat_completion(&config, vec![user_message("hi")], 0.0).await;
+        assert!(result.is_err(), "空内容块应返回错误");
+    }
+
+    #[tokio::test]
+    async fn test_claude_api_error_status() {
+        let (addr, _rx) = spawn_mock_server(vec![(
+            401,
+            "application/json",
+            r#"{"type":"error","error":{"type":"authentication_error","message":"invalid x-api-key"}}"#.to_string(),
+        )]);
+        let config = mock_config("claude", &addr);
+
+        let err = llm_service::chat_completion(&config, vec![user_message("hi")], 0.0)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), "auth", "401 应归类为认证错误");
+        assert!(!err.is_retryable(), "认证错误不应重试");
+        let msg = err.to_string();
+        assert!(msg.contains("401"), "错误信息应包含状态码: {}", msg);
+        assert!(
+            msg.contains("invalid x-api-key"),
+            "错误信息应包含响应体: {}",
+            msg
+        );
+    }
+
+    // ---------- 流式输出（SSE）测试 ----------
+
+    use crate::services::job_registry::{CancelToken, JobRegistry, JobStatus, JOB_CANCELLED};
+
+    #[tokio::test]
+    async fn test_openai_stream_collects_deltas() {
+        let body = concat!(
+            "data: {\"choices\":[{\"delta\":{\"role\":\"assistant\"}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"你\"}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"好\"}}]}\r\n\r\n",
+            "data: [DONE]\n\n",
+        );
+        let (addr, rx) = spawn_mock_server(vec![(200, "text/event-stream", body.to_string())]);
+        let config = mock_config("openai", &addr);
+        let cancel = CancelToken::default();
+
+        let mut deltas = Vec::new();
+        let outcome = llm_service::chat_completion_stream(
+            &config,
+            vec![user_message("hi")],
+            0.7,
+            &cancel,
+            |d| deltas.push(d.to_string()),
+        )
+        .await
+        .expect("流式请求应成功");
+
+        assert_eq!(outcome.content, "你好");
+        assert!(!outcome.cancelled);
+        assert_eq!(deltas, vec!["你", "好"]);
+
+        let req = rx.recv().unwrap();
+        assert_eq!(req.request_line, "POST /chat/completions HTTP/1.1");
+        assert_eq!(req.json()["stream"], true);
+    }
+
+    #[tokio::test]
+    async fn test_anthropic_stream_collects_text_deltas() {
+        let body = concat!(
+            "event: message_start\ndata: {\"type\":\"message_start\",\"message\":{}}\n\n",
+            "event: content_block_start\ndata: {\"type\":\"content_block_start\",\"index\":0}\n\n",
+            "event: ping\ndata: {\"type\":\"ping\"}\n\n",
+            "event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Hello\"}}\n\n",
+            "event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\" world\"}}\n\n",
+            "event: message_stop\ndata: {\"type\":\"message_stop\"}\n\n",
+        );
+        let (addr, rx) = spawn_mock_server(vec![(200, "text/event-stream", body.to_string())]);
+        let config = mock_config("claude", &addr);
+        let cancel = CancelToken::default();
+
+        let outcome = llm_service::chat_completion_stream(
+            &config,
+            vec![user_message("hi")],
+            0.7,
+            &cancel,
+            |_| {},
+        )
+        .await
+        .expect("Anthropic 流式请求应成功");
+        assert_eq!(outcome.content, "Hello world");
+
+        let req = rx.recv().unwrap();
+        assert_eq!(req.request_line, "POST /messages HTTP/1.1");
+        assert_eq!(req.json()["stream"], true);
+    }
+
+    #[tokio::test]
+    async fn test_anthropic_stream_error_event() {
+        let body = "event: error\ndata: {\"type\":\"error\",\"error\":{\"type\":\"overloaded_error\",\"message\":\"Overloaded\"}}\n\n";
+        let (addr, _rx) = spawn_mock_server(vec![(200, "text/event-stream", body.to_string())]);
+        let config = mock_config("claude", &addr);
+        let cancel = CancelToken::default();
+
+        let err = llm_service::chat_completion_stream(
+            &config,
+            vec![user_message("hi")],
+            0.7,
+            &cancel,
+            |_| {},
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.kind(), "server", "overloaded_error 应归类为服务端过载");
+        assert!(
+            err.to_string().contains("Overloaded"),
+            "应透传流内错误事件: {}",
+            err
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stream_cancelled_before_first_chunk() {
+        let body =
+            "data: {\"choices\":[{\"delta\":{\"content\":\"不会收到\"}}]}\n\ndata: [DONE]\n\n";
+        let (addr, _rx) = spawn_mock_server(vec![(200, "text/event-stream", body.to_string())]);
+        let config = mock_config("openai", &addr);
+        let cancel = CancelToken::default();
+        cancel.cancel();
+
+        let outcome = llm_service::chat_completion_stream(
+            &config,
+            vec![user_message("hi")],
+            0.7,
+            &cancel,
+            |_| {},
+        )
+        .await
+        .unwrap();
+        assert!(outcome.cancelled, "取消标记应提前结束流");
+        assert_eq!(outcome.content, "");
+    }
+
+    #[test]
+    fn test_partial_stream_output_is_kept() {
+        use crate::commands::article::save_partial_article;
+
+        let conn = setup_db();
+        conn.execute(
+            "INSERT INTO article (title, status) VALUES ('中断的文章', 'generating')",
+            [],
+        )
+        .unwrap();
+        let article_id = conn.last_insert_rowid();
+
+        save_partial_article(&conn, article_id, "已经生成的前半段").unwrap();
+        let article = get_article_by_id(&conn, article_id).unwrap();
+        assert_eq!(article.status, "partial");
+        assert_eq!(article.ai_generated_content, "已经生成的前半段");
+        assert!(
+            article.ai_flavor_generated.is_some(),
+            "保存时一并计算 AI 味评分"
+        );
+    }
+
+    #[test]
+    fn test_ranked_skill_content_propagates_spec_errors() {
+        use crate::commands::article::ranked_skill_content;
+
+        let conn = setup_db();
+        let skill_id = insert_skill(&conn, "排序", "通用", "");
+        insert_version(&conn, skill_id, 1, "# 手写内容", "{}", "初始版本");
+        assert_eq!(
+            ranked_skill_content(&conn, skill_id, "# 手写内容".to_string()).unwrap(),
+            "# 手写内容",
+            "没有结构化内容时原样使用 Markdown"
+        );
+
+        conn.execute(
+            "UPDATE skill_version SET content_json = '{\"role\": ' WHERE skill_id = ?1",
+            [skill_id],
+        )
+        .unwrap();
+        assert!(
+            ranked_skill_content(&conn, skill_id, "# 手写内容".to_string()).is_err(),
+            "结构化内容损坏时不应静默退回未排序的内容"
+        );
+    }
+
+    // ---------- 任务注册表测试 ----------
+
+    #[test]
+    fn test_job_registry_assigns_ids_and_lists() {
+        let registry = JobRegistry::default();
+        let a = registry.start("generate_article", "主题 A");
+        let b = registry.start("analyze_diff", "文章 #1");
+        assert_ne!(a.info().id, b.info().id, "任务 ID 应唯一");
+
+        let jobs = registry.list();
+        assert_eq!(jobs.len(), 2);
+        assert!(jobs.iter().all(|j| j.status == JobStatus::Running));
+    }
+
+    #[test]
+    fn test_job_registry_cancel_unknown_job() {
+        let registry = JobRegistry::default();
+        assert!(!registry.cancel(42), "取消不存在的任务应返回 false");
+    }
+
+    #[test]
+    fn test_job_guard_drop_moves_to_history() {
+        let registry = JobRegistry::default();
+        let id = {
+            let mut job = registry.start("generate_article", "主题");
+            job.set_status(JobStatus::Completed);
+            job.info().id
+        };
+
+        assert!(!registry.cancel(id), "已结束的任务不可再取消");
+        let jobs = registry.list();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].status, JobStatus::Completed);
+        assert!(jobs[0].finished_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_job_run_completes() {
+        let registry = JobRegistry::default();
+        let mut job = registry.start("analyze_diff", "文章 #1");
+        let result = job.run(async { Ok::<_, String>(7) }).await;
+        assert_eq!(result, Ok(7));
+        drop(job);
+        assert_eq!(registry.list()[0].status, JobStatus::Completed);
+    }
+
+    #[tokio::test]
+    async fn test_job_run_cancelled_drops_future() {
+        let registry = JobRegistry::default();
+        let mut job = registry.start("generate_article", "长文");
+        let id = job.info().id;
+        assert!(registry.cancel(id));
+
+        // 永不完成的 future 也应因取消立即返回
+        let result: Result<(), String> =
+            job.run(std::future::pending::<Result<(), String>>()).await;
+        assert_eq!(result, Err(JOB_CANCELLED.to_string()));
+        drop(job);
+
+        let jobs = registry.list();
+        assert_eq!(jobs[0].status, JobStatus::Cancelled, "取消应被记录到历史");
+    }
+
+    #[tokio::test]
+    async fn test_job_run_failure_recorded() {
+        let registry = JobRegistry::default();
+        let mut job = registry.start("create_skill_with_samples", "Skill");
+        let result: Result<(), String> = job.run(async { Err("LLM 错误".to_string()) }).await;
+        assert!(result.is_err());
+        drop(job);
+        assert_eq!(registry.list()[0].status, JobStatus::Failed);
+    }
+
+    // ---------- 错误分类与重试测试 ----------
+
+    use crate::services::llm_error::{parse_retry_after, LlmError};
+    use crate::services::llm_service::RetryPolicy;
+    use std::time::Duration;
+
+    /// 测试用快速重试策略
+    fn fast_retry_policy(max_retries: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_retries,
+            initial_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(20),
+            multiplier: 2.0,
+        }
+    }
+
+    #[test]
+    fn test_llm_error_classification() {
+        assert_eq!(
+            LlmError::from_status(401, String::new(), None).kind(),
+            "auth"
+        );
+        assert_eq!(
+            LlmError::from_status(403, String::new(), None).kind(),
+            "auth"
+        );
+        assert_eq!(
+            LlmError::from_status(408, String::new(), None).kind(),
+            "timeout"
+        );
+        assert_eq!(
+            LlmError::from_status(429, String::new(), None).kind(),
+            "rate_limited"
+        );
+        assert_eq!(
+            LlmError::from_status(503, String::new(), None).kind(),
+            "server"
+        );
+        assert_eq!(
+            LlmError::from_status(529, String::new(), None).kind(),
+            "server"
+        );
+        assert_eq!(
+            LlmError::from_status(400, String::new(), None).kind(),
+            "bad_request"
+        );
+
+        assert!(LlmError::from_status(429, String::new(), None).is_retryable());
+        assert!(LlmError::from_status(502, String::new(), None).is_retryable());
+        assert!(!LlmError::from_status(400, String::new(), None).is_retryable());
+        assert!(!LlmError::from_status(401, String::new(), None).is_retryable());
+    }
+
+    #[test]
+    fn test_llm_error_serializes_kind() {
+        let err = LlmError::from_status(429, "slow down".to_string(), Some(Duration::from_secs(5)));
+        let value = serde_json::to_value(&err).unwrap();
+        assert_eq!(value["kind"], "rate_limited");
+        assert_eq!(value["retry_after_secs"], 5);
+        assert!(
+            err.to_string().starts_with("[rate_limited]"),
+            "字符串形式应带类别前缀"
+        );
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        assert_eq!(parse_retry_after("7"), Some(Duration::from_secs(7)));
+        assert_eq!(parse_retry_after(" 0 "), Some(Duration::from_secs(0)));
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"),
+            Some(Duration::from_secs(0)),
+            "过去的日期应视为立即重试"
+        );
+        assert_eq!(parse_retry_after("soon"), None);
+    }
+
+    #[test]
+    fn test_retry_policy_backoff() {
+        let policy = RetryPolicy {
+            max_retries: 5,
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(5),
+            multiplier: 2.0,
+        };
+        let err = LlmError::from_status(503, String::new(), None);
+        assert_eq!(policy.delay_for(1, &err), Duration::from_secs(1));
+        assert_eq!(policy.delay_for(2, &err), Duration::from_secs(2));
+        assert_eq!(policy.delay_for(3, &err), Duration::from_secs(4));
+        assert_eq!(
+            policy.delay_for(4, &err),
+            Duration::from_secs(5),
+            "应受 max_delay 限制"
+        );
+
+        let limited = LlmError::from_status(429, String::new(), Some(Duration::from_secs(3)));
+        assert_eq!(
+            policy.delay_for(1, &limited),
+            Duration::from_secs(3),
+            "应优先使用 Retry-After"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_retry_recovers_from_503() {
+        let ok = r#"{"choices":[{"message":{"content":"分析完成"}}]}"#.to_string();
+        let (addr, rx) = spawn_mock_server(vec![
+            (503, "text/plain", "unavailable".to_string()),
+            (429, "text/plain", "slow down".to_string()),
+            (200, "application/json", ok),
+        ]);
+        let config = mock_config("openai", &addr);
+
+        let mut notices = Vec::new();
+        let result = llm_service::chat_completion_with_retry(
+            &config,
+            vec![user_message("hi")],
+            0.3,
+            &fast_retry_policy(3),
+            |n| notices.push((n.attempt, n.error.kind())),
+        )
+        .await
+        .expect("瞬时错误后应重试成功");
+
+        assert_eq!(result, "分析完成");
+        assert_eq!(notices, vec![(1, "server"), (2, "rate_limited")]);
+        assert_eq!(rx.try_iter().count(), 3, "应共发送 3 次请求");
+    }
+
+    #[tokio::test]
+    async fn test_retry_does_not_retry_auth_error() {
+        let (addr, rx) = spawn_mock_server(vec![
+            (401, "text/plain", "bad key".to_string()),
+            (
+                200,
+                "application/json",
+                r#"{"choices":[{"message":{"content":"x"}}]}"#.to_string(),
+            ),
+        ]);
+        let config = mock_config("openai", &addr);
+
+        let mut retried = false;
+        let err = llm_service::chat_completion_with_retry(
+            &config,
+            vec![user_message("hi")],
+            0.3,
+            &fast_retry_policy(3),
+            |_| retried = true,
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!(err.kind(), "auth");
+        assert!(!retried, "认证错误不应触发重试");
+        assert_eq!(rx.try_iter().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_retry_gives_up_after_max_retries() {
+        let (addr, _rx) = spawn_mock_server(vec![
+            (500, "text/plain", "boom".to_string()),
+            (500, "text/plain", "boom".to_string()),
+        ]);
+        let config = mock_config("openai", &addr);
+
+        let err = llm_service::chat_completion_with_retry(
+            &config,
+            vec![user_message("hi")],
+            0.3,
+            &fast_retry_policy(1),
+            |_| {},
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.kind(), "server");
+    }
+
+    #[tokio::test]
+    async fn test_network_error_classified() {
+        // 绑定后立即释放端口，连接会被拒绝
+        let addr = {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            format!("http://{}", listener.local_addr().unwrap())
+        };
+        let config = mock_config("openai", &addr);
+
+        let err = llm_service::chat_completion(&config, vec![user_message("hi")], 0.0)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), "network");
+        assert!(err.is_retryable());
+    }
+
+    // ---------- 结构化输出测试 ----------
+
+    use crate::models::analysis::DiffAnalysis;
+    use crate::models::skill_spec::SkillSpec;
+    use crate::services::structured_output::{self, extract_json, parse_structured};
+
+    const VALID_STYLE_JSON: &str = r#"{
+        "role": {"identity": "独立开发者", "tone": "冷静克制", "audience": "工程师"},
+        "style_principles": ["短句为主", "先结论后论证"],
+        "blocklist": {"forbidden_words": ["赋能"], "forbidden_patterns": [], "forbidden_structures": []},
+        "references": {"terminology": [], "sample_features": []}
+    }"#;
+
+    /// 包装为 OpenAI 兼容的非流式响应
+    fn openai_reply(content: &str) -> String {
+        serde_json::json!({"choices": [{"message": {"content": content}}]}).to_string()
+    }
+
+    #[test]
+    fn test_extract_json_plain() {
+        assert_eq!(extract_json(r#"{"a":1}"#), Some(r#"{"a":1}"#));
+    }
+
+    #[test]
+    fn test_extract_json_strips_code_fence_and_prose() {
+        let text = "好的，分析如下：\n```json\n{\"a\": {\"b\": 2}}\n```\n希望有帮助";
+        assert_eq!(extract_json(text), Some("{\"a\": {\"b\": 2}}"));
+    }
+
+    #[test]
+    fn test_extract_json_ignores_braces_in_strings() {
+        let text = r#"结果：{"s": "含有 } 和 { 的\"字符串\"", "n": 1} 以上"#;
+        assert_eq!(
+            extract_json(text),
+            Some(r#"{"s": "含有 } 和 { 的\"字符串\"", "n": 1}"#)
+        );
+    }
+
+    #[test]
+    fn test_extract_json_unbalanced() {
+        assert_eq!(extract_json(r#"{"a": {"b": 1}"#), None);
+        assert_eq!(extract_json("没有 JSON"), None);
+    }
+
+    #[test]
+    fn test_parse_style_analysis_valid() {
+        let analysis: SkillSpec = parse_structured(VALID_STYLE_JSON).unwrap();
+        assert_eq!(analysis.role.identity, "独立开发者");
+        assert_eq!(analysis.style_principles.len(), 2);
+        assert_eq!(analysis.blocklist.forbidden_words, vec!["赋能"]);
+    }
+
+    #[test]
+    fn test_parse_style_analysis_missing_section() {
+        let err = parse_structured::<SkillSpec>(
+            r#"{"role": {"identity": "a", "tone": "b"}, "style_principles": ["x"]}"#,
+        )
+        .unwrap_err();
+        assert!(err.contains("blocklist"), "应指出缺失的字段: {}", err);
+    }
+
+    #[test]
+    fn test_parse_style_analysis_requires_principles() {
+        let json = VALID_STYLE_JSON.replace(r#"["短句为主", "先结论后论证"]"#, r#"["  "]"#);
+        let err = parse_structured::<SkillSpec>(&json).unwrap_err();
+        assert!(err.contains("style_principles"), "{}", err);
+    }
+
+    #[test]
+    fn test_parse_diff_analysis_sanitizes_blank_rules() {
+        let analysis: DiffAnalysis = parse_structured(
+            r#"{"modification_analysis": [{"type": "词汇替换", "description": "去掉赋能", "intent": "更口语"}],
+                "new_rules": {"add_to_blocklist_words": [" 赋能 ", ""], "add_to_style_principles": []},
+                "summary": "减少套话"}"#,
+        )
+        .unwrap();
+        assert_eq!(analysis.new_rules.add_to_blocklist_words, vec!["赋能"]);
+        assert_eq!(analysis.modification_analysis[0].kind, "词汇替换");
+    }
+
+    #[tokio::test]
+    async fn test_chat_structured_uses_json_mode() {
+        let (addr, rx) = spawn_mock_server(vec![(
+            200,
+            "application/json",
+            openai_reply(VALID_STYLE_JSON),
+        )]);
+        let config = mock_config("openai", &addr);
+
+        let (analysis, json) = structured_output::chat_structured::<SkillSpec, _>(
+            &config,
+            vec![user_message("分析风格，输出 JSON")],
+            0.3,
+            &fast_retry_policy(0),
+            |_| {},
+        )
+        .await
+        .unwrap();
+        assert_eq!(analysis.role.tone, "冷静克制");
+        assert!(
+            serde_json::from_str::<serde_json::Value>(&json).is_ok(),
+            "应返回规范化 JSON"
+        );
+
+        let body = rx.recv().unwrap().json();
+        assert_eq!(body["response_format"]["type"], "json_object");
+    }
+
+    #[tokio::test]
+    async fn test_chat_structured_repairs_invalid_output() {
+        let fenced = format!("```json\n{}\n```", VALID_STYLE_JSON);
+        let (addr, rx) = spawn_mock_server(vec![
+            (
+                200,
+                "application/json",
+                r#"{"content":[{"type":"text","text":"这是分析：{\"role\": 1}"}]}"#.to_string(),
+            ),
+            (
+                200,
+                "application/json",
+                serde_json::json!({"content": [{"type": "text", "text": fenced}]}).to_string(),
+            ),
+        ]);
+        let config = mock_config("claude", &addr);
+
+        let (analysis, _) = structured_output::chat_structured::<SkillSpec, _>(
+            &config,
+            vec![user_message("分析风格")],
+            0.3,
+            &fast_retry_policy(0),
+            |_| {},
+        )
+        .await
+        .expect("修复后应成功");
+        assert_eq!(analysis.style_principles[0], "短句为主");
+
+        let first = rx.recv().unwrap().json();
+        assert!(
+            first.get("response_format").is_none(),
+            "Anthropic 不支持 response_format"
+        );
+
+        let second = rx.recv().unwrap().json();
+        let sent = second["messages"].as_array().unwrap();
+        assert_eq!(sent.len(), 3, "修复请求应带上原输出和错误反馈");
+        assert_eq!(sent[1]["role"], "assistant");
+        assert!(sent[2]["content"].as_str().unwrap().contains("校验"));
+    }
+
+    #[tokio::test]
+    async fn test_chat_structured_gives_up_after_bounded_repairs() {
+        let bad = openai_reply("完全不是 JSON");
+        let (addr, rx) = spawn_mock_server(vec![
+            (200, "application/json", bad.clone()),
+            (200, "application/json", bad.clone()),
+            (200, "application/json", bad),
+        ]);
+        let config = mock_config("openai", &addr);
+
+        let err = structured_output::chat_structured::<DiffAnalysis, _>(
+            &config,
+            vec![user_message("分析 diff")],
+            0.3,
+            &fast_retry_policy(0),
+            |_| {},
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.kind(), "invalid_response");
+        assert_eq!(
+            rx.try_iter().count(),
+            1 + structured_output::MAX_REPAIR_ATTEMPTS,
+            "修复轮数应有上限"
+        );
+    }
+
+    // ---------- SkillSpec 测试 ----------
+
+    use crate::commands::skill::load_current_spec;
+
+    #[test]
+    fn test_skill_spec_round_trip() {
+        let spec = SkillSpec::from_content_json(VALID_STYLE_JSON)
+            .unwrap()
+            .expect("完整 JSON 应解析为 SkillSpec");
+        let json = spec.to_content_json().unwrap();
+        let again = SkillSpec::from_content_json(&json).unwrap().unwrap();
+        assert_eq!(spec, again);
+    }
+
+    #[test]
+    fn test_skill_spec_empty_means_markdown_only() {
+        assert_eq!(SkillSpec::from_content_json("").unwrap(), None);
+        assert_eq!(SkillSpec::from_content_json(" {} ").unwrap(), None);
+    }
+
+    #[test]
+    fn test_skill_spec_rejects_partial_json() {
+        let err = SkillSpec::from_content_json(r#"{"tone": "formal"}"#).unwrap_err();
+        assert!(err.contains("Skill 结构化内容无效"), "{}", err);
+
+        let err = SkillSpec::from_content_json(
+            r#"{"role": {"identity": "", "tone": "x"}, "style_principles": ["a"],
+                "blocklist": {}, "references": {}}"#,
+        )
+        .unwrap_err();
+        assert!(err.contains("role.identity"), "{}", err);
+    }
+
+    #[test]
+    fn test_skill_spec_to_markdown() {
+        let spec = SkillSpec::from_content_json(VALID_STYLE_JSON)
+            .unwrap()
+            .unwrap();
+        let md = crate::prompts::analyze_style::spec_to_markdown("我的风格", &spec);
+
+        assert!(md.starts_with("# 我的风格 — Writing Style Skill"));
+        assert!(md.contains("**身份**：独立开发者"));
+        assert!(md.contains("1. 短句为主\n2. 先结论后论证"));
+        assert!(md.contains("### 禁用词\n\n- 赋能"));
+        assert!(!md.contains("### 禁用句式"), "空列表不应渲染子标题");
+        assert!(!md.contains("## 参考"), "全部为空的章节应省略");
+    }
+
+    #[test]
+    fn test_load_current_spec() {
+        let conn = setup_db();
+        let skill_id = insert_skill(&conn, "结构化", "通用", "");
+        insert_version(&conn, skill_id, 1, "# md", "{}", "初始版本");
+        assert_eq!(load_current_spec(&conn, skill_id).unwrap(), None);
+
+        insert_version(&conn, skill_id, 2, "# md", VALID_STYLE_JSON, "进化");
+        conn.execute(
+            "UPDATE skill SET current_version = 2 WHERE id = ?1",
+            [skill_id],
+        )
+        .unwrap();
+        let spec = load_current_spec(&conn, skill_id).unwrap().unwrap();
+        assert_eq!(spec.role.identity, "独立开发者");
+    }
+
+    // ---------- Diff 规则应用测试 ----------
+
+    use crate::commands::diff::{build_evolution_preview, commit_diff_rules};
+    use crate::services::skill_evolution::{
+        apply_candidates, candidate_rules, normalize_rule, rule_id,
+    };
+
+    /// 辅助：创建带结构化 v1 的 Skill、一篇文章及其 Diff 记录，返回 (skill_id, diff_record_id)
+    fn setup_pending_diff(conn: &Connection, llm_analysis: &str) -> (i64, i64) {
+        let skill_id = insert_skill(conn, "进化测试", "通用", "");
+        insert_version(conn, skill_id, 1, "# v1", VALID_STYLE_JSON, "初始版本");
+        conn.execute(
+            "INSERT INTO article (skill_id, skill_version_used, title) VALUES (?1, 1, '文章')",
+            [skill_id],
+        )
+        .unwrap();
+        let article_id = conn.last_insert_rowid();
+        conn.execute(
+            "INSERT INTO diff_record (article_id, diff_data, llm_analysis) VALUES (?1, '[]', ?2)",
+            rusqlite::params![article_id, llm_analysis],
+        )
+        .unwrap();
+        (skill_id, conn.last_insert_rowid())
+    }
+
+    const DIFF_ANALYSIS_JSON: &str = r#"{
+        "modification_analysis": [{"type": "删除", "description": "删掉了口号", "intent": "去套话"}],
+        "new_rules": {
+            "add_to_style_principles": ["先结论后论证。", "多用具体数字"],
+            "add_to_blocklist_words": ["抓手"],
+            "add_to_blocklist_patterns": [],
+            "other_observations": []
+        },
+        "summary": "减少套话"
+    }"#;
+
+    #[test]
+    fn test_normalize_rule_ignores_case_space_and_punctuation() {
+        assert_eq!(normalize_rule(" 先结论 后论证。"), "先结论后论证");
+        assert_eq!(normalize_rule("Use Short Sentences."), "useshortsentences");
+        assert_eq!(normalize_rule("……"), "");
+    }
+
+    #[test]
+    fn test_apply_candidates_skips_duplicates() {
+        let mut spec = SkillSpec::from_content_json(VALID_STYLE_JSON)
+            .unwrap()
+            .unwrap();
+        let analysis: DiffAnalysis = parse_structured(DIFF_ANALYSIS_JSON).unwrap();
+        let candidates = candidate_rules(&spec, &analysis.new_rules);
+        let report = apply_candidates(&mut spec, &candidates);
+
+        assert_eq!(report.added_principles, vec!["多用具体数字"]);
+        assert_eq!(report.added_forbidden_words, vec!["抓手"]);
+        assert!(report.added_forbidden_patterns.is_empty());
+        assert_eq!(spec.style_principles.len(), 3, "重复原则不应再次追加");
+    }
+
+    #[test]
+    fn test_apply_diff_record_creates_version() {
+        let conn = setup_db();
+        let (skill_id, diff_id) = setup_pending_diff(&conn, DIFF_ANALYSIS_JSON);
+
+        let result = commit_diff_rules(&conn, diff_id, None).unwrap();
+        assert!(result.created_version);
+        assert_eq!(result.version_number, 2);
+        assert_eq!(
+            result.change_summary,
+            format!(
+                "应用 Diff #{}：新增 1 条风格原则、1 个禁用词（减少套话）",
+                diff_id
+            )
+        );
+
+        let spec = load_current_spec(&conn, skill_id).unwrap().unwrap();
+        assert!(spec.style_principles.contains(&"多用具体数字".to_string()));
+        assert!(spec.blocklist.forbidden_words.contains(&"抓手".to_string()));
+
+        let applied: i64 = conn
+            .query_row(
+                "SELECT applied_to_skill FROM diff_record WHERE id = ?1",
+                [diff_id],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(applied, 1, "应用后应标记 applied_to_skill");
+
+        let err = commit_diff_rules(&conn, diff_id, None).unwrap_err();
+        assert!(err.contains("已应用"), "重复应用应被拒绝: {}", err);
+    }
+
+    #[test]
+    fn test_apply_diff_record_without_new_rules() {
+        let conn = setup_db();
+        let analysis = r#"{"modification_analysis": [], "new_rules": {
+            "add_to_style_principles": ["短句为主"]}, "summary": ""}"#;
+        let (skill_id, diff_id) = setup_pending_diff(&conn, analysis);
+
+        let result = commit_diff_rules(&conn, diff_id, None).unwrap();
+        assert!(!result.created_version, "规则全部已存在时不应创建新版本");
+        assert_eq!(result.version_number, 1);
+
+        let versions: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM skill_version WHERE skill_id = ?1",
+                [skill_id],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(versions, 1);
+    }
+
+    // ---------- 进化预览与逐条批准测试 ----------
+
+    use crate::models::skill_spec::RuleSection;
+
+    #[test]
+    fn test_rule_id_is_stable_across_formatting() {
+        assert_eq!(
+            rule_id(RuleSection::StylePrinciples, "多用 具体数字。"),
+            rule_id(RuleSection::StylePrinciples, "多用具体数字")
+        );
+        assert_ne!(
+            rule_id(RuleSection::StylePrinciples, "抓手"),
+            rule_id(RuleSection::ForbiddenWords, "抓手"),
+            "不同章节的同一文本应有不同 ID"
+        );
+        assert!(rule_id(RuleSection::ForbiddenWords, "抓手").starts_with("forbidden_words-"));
+    }
+
+    #[test]
+    fn test_preview_evolution_groups_candidates() {
+        let conn = setup_db();
+        let (skill_id, diff_id) = setup_pending_diff(&conn, DIFF_ANALYSIS_JSON);
+
+        let preview = build_evolution_preview(&conn, diff_id).unwrap();
+        assert_eq!(preview.skill_id, skill_id);
+        assert_eq!(preview.base_version, 1);
+        assert_eq!(
+            preview.style_principles.len(),
+            1,
+            "已存在的原则不应成为候选"
+        );
+        assert_eq!(preview.style_principles[0].text, "多用具体数字");
+        assert_eq!(preview.forbidden_words[0].text, "抓手");
+        assert!(preview.forbidden_patterns.is_empty());
+
+        // 预览不写数据库，再次预览得到相同的 ID
+        let again = build_evolution_preview(&conn, diff_id).unwrap();
+        assert_eq!(preview.style_principles, again.style_principles);
+        let version: i64 = conn
+            .query_row(
+                "SELECT current_version FROM skill WHERE id = ?1",
+                [skill_id],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(version, 1);
+    }
+
+    #[test]
+    fn test_commit_evolution_applies_only_accepted_rules() {
+        let conn = setup_db();
+        let (skill_id, diff_id) = setup_pending_diff(&conn, DIFF_ANALYSIS_JSON);
+        let preview = build_evolution_preview(&conn, diff_id).unwrap();
+
+        let accepted = vec![preview.forbidden_words[0].id.clone()];
+        let result = commit_diff_rules(&conn, diff_id, Some(&accepted)).unwrap();
+        assert_eq!(result.version_number, 2);
+        assert_eq!(result.added_forbidden_words, vec!["抓手"]);
+        assert!(result.added_principles.is_empty());
+
+        let spec = load_current_spec(&conn, skill_id).unwrap().unwrap();
+        assert!(spec.blocklist.forbidden_words.contains(&"抓手".to_string()));
+        assert!(
+            !spec.style_principles.contains(&"多用具体数字".to_string()),
+            "未批准的规则不应写入新版本"
+        );
+    }
+
+    #[test]
+    fn test_commit_evolution_rejects_unknown_rule_id() {
+        let conn = setup_db();
+        let (_, diff_id) = setup_pending_diff(&conn, DIFF_ANALYSIS_JSON);
+
+        let accepted = vec!["style_principles-0000000000000000".to_string()];
+        let err = commit_diff_rules(&conn, diff_id, Some(&accepted)).unwrap_err();
+        assert!(err.contains("不存在或已过期"), "{}", err);
+
+        // 全部拒绝：不创建版本，但记录标记为已处理
+        let result = commit_diff_rules(&conn, diff_id, Some(&[])).unwrap();
+        assert!(!result.created_version);
+        assert!(build_evolution_preview(&conn, diff_id).is_err());
+    }
+
+    // ---------- 版本回滚测试 ----------
+
+    use crate::commands::skill::rollback_to_version;
+
+    #[test]
+    fn test_rollback_creates_restoring_version() {
+        let conn = setup_db();
+        let skill_id = insert_skill(&conn, "回滚", "通用", "");
+        insert_version(&conn, skill_id, 1, "# v1", VALID_STYLE_JSON, "初始版本");
+        insert_version(&conn, skill_id, 2, "# v2", "{}", "进化");
+        conn.execute(
+            "UPDATE skill SET current_version = 2 WHERE id = ?1",
+            [skill_id],
+        )
+        .unwrap();
+
+        let restored = rollback_to_version(&conn, skill_id, 1).unwrap();
+        assert_eq!(restored.version_number, 3, "回滚应追加新版本而不是移动指针");
+        assert_eq!(restored.content_markdown, "# v1");
+        assert_eq!(restored.content_json, VALID_STYLE_JSON);
+        assert_eq!(restored.change_summary, "rolled back from v1");
+
+        let (current, versions): (i64, i64) = conn
+            .query_row(
+                "SELECT current_version, (SELECT COUNT(*) FROM skill_version WHERE skill_id = ?1)
+                 FROM skill WHERE id = ?1",
+                [skill_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(current, 3);
+        assert_eq!(versions, 3, "历史版本应全部保留");
+    }
+
+    #[test]
+    fn test_rollback_rejects_current_and_missing_versions() {
+        let conn = setup_db();
+        let skill_id = insert_skill(&conn, "回滚", "通用", "");
+        insert_version(&conn, skill_id, 1, "# v1", "{}", "初始版本");
+
+        let err = rollback_to_version(&conn, skill_id, 1).unwrap_err();
+        assert!(err.contains("已经是当前版本"), "{}", err);
+        let err = rollback_to_version(&conn, skill_id, 9).unwrap_err();
+        assert!(err.contains("版本未找到"), "{}", err);
+        assert!(rollback_to_version(&conn, 999, 1).is_err());
+    }
+
+    #[test]
+    fn test_new_version_is_all_or_nothing() {
+        let conn = setup_db();
+        let skill_id = insert_skill(&conn, "原子", "通用", "");
+        insert_version(&conn, skill_id, 1, "# v1", VALID_STYLE_JSON, "初始版本");
+        insert_version(&conn, skill_id, 2, "# v2", "{}", "进化");
+        conn.execute(
+            "UPDATE skill SET current_version = 2 WHERE id = ?1",
+            [skill_id],
+        )
+        .unwrap();
+        // 让规则出处同步失败
+        conn.execute_batch("DROP TABLE skill_rule").unwrap();
+
+        assert!(rollback_to_version(&conn, skill_id, 1).is_err());
+        let (current, versions): (i64, i64) = conn
+            .query_row(
+                "SELECT current_version, (SELECT COUNT(*) FROM skill_version WHERE skill_id = ?1)
+                 FROM skill WHERE id = ?1",
+                [skill_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((current, versions), (2, 2), "规则同步失败时不应留下新版本");
+        assert!(conn.is_autocommit(), "失败后不应残留未结束的事务");
+    }
+
+    // ---------- 版本对比测试 ----------
+
+    use crate::commands::skill::compare_versions;
+    use crate::services::skill_diff::compare_specs;
+
+    #[test]
+    fn test_compare_specs_added_removed_modified() {
+        let from = SkillSpec::from_content_json(VALID_STYLE_JSON)
+            .unwrap()
+            .unwrap();
+        let mut to = from.clone();
+        to.role.tone = "热情直接".to_string();
+        to.style_principles = vec![
+            "短句为主，".to_string(),           // 只改了标点：视为未变
+            "先给结论，再展开论证".to_string(), // 改写
+            "多用具体数字".to_string(),         // 新增
+        ];
+        to.blocklist.forbidden_words.clear();
+
+        let sections = compare_specs(&from, &to);
+        let names: Vec<&str> = sections.iter().map(|s| s.section.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["role.tone", "style_principles", "forbidden_words"]
+        );
+
+        assert_eq!(sections[0].modified[0].to, "热情直接");
+        let principles = &sections[1];
+        assert_eq!(principles.added, vec!["多用具体数字"]);
+        assert!(principles.removed.is_empty(), "{:?}", principles);
+        assert_eq!(principles.modified[0].from, "先结论后论证");
+        assert_eq!(sections[2].removed, vec!["赋能"]);
+    }
+
+    #[test]
+    fn test_compare_skill_versions_includes_markdown_diff() {
+        let conn = setup_db();
+        let skill_id = insert_skill(&conn, "对比", "通用", "");
+        insert_version(&conn, skill_id, 1, "# 标题\n旧行\n", "{}", "初始版本");
+        insert_version(
+            &conn,
+            skill_id,
+            2,
+            "# 标题\n新行\n",
+            VALID_STYLE_JSON,
+            "进化",
+        );
+
+        let cmp = compare_versions(&conn, skill_id, 1, 2).unwrap();
+        assert_eq!((cmp.from_version, cmp.to_version), (1, 2));
+        assert!(
+            cmp.sections
+                .iter()
+                .any(|s| s.section == "style_principles" && s.added.len() == 2),
+            "仅有 Markdown 的版本应按空规则对比"
+        );
+        let tags: Vec<&str> = cmp.markdown_diff.iter().map(|c| c.tag.as_str()).collect();
+        assert_eq!(tags, vec!["equal", "delete", "insert"]);
+
+        assert!(compare_versions(&conn, skill_id, 1, 5).is_err());
+    }
+
+    // ---------- 规则出处测试 ----------
+
+    use crate::commands::rule::{backfill_all_rules, list_rules, rule_history};
+    use crate::commands::sample::sample_title;
+
+    #[test]
+    fn test_list_rules_is_read_only_and_backfill_runs_once() {
+        let conn = setup_db();
+        let skill_id = insert_skill(&conn, "旧数据", "通用", "");
+        insert_version(&conn, skill_id, 1, "# v1", VALID_STYLE_JSON, "初始版本");
+        let count = |conn: &Connection| -> i64 {
+            conn.query_row("SELECT COUNT(*) FROM skill_rule", [], |row| row.get(0))
+                .unwrap()
+        };
+
+        assert!(list_rules(&conn, skill_id, false).unwrap().is_empty());
+        assert_eq!(count(&conn), 0, "列出规则不应写数据库");
+
+        backfill_all_rules(&conn).unwrap();
+        let rules = list_rules(&conn, skill_id, false).unwrap();
+        assert!(!rules.is_empty(), "迁移应回放历史版本补齐规则");
+        assert!(rules.iter().all(|r| r.introduced_version == 1));
+        backfill_all_rules(&conn).unwrap();
+        assert_eq!(count(&conn), rules.len() as i64, "已有记录时不重复补齐");
+        assert!(list_rules(&conn, 9999, false).is_err());
+    }
+
+    #[test]
+    fn test_rules_record_diff_provenance() {
+        let conn = setup_db();
+        let (skill_id, diff_id) = setup_pending_diff(&conn, DIFF_ANALYSIS_JSON);
+        commit_diff_rules(&conn, diff_id, None).unwrap();
+
+        let rules = list_rules(&conn, skill_id, false).unwrap();
+        let initial = rules.iter().find(|r| r.text == "赋能").unwrap();
+        assert_eq!(initial.introduced_version, 1, "旧版本的规则应被回放补齐");
+        assert_eq!(initial.diff_record_id, None);
+
+        let learned = rules.iter().find(|r| r.text == "抓手").unwrap();
+        assert_eq!(learned.section, "forbidden_words");
+        assert_eq!(learned.introduced_version, 2);
+        assert_eq!(learned.diff_record_id, Some(diff_id), "应记录来源 Diff");
+        assert_eq!(learned.removed_version, None);
+    }
+
+    #[test]
+    fn test_rule_history_tracks_removal_and_restore() {
+        let conn = setup_db();
+        let (skill_id, diff_id) = setup_pending_diff(&conn, DIFF_ANALYSIS_JSON);
+        commit_diff_rules(&conn, diff_id, None).unwrap();
+        conn.execute(
+            "UPDATE skill_rule SET support_count = 4, contradiction_count = 1 WHERE text = '抓手'",
+            [],
+        )
+        .unwrap();
+        // v3 回到 v1（移除“抓手”），v4 再回到 v2（恢复“抓手”）
+        rollback_to_version(&conn, skill_id, 1).unwrap();
+        rollback_to_version(&conn, skill_id, 2).unwrap();
+
+        let active = list_rules(&conn, skill_id, false).unwrap();
+        let restored = active.iter().find(|r| r.text == "抓手").unwrap();
+        assert_eq!(
+            (restored.support_count, restored.contradiction_count),
+            (4, 1),
+            "恢复的规则应沿用原来的置信度记录"
+        );
+        let history = rule_history(&conn, restored.id).unwrap();
+
+        let spans: Vec<(i64, Option<i64>)> = history
+            .iter()
+            .map(|r| (r.introduced_version, r.removed_version))
+            .collect();
+        assert_eq!(spans, vec![(2, Some(3)), (4, None)]);
+        assert!(
+            history.iter().all(|r| r.diff_record_id == Some(diff_id)),
+            "恢复的规则应沿用原来的出处"
+        );
+
+        let all = list_rules(&conn, skill_id, true).unwrap();
+        assert!(all.len() > active.len());
+        assert!(rule_history(&conn, 9999).is_err());
+    }
+
+    #[test]
+    fn test_sample_title() {
+        assert_eq!(sample_title("\n# 我的第一篇文章\n正文"), "我的第一篇文章");
+        assert_eq!(sample_title("   \n"), "未命名样本");
+        assert_eq!(sample_title(&"长".repeat(80)).chars().count(), 50);
+    }
+
+    // ---------- 规则置信度测试 ----------
+
+    use crate::commands::rule::{rank_spec_for_generation, record_rule_feedback};
+    use crate::services::skill_evolution::{rank_rules, rule_confidence, MIN_RULE_CONFIDENCE};
+
+    #[test]
+    fn test_rule_confidence_thresholds() {
+        assert!(rule_confidence(1, 0) > MIN_RULE_CONFIDENCE);
+        assert!(
+            rule_confidence(1, 1) >= MIN_RULE_CONFIDENCE,
+            "一次违背不应直接过滤规则"
+        );
+        assert!(rule_confidence(1, 2) < MIN_RULE_CONFIDENCE);
+        assert!(rule_confidence(3, 0) > rule_confidence(1, 0));
+    }
+
+    #[test]
+    fn test_rank_rules_orders_and_filters() {
+        let spec = SkillSpec::from_content_json(VALID_STYLE_JSON)
+            .unwrap()
+            .unwrap();
+        let ranked = rank_rules(&spec, MIN_RULE_CONFIDENCE, |_, text| match text {
+            "先结论后论证" => Some(0.9),
+            "赋能" => Some(0.2),
+            _ => None,
+        });
+        assert_eq!(ranked.style_principles, vec!["先结论后论证", "短句为主"]);
+        assert!(
+            ranked.blocklist.forbidden_words.is_empty(),
+            "低置信度规则应被过滤"
+        );
+        assert_eq!(ranked.role, spec.role);
+    }
+
+    #[test]
+    fn test_rule_feedback_reinforces_and_contradicts() {
+        let conn = setup_db();
+        let (skill_id, diff_id) = setup_pending_diff(&conn, DIFF_ANALYSIS_JSON);
+        conn.execute(
+            "INSERT INTO article (skill_id, skill_version_used, title) VALUES (?1, 1, '另一篇')",
+            [skill_id],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO diff_record (article_id, diff_data, llm_analysis) VALUES (?1, '[]', '')",
+            [conn.last_insert_rowid()],
+        )
+        .unwrap();
+        let other_diff_id = conn.last_insert_rowid();
+        // 新分析再次提取“先结论后论证”，模型认为“短句为主”被违背，用户又写回了禁用词“赋能”
+        let analysis: DiffAnalysis = parse_structured(
+            r#"{"new_rules": {"add_to_style_principles": ["先结论后论证。"]},
+                "contradicted_rules": ["短句为主"]}"#,
+        )
+        .unwrap();
+        record_rule_feedback(
+            &conn,
+            skill_id,
+            diff_id,
+            &analysis,
+            "原文",
+            "我们要赋能用户",
+        )
+        .unwrap();
+        record_rule_feedback(&conn, skill_id, other_diff_id, &analysis, "原文", "赋能").unwrap();
+        // 同一篇文章重新分析：不再累加
+        record_rule_feedback(&conn, skill_id, diff_id, &analysis, "原文", "赋能").unwrap();
+        conn.execute(
+            "INSERT INTO diff_record (article_id, diff_data, llm_analysis)
+             SELECT article_id, '[]', '' FROM diff_record WHERE id = ?1",
+            [diff_id],
+        )
+        .unwrap();
+        let rerun_id = conn.last_insert_rowid();
+        record_rule_feedback(&conn, skill_id, rerun_id, &analysis, "原文", "赋能").unwrap();
+        assert!(record_rule_feedback(&conn, skill_id, 9999, &analysis, "", "").is_err());
+
+        let rules = list_rules(&conn, skill_id, false).unwrap();
+        let counts = |text: &str| {
+            let r = rules.iter().find(|r| r.text == text).unwrap();
+            (r.support_count, r.contradiction_count)
+        };
+        assert_eq!(counts("先结论后论证"), (3, 0));
+        assert_eq!(counts("短句为主"), (1, 2));
+        assert_eq!(counts("赋能"), (1, 2));
+
+        let spec = load_current_spec(&conn, skill_id).unwrap().unwrap();
+        let ranked = rank_spec_for_generation(&conn, skill_id, &spec).unwrap();
+        assert_eq!(ranked.style_principles, vec!["先结论后论证"]);
+        assert!(ranked.blocklist.forbidden_words.is_empty());
+    }
+
+    // ---------- Skill 压缩测试 ----------
+
+    use crate::services::skill_compaction::{build_compaction_summary, dedup_spec, enforce_budget};
+    use crate::services::tokens::estimate_tokens;
+
+    #[test]
+    fn test_estimate_tokens() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("短句为主"), 4);
+        assert_eq!(estimate_tokens("hello world!"), 3);
+        assert_eq!(estimate_tokens("用 Rust 写"), 4);
+    }
+
+    #[test]
+    fn test_dedup_spec_merges_near_duplicates() {
+        let mut spec = SkillSpec::from_content_json(VALID_STYLE_JSON)
+            .unwrap()
+            .unwrap();
+        spec.style_principles = vec![
+            "短句为主".to_string(),
+            "先结论后论证".to_string(),
+            "短句为主，少用长句".to_string(),
+            "先结论后论证。".to_string(),
+        ];
+        spec.blocklist.forbidden_words = vec![
+            "全面赋能".to_string(),
+            "赋能".to_string(),
+            "抓手".to_string(),
+            "抓手".to_string(),
+        ];
+
+        let merged = dedup_spec(&mut spec);
+        assert_eq!(
+            spec.style_principles,
+            vec!["短句为主，少用长句", "先结论后论证"],
+            "近似规则应保留更完整的措辞，位置不变"
+        );
+        assert_eq!(spec.blocklist.forbidden_words, vec!["赋能", "抓手"]);
+        let merged_count: usize = merged.iter().map(|m| m.merged.len()).sum();
+        assert_eq!(merged_count, 4);
+    }
+
+    #[test]
+    fn test_dedup_spec_keeps_negated_and_prefixed_terms() {
+        let mut spec = SkillSpec::from_content_json(VALID_STYLE_JSON)
+            .unwrap()
+            .unwrap();
+        spec.style_principles = vec![
+            "用短句".to_string(),
+            "不用短句".to_string(),
+            "Use passive voice sparingly".to_string(),
+            "Never use passive voice sparingly".to_string(),
+        ];
+        spec.references.terminology = vec![
+            "RAG".to_string(),
+            "GraphRAG".to_string(),
+            "Rust".to_string(),
+            "Rusty".to_string(),
+        ];
+        spec.blocklist.forbidden_words = vec!["RAG".to_string(), "GraphRAG".to_string()];
+
+        let merged = dedup_spec(&mut spec);
+        assert!(merged.is_empty(), "不应合并任何规则: {:?}", merged);
+        assert_eq!(spec.style_principles.len(), 4, "否定与肯定的规则意思相反");
+        assert_eq!(
+            spec.references.terminology.len(),
+            4,
+            "术语前缀不同即为不同术语"
+        );
+        assert_eq!(
+            spec.blocklist.forbidden_words,
+            vec!["RAG", "GraphRAG"],
+            "英文禁用词按整词命中，RAG 不覆盖 GraphRAG"
+        );
+    }
+
+    #[test]
+    fn test_enforce_budget_trims_low_priority_first() {
+        let mut spec = SkillSpec::from_content_json(VALID_STYLE_JSON)
+            .unwrap()
+            .unwrap();
+        spec.references.sample_features = vec!["很长的样本特征描述".repeat(20)];
+        let render = |s: &SkillSpec| crate::prompts::analyze_style::spec_to_markdown("压缩", s);
+        let estimator = TokenEstimator::DEFAULT;
+        let budget = estimate_tokens(&render(&spec)) - 10;
+
+        let trimmed = enforce_budget(&mut spec, budget, &estimator, render);
+        assert_eq!(trimmed.len(), 1);
+        assert_eq!(trimmed[0].section, "sample_features");
+        assert_eq!(spec.style_principles.len(), 2, "风格原则不应被优先裁剪");
+
+        // 预算极小时至少保留一条风格原则
+        enforce_budget(&mut spec, 1, &estimator, render);
+        assert_eq!(spec.style_principles, vec!["短句为主"]);
+        assert!(spec.blocklist.forbidden_words.is_empty());
+    }
+
+    #[test]
+    fn test_enforce_budget_uses_model_estimator() {
+        let mut spec = SkillSpec::from_content_json(VALID_STYLE_JSON)
+            .unwrap()
+            .unwrap();
+        spec.references.sample_features = vec!["很长的样本特征描述".repeat(20)];
+        let render = |s: &SkillSpec| crate::prompts::analyze_style::spec_to_markdown("压缩", s);
+        let budget = estimate_tokens(&render(&spec));
+
+        let mut generic = spec.clone();
+        assert!(
+            enforce_budget(&mut generic, budget, &TokenEstimator::DEFAULT, render).is_empty(),
+            "通用估算恰好不超预算"
+        );
+
+        // Claude 切中文更碎，同样的内容在其分词器下会超出预算
+        let claude = TokenEstimator::for_model("claude", "claude-3-5-sonnet");
+        let trimmed = enforce_budget(&mut spec, budget, &claude, render);
+        assert!(!trimmed.is_empty(), "应按生成模型的分词器裁剪");
+        assert!(claude.estimate(&render(&spec)) <= budget);
+    }
+
+    #[test]
+    fn test_compaction_summary() {
+        let summary = build_compaction_summary(&[], &[], true, 1820, 1490);
+        assert_eq!(summary, "压缩 Skill：经 LLM 整理（1820 → 1490 tokens）");
+    }
+
+    // ---------- 规则一致性检查测试 ----------
+
+    use crate::services::consistency::check_spec;
+
+    #[test]
+    fn test_consistency_detects_opposing_principles() {
+        let mut spec = SkillSpec::from_content_json(VALID_STYLE_JSON)
+            .unwrap()
+            .unwrap();
+        assert!(check_spec(&spec).is_empty());
+
+        // “少用长句”与“短句为主”取向一致，不算冲突
+        spec.style_principles
+            .push("少用长句，一句只讲一件事".to_string());
+        assert!(check_spec(&spec).is_empty(), "{:?}", check_spec(&spec));
+
+        spec.style_principles
+            .push("Prefer long flowing sentences".to_string());
+        let conflicts = check_spec(&spec);
+        assert_eq!(conflicts.len(), 2, "{:?}", conflicts);
+        assert!(conflicts.iter().all(|c| c.kind == "opposing_principles"));
+        assert_eq!(conflicts[0].rule_b, "Prefer long flowing sentences");
+    }
+
+    #[test]
+    fn test_consistency_negation_must_precede_term() {
+        let mut spec = SkillSpec::from_content_json(VALID_STYLE_JSON)
+            .unwrap()
+            .unwrap();
+        spec.style_principles = vec![
+            "短句为主".to_string(),
+            "文字简洁".to_string(),
+            "开头特别简洁".to_string(),
+            "注意区别简洁与空洞".to_string(),
+            "Piano concise phrasing".to_string(),
+        ];
+        assert!(
+            check_spec(&spec).is_empty(),
+            "特别/区别中的“别”、piano 结尾的 no 都不是否定: {:?}",
+            check_spec(&spec)
+        );
+
+        // 真正的否定仍能识别：取向与“短句为主”一致
+        spec.style_principles = vec![
+            "短句为主".to_string(),
+            "别写长句".to_string(),
+            "避免使用长句".to_string(),
+            "Avoid using long sentences".to_string(),
+        ];
+        assert!(check_spec(&spec).is_empty(), "{:?}", check_spec(&spec));
+
+        spec.style_principles.push("多用长句".to_string());
+        assert_eq!(check_spec(&spec).len(), 4);
+    }
+
+    #[test]
+    fn test_consistency_detects_forbidden_terminology() {
+        let mut spec = SkillSpec::from_content_json(VALID_STYLE_JSON)
+            .unwrap()
+            .unwrap();
+        spec.references.terminology = vec!["赋能：保留原词，不做解释".to_string()];
+
+        let conflicts = check_spec(&spec);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].kind, "forbidden_terminology");
+        assert_eq!(conflicts[0].rule_a, "赋能");
+    }
+
+    #[test]
+    fn test_new_version_reports_conflicts() {
+        let conn = setup_db();
+        let analysis = r#"{"new_rules": {"add_to_style_principles": ["多用长句，营造节奏感"]}}"#;
+        let (_, diff_id) = setup_pending_diff(&conn, analysis);
+
+        let result = commit_diff_rules(&conn, diff_id, None).unwrap();
+        assert!(result.created_version, "冲突不应阻止保存");
+        assert_eq!(result.conflicts.len(), 1);
+        assert_eq!(result.conflicts[0].rule_a, "短句为主");
+        assert_eq!(result.conflicts[0].rule_b, "多用长句，营造节奏感");
+    }
+
+    // ---------- 本地禁用规则检查测试 ----------
+
+    use crate::commands::lint::lint_with_skill;
+    use crate::models::lint::LintSeverity;
+    use crate::services::linter::{lint, pattern_to_regex, rules_from_spec, word_to_regex};
+
+    #[test]
+    fn test_word_regex_respects_ascii_boundaries() {
+        let re = word_to_regex("AI").unwrap();
+        assert!(re.is_match("这是 ai 写的"));
+        assert!(!re.is_match("AIM high"));
+        assert!(word_to_regex("赋能").unwrap().is_match("全面赋能业务"));
+        assert!(word_to_regex("  ").is_none());
+    }
+
+    #[test]
+    fn test_pattern_regex_wildcards() {
+        let re = pattern_to_regex("不是……而是……").unwrap();
+        assert!(re.is_match("这不是技术问题，而是管理问题"));
+        assert!(
+            !re.is_match("这不是技术问题。而是管理问题"),
+            "占位符不应跨句匹配"
+        );
+        assert!(pattern_to_regex("/in today's (fast-paced|digital) world/")
+            .unwrap()
+            .is_match("In today's digital world"));
+        assert!(pattern_to_regex("……").is_none(), "只有占位符的句式应被忽略");
+        assert!(
+            pattern_to_regex("(未闭合").unwrap().is_match("(未闭合"),
+            "普通句式按字面匹配"
+        );
+    }
+
+    #[test]
+    fn test_lint_reports_utf16_spans() {
+        let mut spec = SkillSpec::from_content_json(VALID_STYLE_JSON)
+            .unwrap()
+            .unwrap();
+        spec.blocklist.forbidden_patterns = vec!["不仅...更是...".to_string()];
+        let rules = rules_from_spec(&spec);
+
+        let text = "😀我们要赋能，这不仅是工具，更是理念";
+        let issues = lint(text, &rules);
+        assert_eq!(issues.len(), 2);
+
+        let word = &issues[0];
+        assert_eq!(word.rule, "赋能");
+        assert_eq!(word.severity, LintSeverity::Error);
+        // emoji 占 2 个 UTF-16 码元
+        assert_eq!((word.start, word.end), (5, 7));
+        let utf16: Vec<u16> = text.encode_utf16().collect();
+        assert_eq!(
+            String::from_utf16(&utf16[word.start..word.end]).unwrap(),
+            "赋能"
+        );
+
+        assert_eq!(issues[1].severity, LintSeverity::Warning);
+        assert!(issues[1].matched.starts_with("不仅"));
+    }
+
+    #[test]
+    fn test_lint_with_skill() {
+        let conn = setup_db();
+        let skill_id = insert_skill(&conn, "检查", "通用", "");
+        insert_version(&conn, skill_id, 1, "# md", "{}", "初始版本");
+        assert!(lint_with_skill(&conn, skill_id, "赋能").unwrap().is_empty());
+
+        insert_version(&conn, skill_id, 2, "# md", VALID_STYLE_JSON, "进化");
+        conn.execute(
+            "UPDATE skill SET current_version = 2 WHERE id = ?1",
+            [skill_id],
+        )
+        .unwrap();
+        let issues = lint_with_skill(&conn, skill_id, "赋能，再赋能").unwrap();
+        assert_eq!(issues.len(), 2);
+    }
+
+    // ---------- AI 味评分 ----------
+
+    use crate::commands::ai_flavor::ai_flavor_trend;
+    use crate::commands::article::{get_article_by_id, refresh_ai_flavor};
+    use crate::services::ai_flavor::{self, DICTIONARY_VERSION};
+
+    const AI_FLAVORED_TEXT: &str = "值得注意的是，在当今数字化时代，AI 扮演着至关重要的角色。\n\n\
+        - **效率**：显著提升工作效率。\n\
+        - **质量**：全面提高内容质量。\n\
+        - **体验**：持续优化用户体验。\n\n\
+        总而言之，让我们一起拥抱变化。";
+
+    const HUMAN_TEXT: &str = "昨天下午去了趟菜市场。卖鱼的老王说今年的鲈鱼比往年贵了快一半，\
+        因为上游闹了一场水灾，塘里的鱼跑了不少。我没买。\
+        回家路上碰到楼下的张阿姨，她拎着两把韭菜，说晚上包饺子，问我要不要来。";
+
+    #[test]
+    fn test_ai_flavor_score_separates_styles() {
+        let ai = ai_flavor::score(AI_FLAVORED_TEXT);
+        let human = ai_flavor::score(HUMAN_TEXT);
+        assert_eq!(ai.dictionary_version, DICTIONARY_VERSION);
+        assert!(
+            ai.score > human.score + 30.0,
+            "套话密集的文本评分应明显更高: {} vs {}",
+            ai.score,
+            human.score
+        );
+        let rules: Vec<&str> = ai.hits.iter().map(|h| h.rule.as_str()).collect();
+        for phrase in ["值得注意的是", "在当今……时代", "至关重要", "总而言之"]
+        {
+            assert!(rules.contains(&phrase), "应命中 {}", phrase);
+        }
+        assert!(ai.list_line_ratio > 0.5, "列表行占比应被识别");
+        assert!(ai.bold_density > 0.0);
+        assert!(human.hits.is_empty(), "自然文本不应命中词典");
+
+        let empty = ai_flavor::score("");
+        assert_eq!(empty.score, 0.0);
+        assert_eq!(empty.sentence_length_cv, None);
+    }
+
+    #[test]
+    fn test_ai_flavor_english_phrases() {
+        let text = "In today’s fast-paced world, we must delve into the data. \
+            It's worth noting that this plays a pivotal role. She leveraged nothing.";
+        let hits = ai_flavor::score(text).hits;
+        let rules: Vec<&str> = hits.iter().map(|h| h.rule.as_str()).collect();
+        assert_eq!(rules.len(), 4, "命中: {:?}", rules);
+        assert!(
+            !rules.contains(&"leverage"),
+            "英文短语按整词匹配，leveraged 不应命中 leverage"
+        );
+    }
+
+    #[test]
+    fn test_article_ai_flavor_columns() {
+        let conn = setup_db();
+        let skill_id = insert_skill(&conn, "AI 味", "通用", "");
+        insert_version(&conn, skill_id, 1, "# md", "{}", "初始版本");
+        conn.execute(
+            "INSERT INTO article (skill_id, skill_version_used, title, ai_generated_content)
+             VALUES (?1, 1, '测试文章', ?2)",
+            rusqlite::params![skill_id, AI_FLAVORED_TEXT],
+        )
+        .unwrap();
+        let article_id = conn.last_insert_rowid();
+
+        refresh_ai_flavor(&conn, article_id).unwrap();
+        let article = get_article_by_id(&conn, article_id).unwrap();
+        assert!(article.ai_flavor_generated.unwrap() > 0.0);
+        assert_eq!(article.ai_flavor_refined, None, "尚未修改时修改稿评分为空");
+        assert_eq!(article.ai_flavor_dict_version, Some(DICTIONARY_VERSION));
+
+        conn.execute(
+            "UPDATE article SET user_refined_content = ?1 WHERE id = ?2",
+            rusqlite::params![HUMAN_TEXT, article_id],
+        )
+        .unwrap();
+        refresh_ai_flavor(&conn, article_id).unwrap();
+
+        let trend = ai_flavor_trend(&conn, skill_id).unwrap();
+        assert_eq!(trend.len(), 1);
+        let point = &trend[0];
+        assert_eq!(point.article_id, article_id);
+        assert!(
+            point.ai_flavor_refined.unwrap() < point.ai_flavor_generated.unwrap(),
+            "修改稿的 AI 味应低于初稿"
+        );
+    }
+
+    // ---------- 量化文风指纹 ----------
+
+    use crate::commands::skill::get_version_by_number;
+    use crate::models::fingerprint::StyleFingerprint;
+    use crate::prompts::analyze_style::build_analyze_prompt;
+    use crate::services::stylometry;
+
+    #[test]
+    fn test_sentence_lengths_split_rules() {
+        assert_eq!(
+            stylometry::sentence_lengths("# 标题\n他说：“走吧。”我没动。价格涨了 3.5 倍!"),
+            vec![8, 4, 9],
+            "句末引号归入本句，小数点不断句，标题行不计入"
+        );
+        let dist = stylometry::distribution(&[2, 4, 6, 8]);
+        assert_eq!(dist.mean, 5.0);
+        assert_eq!(dist.median, 5.0);
+        assert_eq!((dist.p10, dist.p90), (2.0, 8.0));
+    }
+
+    #[test]
+    fn test_fingerprint_measures_samples() {
+        let samples = vec![
+            "我写东西喜欢短句。不绕弯子。\n\n有话直说——这是习惯……".to_string(),
+            "用 Rust 写了个 CLI 工具，挺好用的。".to_string(),
+        ];
+        let fp = stylometry::fingerprint(&samples);
+        assert_eq!(fp.sample_count, 2);
+        assert_eq!(fp.paragraph_length.count, 3);
+        assert_eq!(fp.sentence_length.count, 4);
+        assert!(
+            fp.punctuation.full_width_ratio > 0.9,
+            "样本几乎只用全角标点"
+        );
+        assert!(fp.punctuation.em_dash_per_k > 0.0, "“——”应计为破折号");
+        assert!(fp.punctuation.ellipsis_per_k > 0.0);
+        assert!(fp.cjk_ratio > 0.5 && fp.cjk_ratio < 1.0, "中英混排比例");
+        assert!(fp.latin_words_per_k > 0.0);
+        assert!(fp.function_words.contains_key("的"));
+        assert!(!fp.function_words.contains_key("the"), "未出现的虚词不记录");
+
+        let prompt = build_analyze_prompt(&samples, &fp);
+        assert!(prompt.contains("量化指纹"));
+        assert!(prompt.contains(&format!("平均 {} 字", fp.sentence_length.mean)));
+    }
+
+    #[test]
+    fn test_new_version_keeps_fingerprint() {
+        let conn = setup_db();
+        let skill_id = insert_skill(&conn, "指纹", "通用", "");
+        insert_version(&conn, skill_id, 1, "# v1", VALID_STYLE_JSON, "初始版本");
+        insert_version(&conn, skill_id, 2, "# v2", "{}", "进化");
+        let fp_json =
+            serde_json::to_string(&stylometry::fingerprint(&["短句。很短。".to_string()])).unwrap();
+        conn.execute(
+            "UPDATE skill_version SET fingerprint_json = ?1 WHERE skill_id = ?2 AND version_number = 2",
+            rusqlite::params![fp_json, skill_id],
+        )
+        .unwrap();
+        conn.execute(
+            "UPDATE skill SET current_version = 2 WHERE id = ?1",
+            [skill_id],
+        )
+        .unwrap();
+
+        rollback_to_version(&conn, skill_id, 1).unwrap();
+        let v3 = get_version_by_number(&conn, skill_id, 3).unwrap();
+        let fp: StyleFingerprint = serde_json::from_str(&v3.fingerprint_json).unwrap();
+        assert_eq!(fp.sentence_length.count, 2, "新版本应沿用当前版本的指纹");
+        assert!(get_version_by_number(&conn, skill_id, 1)
+            .unwrap()
+            .fingerprint_json
+            .is_empty());
+    }
+
+    // ---------- 风格匹配评分 ----------
+
+    use crate::commands::article::record_style_match;
+    use crate::commands::style_match::match_skill_style;
+    use crate::services::style_match;
+
+    const CALM_SAMPLE: &str = "周末去爬了山。山不高，走了两个小时。\n\n\
+        山顶有个小卖部，卖的水比山下贵一倍。我买了一瓶，坐着看了会儿云。";
+
+    #[test]
+    fn test_style_match_reports_deviations() {
+        let expected = stylometry::fingerprint(&[CALM_SAMPLE.to_string()]);
+        let same = style_match::compare(&expected, &expected);
+        assert_eq!(same.similarity, 100.0, "与自身比较应完全一致");
+
+        let loud = stylometry::fingerprint(&["太棒了！！真的太棒了！你一定要去！\
+            这是我见过最美的风景！没有之一！"
+            .to_string()]);
+        let result = style_match::compare(&expected, &loud);
+        assert!(result.similarity < 80.0, "相似度: {}", result.similarity);
+        assert!(
+            result
+                .deviations
+                .windows(2)
+                .all(|w| w[0].distance >= w[1].distance),
+            "偏差应按距离降序排列"
+        );
+        let exclamation = result
+            .deviations
+            .iter()
+            .find(|d| d.feature == "punctuation.exclamation_per_k")
+            .unwrap();
+        assert_eq!(exclamation.expected, 0.0);
+        assert!(exclamation.message.contains("感叹号多了"));
+
+        let longer = stylometry::fingerprint(&["周末去爬了山，山不高，走了两个小时才到顶上。\
+            山顶有个小卖部，卖的水比山下贵了整整一倍多。我买了一瓶，坐着看了很久很久的云。"
+            .to_string()]);
+        let sentence = style_match::compare(&expected, &longer)
+            .deviations
+            .into_iter()
+            .find(|d| d.feature == "sentence_length.mean")
+            .unwrap();
+        assert!(
+            sentence.message.starts_with("句子比你的长"),
+            "{}",
+            sentence.message
+        );
+    }
+
+    #[test]
+    fn test_generated_article_records_style_similarity() {
+        let conn = setup_db();
+        let skill_id = insert_skill(&conn, "匹配", "通用", "");
+        insert_version(&conn, skill_id, 1, "# v1", VALID_STYLE_JSON, "初始版本");
+        assert!(
+            match_skill_style(&conn, skill_id, None, CALM_SAMPLE)
+                .unwrap()
+                .is_none(),
+            "没有样本指纹时不评分"
+        );
+
+        let fp_json =
+            serde_json::to_string(&stylometry::fingerprint(&[CALM_SAMPLE.to_string()])).unwrap();
+        conn.execute(
+            "UPDATE skill_version SET fingerprint_json = ?1 WHERE skill_id = ?2",
+            rusqlite::params![fp_json, skill_id],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO article (skill_id, skill_version_used, title, ai_generated_content)
+             VALUES (?1, 1, '爬山', ?2)",
+            rusqlite::params![skill_id, CALM_SAMPLE],
+        )
+        .unwrap();
+        let article_id = conn.last_insert_rowid();
+
+        record_style_match(&conn, article_id).unwrap();
+        let article = get_article_by_id(&conn, article_id).unwrap();
+        assert_eq!(article.style_similarity, Some(100.0));
+        let trend = ai_flavor_trend(&conn, skill_id).unwrap();
+        assert_eq!(trend[0].style_similarity, Some(100.0));
+    }
+
+    // ---------- 原创样本管理 ----------
+
+    use crate::commands::rule::{sync_skill_rules, RuleSource};
+    use crate::commands::sample::{get_sample_by_id, insert_sample, list_samples_for};
+
+    #[test]
+    fn test_sample_crud_helpers() {
+        let conn = setup_db();
+        let skill_id = insert_skill(&conn, "样本", "通用", "");
+
+        let first = insert_sample(&conn, skill_id, None, "\n# 第一篇\n正文一\n").unwrap();
+        let second = insert_sample(&conn, skill_id, Some("  自定义标题 "), "正文二").unwrap();
+        assert!(
+            insert_sample(&conn, skill_id, None, "   ").is_err(),
+            "空样本应被拒绝"
+        );
+
+        let samples = list_samples_for(&conn, skill_id).unwrap();
+        let ids: Vec<i64> = samples.iter().map(|s| s.id).collect();
+        assert_eq!(ids, vec![first, second], "按添加顺序返回");
+        assert_eq!(samples[0].title, "第一篇");
+        assert_eq!(samples[0].content, "# 第一篇\n正文一", "正文应去掉首尾空白");
+        assert_eq!(samples[1].title, "自定义标题");
+        assert!(get_sample_by_id(&conn, 9999).is_err());
+    }
+
+    #[test]
+    fn test_deleting_sample_keeps_its_rules() {
+        let conn = setup_db();
+        let skill_id = insert_skill(&conn, "样本", "通用", "");
+        insert_version(&conn, skill_id, 1, "# v1", VALID_STYLE_JSON, "初始版本");
+        let sample_id = insert_sample(&conn, skill_id, None, "样本正文").unwrap();
+        sync_skill_rules(&conn, skill_id, 1, RuleSource::Samples).unwrap();
+        let rules = list_rules(&conn, skill_id, false).unwrap();
+        assert!(
+            rules.iter().all(|r| r.original_sample_id.is_none()),
+            "从整批样本提取的规则不归属于某一篇样本"
+        );
+        conn.execute(
+            "UPDATE skill_rule SET original_sample_id = ?1 WHERE skill_id = ?2",
+            [sample_id, skill_id],
+        )
+        .unwrap();
+
+        conn.execute("DELETE FROM original_sample WHERE id = ?1", [sample_id])
+            .unwrap();
+        let rules = list_rules(&conn, skill_id, false).unwrap();
+        assert!(!rules.is_empty(), "删除样本不应删除规则");
+        assert!(rules.iter().all(|r| r.original_sample_id.is_none()));
+        assert!(list_samples_for(&conn, skill_id).unwrap().is_empty());
+    }
+
+    // ---------- 样本批量导入 ----------
+
+    use crate::commands::sample::store_imported_samples;
+    use crate::models::skill::{SampleImportReport, SkipReason};
+    use crate::services::sample_import::{content_hash, extract_text, read_samples};
+    use std::path::PathBuf;
+
+    fn import_sample_files(
+        conn: &rusqlite::Connection,
+        skill_id: i64,
+        paths: &[PathBuf],
+    ) -> Result<SampleImportReport, String> {
+        let (samples, skipped) = read_samples(paths);
+        store_imported_samples(conn, skill_id, samples, skipped)
+    }
+
+    #[test]
+    fn test_extract_text_strips_markup() {
+        let md = "---\ntitle: \"我的博客\"\ntags: [随笔]\n---\n\n# 标题\n\n\
+            这是**重点**，见[链接](https://example.com)。![图](a.png)\n\n\
+            ```rust\nfn main() {}\n```\n\n> 引用一句\n";
+        let sample = extract_text("md", md);
+        assert_eq!(sample.title.as_deref(), Some("我的博客"));
+        assert_eq!(sample.content, "# 标题\n\n这是重点，见链接。\n\n引用一句");
+
+        let html = "<html><head><title>旧文 &amp; 新篇</title><style>p{}</style></head>\
+            <body><p>第一段&nbsp;文字</p><script>alert(1)</script><p>第二段&#x4E00;</p></body></html>";
+        let sample = extract_text("html", html);
+        assert_eq!(sample.title.as_deref(), Some("旧文 & 新篇"));
+        assert_eq!(sample.content, "第一段 文字\n\n第二段一");
+
+        assert_eq!(
+            content_hash("同一 段\n文字"),
+            content_hash("同一段文字"),
+            "哈希应忽略空白差异"
+        );
+    }
+
+    #[test]
+    fn test_import_sample_files_report() {
+        let dir = std::env::temp_dir().join(format!("savor_import_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("nested")).unwrap();
+        std::fs::write(dir.join("a.md"), "---\ntitle: 第一篇\n---\n正文**一**").unwrap();
+        std::fs::write(dir.join("b.txt"), "正文一").unwrap();
+        std::fs::write(dir.join("c.html"), "<p>正文二</p>").unwrap();
+        std::fs::write(dir.join("image.png"), [0u8, 1, 2]).unwrap();
+        std::fs::write(dir.join(".draft.md"), "隐藏草稿").unwrap();
+        std::fs::write(dir.join("nested").join("d.md"), "---\ntitle: 空\n---\n").unwrap();
+
+        let conn = setup_db();
+        let skill_id = insert_skill(&conn, "导入", "通用", "");
+        let paths = vec![dir.clone(), PathBuf::from("/不存在的路径/x.md")];
+        let report = import_sample_files(&conn, skill_id, &paths).unwrap();
+
+        let titles: Vec<&str> = report.imported.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(titles, vec!["第一篇", "正文二"]);
+        let reasons: Vec<SkipReason> = report.skipped.iter().map(|s| s.reason).collect();
+        assert_eq!(reasons.len(), 5, "跳过明细: {:?}", report.skipped);
+        for reason in [
+            SkipReason::NotFound,
+            SkipReason::UnsupportedType,
+            SkipReason::Duplicate,
+            SkipReason::Empty,
+            SkipReason::Hidden,
+        ] {
+            assert!(reasons.contains(&reason), "缺少跳过原因 {:?}", reason);
+        }
+
+        // 再次导入：全部与已有样本重复
+        let again = import_sample_files(&conn, skill_id, std::slice::from_ref(&dir)).unwrap();
+        assert!(again.imported.is_empty());
+        assert!(again
+            .skipped
+            .iter()
+            .any(|s| s.reason == SkipReason::Duplicate && s.detail.contains("已有样本")));
+        assert!(import_sample_files(&conn, 9999, std::slice::from_ref(&dir)).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_import_reports_symlinked_dirs_and_hidden_entries() {
+        let dir = std::env::temp_dir().join(format!("savor_import_link_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("real")).unwrap();
+        std::fs::create_dir_all(dir.join(".git")).unwrap();
+        std::fs::write(dir.join("real").join("a.md"), "正文").unwrap();
+        std::fs::write(dir.join(".git").join("b.md"), "版本库里的文件").unwrap();
+        std::os::unix::fs::symlink(dir.join("real"), dir.join("link")).unwrap();
+        std::os::unix::fs::symlink(dir.join("real").join("a.md"), dir.join("alias.md")).unwrap();
+
+        let (samples, skipped) = read_samples(std::slice::from_ref(&dir));
+        let read: Vec<&std::path::Path> = samples.iter().map(|(p, _)| p.as_path()).collect();
+        assert_eq!(
+            read,
+            vec![dir.join("alias.md"), dir.join("real").join("a.md")],
+            "指向文件的符号链接照常读取"
+        );
+        let reasons: Vec<(String, SkipReason)> =
+            skipped.iter().map(|s| (s.path.clone(), s.reason)).collect();
+        assert_eq!(
+            reasons,
+            vec![
+                (dir.join(".git").display().to_string(), SkipReason::Hidden),
+                (dir.join("link").display().to_string(), SkipReason::Symlink),
+            ],
+            "隐藏目录与目录符号链接都应记入跳过明细"
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    // ---------- 分批风格分析 ----------
+
+    use crate::models::skill::AnalysisProgress;
+    use crate::services::style_analysis::map_reduce_analysis;
+    use crate::services::tokens::batch_by_tokens;
+
+    #[test]
+    fn test_token_batching() {
+        let items = ["一二三四五", "六七八", "九十", "很长很长很长很长很长很长"];
+        let default = TokenEstimator::DEFAULT;
+        assert_eq!(batch_by_tokens(&items, 8, &default), vec![0..2, 2..3, 3..4]);
+        assert!(batch_by_tokens::<&str>(&[], 8, &default).is_empty());
+        assert_eq!(
+            batch_by_tokens(
+                &items,
+                8,
+                &TokenEstimator::for_model("deepseek", "deepseek-chat")
+            ),
+            vec![0..3, 3..4],
+            "按目标模型的分词粒度分批"
+        );
+
+        let truncated = default.truncate("中文abcdefgh", 3);
+        assert_eq!(truncated, "中文abcd");
+        assert!(estimate_tokens(&truncated) <= 3);
+        assert_eq!(default.truncate("短", 3), "短");
+    }
+
+    #[tokio::test]
+    async fn test_map_reduce_analysis_batches_and_merges() {
+        let (addr, rx) = spawn_mock_server(vec![
+            (200, "application/json", openai_reply(VALID_STYLE_JSON)),
+            (200, "application/json", openai_reply(VALID_STYLE_JSON)),
+            (200, "application/json", openai_reply(VALID_STYLE_JSON)),
+        ]);
+        let config = mock_config("openai", &addr);
+        let samples = vec![
+            "第一篇样本的正文内容。".to_string(),
+            "第二篇样本的正文内容。".to_string(),
+            "第三篇样本的正文内容。".to_string(),
+        ];
+
+        let mut events = Vec::new();
+        let (spec, json) = map_reduce_analysis(
+            &config,
+            &samples,
+            25,
+            &fast_retry_policy(0),
+            |_| {},
+            |p: AnalysisProgress| events.push((p.stage, p.round, p.completed, p.total)),
+        )
+        .await
+        .unwrap();
+        assert_eq!(spec.role.tone, "冷静克制");
+        assert!(SkillSpec::from_content_json(&json).unwrap().is_some());
+
+        assert_eq!(
+            events,
+            vec![
+                ("map".to_string(), 0, 1, 2),
+                ("map".to_string(), 0, 2, 2),
+                ("reduce".to_string(), 1, 1, 1),
+            ]
+        );
+        let first = rx.recv().unwrap().json();
+        let first_prompt = first["messages"][0]["content"].as_str().unwrap();
+        assert!(first_prompt.contains("第二篇") && !first_prompt.contains("第三篇"));
+        rx.recv().unwrap();
+        let reduce = rx.recv().unwrap().json();
+        let reduce_prompt = reduce["messages"][0]["content"].as_str().unwrap();
+        assert!(
+            reduce_prompt.contains("分析结果 2"),
+            "合并请求应包含各批结果"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_map_reduce_single_batch_skips_merge() {
+        let (addr, rx) = spawn_mock_server(vec![(
+            200,
+            "application/json",
+            openai_reply(VALID_STYLE_JSON),
+        )]);
+        let config = mock_config("openai", &addr);
+        let mut events = 0;
+        map_reduce_analysis(
+            &config,
+            &["只有一篇样本。".to_string()],
+            6000,
+            &fast_retry_policy(0),
+            |_| {},
+            |_| events += 1,
+        )
+        .await
+        .unwrap();
+        assert_eq!(events, 1, "只有一批时不进入合并阶段");
+        rx.recv().unwrap();
+        assert!(rx.try_recv().is_err());
+    }
+
+    // ---------- 上下文预算 ----------
+
+    use crate::commands::article::fit_generate_prompt;
+    use crate::services::prompt_budget::{self, fit_sections, PromptSection, Shrink};
+    use crate::services::style_analysis::{batch_tokens_for, ANALYSIS_BATCH_TOKENS};
+    use crate::services::tokens::TokenEstimator;
+
+    #[test]
+    fn test_token_estimator_per_model() {
+        let gpt = TokenEstimator::for_model("openai", "gpt-4o-mini");
+        assert_eq!(gpt.context_window, 128_000);
+        let claude = TokenEstimator::for_model("claude", "claude-3-5-sonnet");
+        assert!(
+            claude.estimate("中文写作") > gpt.estimate("中文写作"),
+            "Claude 分词器切中文更碎"
+        );
+
+        // o 系列只认名称开头，不能靠子串匹配
+        for name in ["o1", "o3-mini", "o4-mini-2025-04-16", "openai/o1-preview"] {
+            assert_eq!(
+                TokenEstimator::for_model("custom", name).context_window,
+                128_000,
+                "{name} 应识别为 o 系列"
+            );
+        }
+        for name in ["pro1-chat", "echo3-7b", "foo4", "o10-test"] {
+            assert_eq!(
+                TokenEstimator::for_model("custom", name),
+                TokenEstimator::DEFAULT,
+                "{name} 不应被误判为 o 系列"
+            );
+        }
+
+        // 未知模型按服务商取默认值
+        assert_eq!(
+            TokenEstimator::for_model("deepseek", "my-finetune").context_window,
+            64_000
+        );
+        assert_eq!(
+            TokenEstimator::for_model("custom", "whatever"),
+            TokenEstimator::DEFAULT
+        );
+
+        // Ollama 即使跑大窗口模型也受服务端默认上下文限制
+        assert_eq!(
+            TokenEstimator::for_model("ollama", "qwen2.5:7b").context_window,
+            4096
+        );
+        assert_eq!(
+            batch_tokens_for(&mock_config("ollama", "http://localhost")),
+            2048
+        );
+        assert_eq!(
+            batch_tokens_for(&mock_config("openai", "http://localhost")),
+            ANALYSIS_BATCH_TOKENS
+        );
+    }
+
+    #[test]
+    fn test_truncate_middle_keeps_head_and_tail() {
+        let estimator = TokenEstimator::DEFAULT;
+        let text = format!("开头{}结尾", "中".repeat(500));
+        let truncated = estimator.truncate_middle(&text, 60);
+        assert!(estimator.estimate(&truncated) <= 60);
+        assert!(truncated.starts_with("开头") && truncated.ends_with("结尾"));
+        assert!(truncated.contains("中间省略约"));
+
+        assert_eq!(estimator.truncate_middle("很短", 60), "很短");
+    }
+
+    #[test]
+    fn test_summarize_diff_drops_equal_lines() {
+        let estimator = TokenEstimator::DEFAULT;
+        let diff = "  不变的一行\n- 删除的一行\n+ 新增的一行\n  又一行不变\n";
+        let summary = prompt_budget::summarize_diff(diff, 100, &estimator);
+        assert_eq!(summary, "- 删除的一行\n+ 新增的一行\n");
+
+        let long: String = (0..50).map(|i| format!("+ 新增第{}行\n", i)).collect();
+        let summary = prompt_budget::summarize_diff(&long, 60, &estimator);
+        assert!(estimator.estimate(&summary) <= 60);
+        assert!(summary.starts_with("+ 新增第0行"));
+        assert!(summary.contains("行变更未列出"));
+    }
+
+    #[test]
+    fn test_fit_sections_shrinks_lowest_priority_first() {
+        let config = mock_config("ollama", "http://localhost");
+        let (texts, report) = fit_sections(
+            &config,
+            "模板",
+            prompt_budget::DEFAULT_OUTPUT_RESERVE,
+            vec![
+                PromptSection::new("keep", "重要".repeat(500), 2, 100, Shrink::Truncate),
+                PromptSection::new("drop", "次要".repeat(2000), 0, 100, Shrink::Truncate),
+            ],
+        );
+        // 4096 窗口，输出预留上限为 1/4
+        assert_eq!(report.reserved_output, 1024);
+        assert!(report.fits && report.needs_warning());
+        assert_eq!(texts[0], "重要".repeat(500), "高优先级内容放得下时不压缩");
+        assert_eq!(report.adjustments.len(), 1);
+        assert_eq!(report.adjustments[0].section, "drop");
+        assert_eq!(report.adjustments[0].action, "truncated");
+        assert!(report.prompt_tokens <= 4096 - 1024);
+
+        // 所有段都压到下限仍放不下时如实报告
+        let (_, report) = fit_sections(
+            &config,
+            "模板",
+            1024,
+            vec![PromptSection::new(
+                "huge",
+                "字".repeat(8000),
+                0,
+                5000,
+                Shrink::Truncate,
+            )],
+        );
+        assert!(!report.fits);
+    }
+
+    #[test]
+    fn test_fit_generate_prompt_within_budget() {
+        let config = mock_config("openai", "http://localhost");
+        let (prompt, report) = fit_generate_prompt(&config, "## 风格\n短句为主", "", "春天");
+        assert!(!report.needs_warning(), "小提示词无需调整");
+        assert!(prompt.contains("短句为主") && prompt.contains("春天"));
+
+        let ollama = mock_config("ollama", "http://localhost");
+        let skill = format!("## 开头规则\n{}\n## 结尾规则", "规则".repeat(3000));
+        let (prompt, report) = fit_generate_prompt(&ollama, &skill, "", "春天");
+        assert!(report.fits);
+        assert_eq!(report.adjustments[0].section, "skill");
+        assert!(prompt.contains("开头规则") && prompt.contains("结尾规则"));
+        assert!(prompt.contains("春天"));
+    }
+
+    // ---------- 生成示例片段 ----------
+
+    use crate::commands::article::few_shot_excerpts;
+    use crate::models::article::{FewShotOptions, SampleExcerpt};
+    use crate::prompts;
+    use crate::services::few_shot::{keep_leading_excerpts, split_passages};
+    use crate::services::lexical::{terms, Bm25};
+
+    #[test]
+    fn test_lexical_terms_and_bm25() {
+        assert_eq!(
+            terms("咖啡馆, Remote Work!"),
+            vec!["咖啡", "啡馆", "remote", "work"]
+        );
+        assert_eq!(terms("猫。狗"), vec!["猫", "狗"], "单字成段时保留单字");
+
+        let docs = [
+            "周末在咖啡馆写代码，咖啡很苦。",
+            "春天的公园里开满了花。",
+            "远程办公让我重新认识了通勤。",
+        ];
+        let scores = Bm25::new(&docs).scores("在咖啡馆远程办公");
+        assert!(scores[0] > 0.0 && scores[2] > 0.0);
+        assert_eq!(scores[1], 0.0, "没有共同词项的文档得 0");
+    }
+
+    #[test]
+    fn test_split_passages_merges_short_paragraphs() {
+        let long = "长".repeat(90);
+        let content = format!("# 标题\n\n短段一。\n\n{}\n\n短段二。\n\n结尾。", long);
+        let passages = split_passages(&content);
+        assert_eq!(passages.len(), 1, "短段落与后续段落合并，结尾并入上一片段");
+        assert!(passages[0].starts_with("短段一。") && passages[0].ends_with("结尾。"));
+        assert!(!passages[0].contains("标题"), "跳过标题行");
+    }
+
+    #[test]
+    fn test_few_shot_excerpts_ranked_by_topic_within_budget() {
+        let conn = setup_db();
+        let skill_id = insert_skill(&conn, "示例", "通用", "");
+        let filler = "这一段写的是日常琐事，用来凑足长度，免得被合并到别的段落里去。";
+        insert_sample(
+            &conn,
+            skill_id,
+            Some("城市"),
+            &format!("{}{}\n\n{}地铁通勤时我总在读书。", filler, filler, filler),
+        )
+        .unwrap();
+        let coffee = insert_sample(
+            &conn,
+            skill_id,
+            Some("咖啡"),
+            &format!("{}咖啡馆里，咖啡的香气让我安静下来。", filler),
+        )
+        .unwrap();
+        let config = mock_config("openai", "http://localhost");
+
+        assert!(few_shot_excerpts(&conn, skill_id, "咖啡馆", None, &config)
+            .unwrap()
+            .is_empty());
+
+        let options = FewShotOptions {
+            token_budget: 200,
+            max_excerpts: 2,
+        };
+        let excerpts =
+            few_shot_excerpts(&conn, skill_id, "咖啡馆", Some(&options), &config).unwrap();
+        assert_eq!(excerpts.len(), 2);
+        assert_eq!(excerpts[0].sample_id, coffee, "最相关的片段排在最前");
+        assert_eq!(excerpts[0].sample_title, "咖啡");
+        assert!(excerpts[0].score > excerpts[1].score);
+        let estimator = TokenEstimator::for_model("openai", "test-model");
+        let used: usize = excerpts.iter().map(|e| estimator.estimate(&e.text)).sum();
+        assert!(used <= 200, "片段合计不超过预算");
+
+        // 主题与样本毫无关联时仍按样本顺序给出示例
+        let unrelated =
+            few_shot_excerpts(&conn, skill_id, "量子力学", Some(&options), &config).unwrap();
+        assert_eq!(unrelated.len(), 2);
+        assert!(unrelated.iter().all(|e| e.score == 0.0));
+    }
+
+    #[test]
+    fn test_generate_prompt_with_excerpts() {
+        let excerpts = vec![
+            SampleExcerpt {
+                sample_id: 1,
+                sample_title: "甲".to_string(),
+                text: "第一段原文。".to_string(),
+                score: 1.0,
+            },
+            SampleExcerpt {
+                sample_id: 2,
+                sample_title: "乙".to_string(),
+                text: "第二段原文。".to_string(),
+                score: 0.5,
+            },
+        ];
+        let rendered = prompts::generate::render_excerpts(&excerpts);
+        let prompt = prompts::generate::build_generate_prompt("规则", &rendered, "主题");
+        assert!(prompt.contains("作者原文片段") && prompt.contains("摘自《乙》"));
+        assert!(
+            !prompts::generate::build_generate_prompt("规则", "", "主题").contains("作者原文片段"),
+            "未开启示例时提示词不变"
+        );
+
+        let estimator = TokenEstimator::DEFAULT;
+        let first_only = estimator.estimate(&rendered) - 5;
+        let kept = keep_leading_excerpts(&rendered, first_only, &estimator);
+        assert!(kept.contains("第一段原文") && !kept.contains("第二段原文"));
+
+        // 小窗口模型先整段去掉示例，再动 Skill
+        let ollama = mock_config("ollama", "http://localhost");
+        let skill = "规则".repeat(1200);
+        let long_excerpts = prompts::generate::render_excerpts(&[SampleExcerpt {
+            text: "原文".repeat(400),
+            ..excerpts[0].clone()
+        }]);
+        let (prompt, report) = fit_generate_prompt(&ollama, &skill, &long_excerpts, "主题");
+        assert!(report.fits);
+        assert_eq!(report.adjustments[0].section, "excerpts");
+        assert!(prompt.contains(&skill), "去掉示例后放得下时 Skill 保持完整");
+    }
+
+    // ---------- 向量索引与语义检索 ----------
+
+    use crate::commands::embedding::{nearest, update_index};
+    use crate::commands::llm::load_embedding_config;
+    use crate::db::Database;
+    use crate::models::embedding::EmbeddingSource;
+    use crate::services::embedding::{self, Embedder, LOCAL_MODEL};
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_hash_embedding_similarity() {
+        let coffee = embedding::hash_embed("周末在咖啡馆写作，咖啡很苦");
+        let coffee_again = embedding::hash_embed("咖啡馆里写作的周末");
+        let garden = embedding::hash_embed("春天的公园开满了花");
+
+        assert_eq!(coffee, embedding::hash_embed("周末在咖啡馆写作，咖啡很苦"));
+        let norm: f32 = coffee.iter().map(|v| v * v).sum();
+        assert!((norm - 1.0).abs() < 1e-4, "向量应归一化");
+        assert!(
+            embedding::cosine(&coffee, &coffee_again) > embedding::cosine(&coffee, &garden),
+            "词项重合多的文本更相似"
+        );
+        assert_eq!(
+            embedding::cosine(&coffee, &[1.0, 0.0]),
+            0.0,
+            "维度不一致时为 0"
+        );
+        assert!(embedding::hash_embed("。，！").iter().all(|v| *v == 0.0));
+
+        let encoded = embedding::encode_vector(&coffee);
+        assert_eq!(encoded.len(), coffee.len() * 4);
+        assert_eq!(embedding::decode_vector(&encoded), coffee);
+    }
+
+    #[test]
+    fn test_embedding_config_requires_route() {
+        let conn = setup_db();
+        assert!(
+            load_embedding_config(&conn).unwrap().is_none(),
+            "未指定档案时使用本地向量，不回落到默认对话配置"
+        );
+        assert_eq!(Embedder::new(None).model_key(), LOCAL_MODEL);
+
+        let profile = insert_profile(&conn, "本地向量", "ollama", "nomic-embed-text");
+        route_task(&conn, LlmTask::Embed, Some(profile));
+        let config = load_embedding_config(&conn).unwrap().unwrap();
+        assert_eq!(
+            Embedder::new(Some(config)).model_key(),
+            "ollama:nomic-embed-text"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_remote_embeddings_request() {
+        let reply =
+            r#"{"data":[{"index":1,"embedding":[0.0,1.0]},{"index":0,"embedding":[1.0,0.0]}]}"#;
+        let (addr, rx) = spawn_mock_server(vec![(200, "application/json", reply.to_string())]);
+        let embedder = Embedder::Remote(mock_config("openai", &addr));
+
+        let vectors = embedder
+            .embed(
+                &["第一段".to_string(), "第二段".to_string()],
+                &fast_retry_policy(0),
+                |_| {},
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            vectors,
+            vec![vec![1.0, 0.0], vec![0.0, 1.0]],
+            "按 index 还原顺序"
+        );
+
+        let request = rx.recv().unwrap();
+        assert!(request.request_line.starts_with("POST /embeddings"));
+        let body = request.json();
+        assert_eq!(body["model"], "test-model");
+        assert_eq!(body["input"][1], "第二段");
+
+        let claude = Embedder::Remote(mock_config("claude", &addr));
+        let err = claude
+            .embed(&["x".to_string()], &fast_retry_policy(0), |_| {})
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("embeddings"));
+        assert!(!err.is_unreachable(), "配置错误不算连不上服务");
+    }
+
+    #[tokio::test]
+    async fn test_local_index_is_incremental_and_searchable() {
+        let conn = setup_db();
+        let skill_id = insert_skill(&conn, "检索", "通用", "");
+        let other_skill = insert_skill(&conn, "其他", "通用", "");
+        let coffee = insert_sample(&conn, skill_id, Some("咖啡"), "在咖啡馆写作的周末").unwrap();
+        insert_sample(&conn, skill_id, Some("公园"), "春天的公园开满了花").unwrap();
+        insert_sample(&conn, other_skill, Some("咖啡二"), "咖啡馆的咖啡很苦").unwrap();
+        conn.execute(
+            "INSERT INTO article (title, skill_id, ai_generated_content, user_refined_content)
+             VALUES ('咖啡馆札记', ?1, '初稿', '咖啡馆里的周末写作')",
+            [skill_id],
+        )
+        .unwrap();
+        let db = Database {
+            conn: Mutex::new(conn),
+        };
+        let embedder = Embedder::Local;
+
+        let report = update_index(
+            &db,
+            &embedder,
+            Some(skill_id),
+            &fast_retry_policy(0),
+            |_| {},
+        )
+        .await
+        .unwrap();
+        assert_eq!(report.model, LOCAL_MODEL);
+        assert_eq!(
+            (report.indexed, report.unchanged, report.removed),
+            (3, 0, 0)
+        );
+
+        let report = update_index(
+            &db,
+            &embedder,
+            Some(skill_id),
+            &fast_retry_policy(0),
+            |_| {},
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            (report.indexed, report.unchanged),
+            (0, 3),
+            "内容未变时不重新计算"
+        );
+
+        {
+            let conn = db.conn.lock().unwrap();
+            conn.execute(
+                "UPDATE original_sample SET content = '在咖啡馆写作的周末，咖啡很香' WHERE id = ?1",
+                [coffee],
+            )
+            .unwrap();
+            conn.execute("DELETE FROM original_sample WHERE title = '公园'", [])
+                .unwrap();
+        }
+        let report = update_index(
+            &db,
+            &embedder,
+            Some(skill_id),
+            &fast_retry_policy(0),
+            |_| {},
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            (report.indexed, report.unchanged, report.removed),
+            (1, 1, 1)
+        );
+
+        let query = embedding::hash_embed("咖啡馆 周末");
+        let conn = db.conn.lock().unwrap();
+        let hits = nearest(
+            &conn,
+            LOCAL_MODEL,
+            &query,
+            Some(skill_id),
+            &EmbeddingSource::ALL,
+            10,
+        )
+        .unwrap();
+        assert_eq!(hits.len(), 2, "只返回该 Skill 下已建索引的内容");
+        assert!(hits[0].similarity >= hits[1].similarity);
+        assert!(hits.iter().all(|h| h.skill_id == Some(skill_id)));
+
+        let articles = nearest(
+            &conn,
+            LOCAL_MODEL,
+            &query,
+            None,
+            &[EmbeddingSource::Article],
+            10,
+        )
+        .unwrap();
+        assert_eq!(articles.len(), 1);
+        assert_eq!(articles[0].title, "咖啡馆札记");
+        assert_eq!(
+            articles[0].snippet, "咖啡馆里的周末写作",
+            "文章取用户修改稿"
+        );
+
+        let other_model = nearest(
+            &conn,
+            "openai:text-embedding-3-small",
+            &query,
+            None,
+            &EmbeddingSource::ALL,
+            10,
+        )
+        .unwrap();
+        assert!(other_model.is_empty(), "不同向量模型的结果互不混用");
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_embedding_service_falls_back_to_local() {
+        let conn = setup_db();
+        let skill_id = insert_skill(&conn, "离线", "通用", "");
+        insert_sample(&conn, skill_id, Some("咖啡"), "在咖啡馆写作的周末").unwrap();
+        let db = Database {
+            conn: Mutex::new(conn),
+        };
+
+        // 端口 9 上没有服务，连接直接被拒绝
+        let offline = Embedder::Remote(mock_config("openai", "http://127.0.0.1:9"));
+        let report = update_index(&db, &offline, Some(skill_id), &fast_retry_policy(0), |_| {})
+            .await
+            .unwrap();
+        assert_eq!(report.model, LOCAL_MODEL, "连不上时改用本地哈希向量");
+        assert_eq!(report.indexed, 1);
+        assert!(
+            report
+                .fallback
+                .as_deref()
+                .is_some_and(|f| f.contains("本地哈希向量")),
+            "报告中注明发生了降级"
+        );
+        {
+            let conn = db.conn.lock().unwrap();
+            let models: Vec<String> = conn
+                .prepare("SELECT DISTINCT model FROM embedding")
+                .unwrap()
+                .query_map([], |row| row.get(0))
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap();
+            assert_eq!(
+                models,
+                vec![LOCAL_MODEL.to_string()],
+                "降级的向量按本地模型标识存放"
+            );
+        }
+
+        let (addr, _rx) = spawn_mock_server(vec![(
+            401,
+            "application/json",
+            r#"{"error":{"message":"invalid key"}}"#.to_string(),
+        )]);
+        let rejected = Embedder::Remote(mock_config("openai", &addr));
+        {
+            let conn = db.conn.lock().unwrap();
+            conn.execute("DELETE FROM embedding", []).unwrap();
+        }
+        let err = update_index(
+            &db,
+            &rejected,
+            Some(skill_id),
+            &fast_retry_policy(0),
+            |_| {},
+        )
+        .await
+        .unwrap_err();
+        assert!(
+            err.contains("401") || err.contains("鉴权"),
+            "鉴权失败照常报错：{}",
+            err
+        );
+        let count: i64 = db
+            .conn
+            .lock()
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM embedding", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 0, "服务端拒绝请求时不降级");
+    }
+
+    #[tokio::test]
+    async fn test_embedding_batches_are_stored_as_they_finish() {
+        let conn = setup_db();
+        let skill_id = insert_skill(&conn, "分批", "通用", "");
+        for i in 0..=embedding::EMBED_BATCH {
+            insert_sample(&conn, skill_id, None, &format!("第 {} 篇样本", i)).unwrap();
+        }
+        let db = Database {
+            conn: Mutex::new(conn),
+        };
+
+        let data: Vec<String> = (0..embedding::EMBED_BATCH)
+            .map(|i| format!(r#"{{"index":{},"embedding":[1.0,0.0]}}"#, i))
+            .collect();
+        let (addr, _rx) = spawn_mock_server(vec![
+            (
+                200,
+                "application/json",
+                format!(r#"{{"data":[{}]}}"#, data.join(",")),
+            ),
+            (
+                401,
+                "application/json",
+                r#"{"error":{"message":"invalid key"}}"#.to_string(),
+            ),
+        ]);
+        let embedder = Embedder::Remote(mock_config("openai", &addr));
+        update_index(&db, &embedder, Some(skill_id), &fast_retry_policy(0), |_| {})
+            .await
+            .unwrap_err();
+
+        let count: i64 = db
+            .conn
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT COUNT(*) FROM embedding WHERE model = 'openai:test-model'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(
+            count,
+            embedding::EMBED_BATCH as i64,
+            "第二批失败时第一批的向量已经写入"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_index_prunes_embeddings_of_unused_models() {
+        let conn = setup_db();
+        let skill_id = insert_skill(&conn, "清理", "通用", "");
+        let sample = insert_sample(&conn, skill_id, None, "在咖啡馆写作的周末").unwrap();
+        let profile = insert_profile(&conn, "远端向量", "openai", "text-embedding-3-small");
+        route_task(&conn, LlmTask::Embed, Some(profile));
+        for model in [
+            "openai:text-embedding-3-small",
+            "openai:text-embedding-ada-002",
+            "local-hash-v0",
+        ] {
+            conn.execute(
+                "INSERT INTO embedding (source, source_id, model, content_hash, dim, vector)
+                 VALUES ('sample', ?1, ?2, 'old', 2, x'00000000')",
+                rusqlite::params![sample, model],
+            )
+            .unwrap();
+        }
+        let db = Database {
+            conn: Mutex::new(conn),
+        };
+
+        let report = update_index(
+            &db,
+            &Embedder::Local,
+            Some(skill_id),
+            &fast_retry_policy(0),
+            |_| {},
+        )
+        .await
+        .unwrap();
+        assert_eq!((report.indexed, report.removed), (1, 2));
+
+        let conn = db.conn.lock().unwrap();
+        let models: Vec<String> = conn
+            .prepare("SELECT model FROM embedding ORDER BY model")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            models,
+            vec![
+                LOCAL_MODEL.to_string(),
+                "openai:text-embedding-3-small".to_string()
+            ],
+            "只保留当前远端模型与本地哈希向量，降级期间不清掉远端向量"
+        );
+    }
 }
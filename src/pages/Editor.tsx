@@ -20,6 +20,9 @@ export default function EditorPage() {
     const [userContent, setUserContent] = useState('');
     const [loading, setLoading] = useState(false);
     const [statusMsg, setStatusMsg] = useState('');
+    // 流式生成中的任务 ID，用于停止生成
+    const [jobId, setJobId] = useState<number | null>(null);
+    const [streaming, setStreaming] = useState(false);
     const editorRef = useRef<unknown>(null);
 
     const [searchParams, setSearchParams] = useSearchParams();
@@ -70,20 +73,70 @@ export default function EditorPage() {
             return;
         }
         setLoading(true);
+        setStreaming(true);
+        setArticle(null);
+        setAiContent('');
+        setUserContent('');
         setStatusMsg(t('editor.generating'));
+
+        // 开始时后端先推送一条空增量，据此锁定本次生成的文章
+        const stream = { articleId: null as number | null, text: '', cancelled: false };
+        const unlisten = await articleApi.onChunk((chunk) => {
+            if (stream.articleId === null) {
+                stream.articleId = chunk.article_id;
+            } else if (chunk.article_id !== stream.articleId) {
+                return;
+            }
+            setJobId(chunk.done ? null : chunk.job_id);
+            if (chunk.delta) {
+                stream.text += chunk.delta;
+                setAiContent(stream.text);
+                setUserContent(stream.text);
+            }
+            if (chunk.done) {
+                stream.cancelled = chunk.cancelled;
+            }
+        });
+
         try {
-            const result = await articleApi.generate(selectedSkillId, topic);
+            const result = await articleApi.generateStream(selectedSkillId, topic);
             setArticle(result);
             setAiContent(result.ai_generated_content);
             setUserContent(result.ai_generated_content);
-            setStatusMsg(t('editor.generateDone'));
+            setStatusMsg(stream.cancelled ? t('editor.generateCancelled') : t('editor.generateDone'));
         } catch (e) {
-            setStatusMsg(`${t('editor.generateFailed')}: ${e}`);
+            // 中途失败时已收到的内容以 partial 状态保存，载入后可继续编辑
+            if (stream.articleId !== null && stream.text) {
+                try {
+                    const partial = await articleApi.get(stream.articleId);
+                    setArticle(partial);
+                    setAiContent(partial.ai_generated_content);
+                    setUserContent(partial.ai_generated_content);
+                } catch (loadError) {
+                    console.error('加载文章失败:', loadError);
+                }
+                setStatusMsg(`${t('editor.generatePartial')}: ${e}`);
+            } else {
+                setStatusMsg(`${t('editor.generateFailed')}: ${e}`);
+            }
         } finally {
+            unlisten();
+            setJobId(null);
+            setStreaming(false);
             setLoading(false);
         }
     };
 
+    // 停止生成：已生成的部分会保留
+    const handleCancel = async () => {
+        if (jobId === null) return;
+        try {
+            await articleApi.cancelGeneration(jobId);
+        } catch (e) {
+            console.error('停止生成失败:', e);
+        }
+    };
+
     // 保存修改
     const handleSave = async () => {
         if (!article) return;
@@ -159,14 +212,24 @@ export default function EditorPage() {
                 </div>
 
                 <div className="toolbar-right">
-                    <button
-                        className="btn btn-primary"
-                        onClick={handleGenerate}
-                        disabled={loading}
-                    >
-                        {loading ? t('editor.generating') : t('editor.generateBtn')}
-                    </button>
-                    {article && (
+                    {streaming ? (
+                        <button
+                            className="btn btn-outline"
+                            onClick={handleCancel}
+                            disabled={jobId === null}
+                        >
+                            {t('editor.cancelBtn')}
+                        </button>
+                    ) : (
+                        <button
+                            className="btn btn-primary"
+                            onClick={handleGenerate}
+                            disabled={loading}
+                        >
+                            {loading ? t('editor.generating') : t('editor.generateBtn')}
+                        </button>
+                    )}
+                    {article && !streaming && (
                         <>
                             <button className="btn btn-outline" onClick={handleSave}>
                                 {t('common.save')}
@@ -183,12 +246,14 @@ export default function EditorPage() {
             {statusMsg && <div className="status-bar">{statusMsg}</div>}
 
             {/* 双栏编辑器 */}
-            {article ? (
+            {article || streaming ? (
                 <div className="editor-panels">
                     <div className="editor-panel">
                         <div className="panel-header">
                             <span className="panel-label">{t('editor.aiDraft')}</span>
-                            <span className="badge">v{article.skill_version_used}</span>
+                            {article && (
+                                <span className="badge">v{article.skill_version_used}</span>
+                            )}
                         </div>
                         <Editor
                             height="100%"
@@ -226,6 +291,7 @@ export default function EditorPage() {
                                 editorRef.current = editor;
                             }}
                             options={{
+                                readOnly: streaming,
                                 minimap: { enabled: false },
                                 fontSize: 14,
                                 lineNumbers: 'off',
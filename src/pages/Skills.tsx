@@ -2,8 +2,9 @@ import { useState, useEffect, useCallback } from 'react';
 import { useSearchParams } from 'react-router-dom';
 import { useTranslation } from 'react-i18next';
 import { skillApi } from '../services/skillApi';
+import { articleApi } from '../services/articleApi';
 import { tauriInvoke } from '../services/api';
-import type { Skill, SkillVersion } from '../types';
+import type { RuleConflict, Skill, SkillVersion } from '../types';
 import './Skills.css';
 
 export default function SkillsPage() {
@@ -23,6 +24,9 @@ export default function SkillsPage() {
     const [expandedVersionId, setExpandedVersionId] = useState<number | null>(null);
     const [editingVersionId, setEditingVersionId] = useState<number | null>(null);
     const [editContent, setEditContent] = useState('');
+    // 被编辑版本的结构化内容，保存时随 Markdown 一起提交
+    const [editContentJson, setEditContentJson] = useState('');
+    const [conflicts, setConflicts] = useState<RuleConflict[]>([]);
 
     // Skill 信息编辑
     const [editingInfo, setEditingInfo] = useState(false);
@@ -124,6 +128,7 @@ export default function SkillsPage() {
         setSelectedSkill(skill);
         setExpandedVersionId(null);
         setEditingVersionId(null);
+        setConflicts([]);
         setEditingInfo(false);
         try {
             const v = await skillApi.getVersions(skill.id);
@@ -187,21 +192,23 @@ export default function SkillsPage() {
     };
 
     // 进入编辑模式
-    const startEditing = (versionId: number, content: string) => {
-        setEditingVersionId(versionId);
-        setEditContent(content);
+    const startEditing = (version: SkillVersion) => {
+        setEditingVersionId(version.id);
+        setEditContent(version.content_markdown);
+        setEditContentJson(version.content_json);
     };
 
     // 保存编辑内容为新版本
     const handleSaveVersion = async () => {
         if (!selectedSkill || !editContent.trim()) return;
         try {
-            await tauriInvoke('evolve_skill', {
-                skillId: selectedSkill.id,
-                newContentMarkdown: editContent,
-                newContentJson: '{}',
-                changeSummary: t('skills.manualEditSummary'),
-            });
+            const found = await articleApi.evolveSkill(
+                selectedSkill.id,
+                editContent,
+                editContentJson,
+                t('skills.manualEditSummary'),
+            );
+            setConflicts(found);
             setEditingVersionId(null);
             setExpandedVersionId(null);
             // 重新加载 Skill 和版本
@@ -211,7 +218,9 @@ export default function SkillsPage() {
             // 更新选中的 Skill 信息
             const updatedSkill = await skillApi.get(selectedSkill.id);
             setSelectedSkill(updatedSkill);
-            setStatusMsg(t('skills.versionSaved'));
+            setStatusMsg(found.length > 0
+                ? t('skills.versionSavedWithConflicts', { count: found.length })
+                : t('skills.versionSaved'));
         } catch (e) {
             setStatusMsg(`${t('skills.versionSaveFailed')}: ${e}`);
         }
@@ -361,6 +370,20 @@ export default function SkillsPage() {
                         </div>
                     )}
 
+                    {conflicts.length > 0 && (
+                        <div className="conflict-list">
+                            <h3>{t('skills.conflictsTitle')}</h3>
+                            <ul>
+                                {conflicts.map((c, i) => (
+                                    <li key={i}>
+                                        <p className="conflict-message">{c.message}</p>
+                                        <p className="conflict-rules">「{c.rule_a}」 ↔ 「{c.rule_b}」</p>
+                                    </li>
+                                ))}
+                            </ul>
+                        </div>
+                    )}
+
                     <div className="version-list">
                         <h3>{t('skills.versionHistory')} ({versions.length})</h3>
                         {versions.map((v) => (
@@ -408,7 +431,7 @@ export default function SkillsPage() {
                                                 <div className="version-edit-actions">
                                                     <button
                                                         className="btn btn-outline btn-sm"
-                                                        onClick={() => startEditing(v.id, v.content_markdown)}
+                                                        onClick={() => startEditing(v)}
                                                     >
                                                         {t('common.edit')}
                                                     </button>
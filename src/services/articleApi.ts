@@ -1,5 +1,6 @@
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
 import { tauriInvoke } from './api';
-import type { Article } from '../types';
+import type { Article, ArticleChunk, FewShotOptions, RuleConflict } from '../types';
 
 export interface DiffChunk {
     tag: 'equal' | 'delete' | 'insert';
@@ -7,8 +8,18 @@ export interface DiffChunk {
 }
 
 export const articleApi = {
-    generate: (skillId: number, topic: string) =>
-        tauriInvoke<Article>('generate_article', { skillId, topic }),
+    generate: (skillId: number, topic: string, fewShot?: FewShotOptions) =>
+        tauriInvoke<Article>('generate_article', { skillId, topic, fewShot }),
+
+    /** 流式生成：增量通过 onChunk 推送，返回完成（或取消后保留）的文章 */
+    generateStream: (skillId: number, topic: string, fewShot?: FewShotOptions) =>
+        tauriInvoke<Article>('generate_article_stream', { skillId, topic, fewShot }),
+
+    onChunk: (handler: (chunk: ArticleChunk) => void): Promise<UnlistenFn> =>
+        listen<ArticleChunk>('article://chunk', (event) => handler(event.payload)),
+
+    cancelGeneration: (jobId: number) =>
+        tauriInvoke<boolean>('cancel_job', { jobId }),
 
     save: (articleId: number, content: string) =>
         tauriInvoke<void>('save_article', { articleId, content }),
@@ -25,13 +36,14 @@ export const articleApi = {
     analyzeDiff: (articleId: number, original: string, modified: string) =>
         tauriInvoke<unknown>('analyze_diff', { articleId, original, modified }),
 
+    /** 保存为新版本，返回新版本中检测到的规则冲突 */
     evolveSkill: (
         skillId: number,
         newContentMarkdown: string,
         newContentJson: string,
         changeSummary: string,
     ) =>
-        tauriInvoke<void>('evolve_skill', {
+        tauriInvoke<RuleConflict[]>('evolve_skill', {
             skillId,
             newContentMarkdown,
             newContentJson,
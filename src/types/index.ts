@@ -20,6 +20,16 @@ export interface SkillVersion {
     created_at: string;
 }
 
+/** 同一版本中相互矛盾的两条规则 */
+export interface RuleConflict {
+    kind: 'forbidden_terminology' | 'opposing_principles';
+    section_a: string;
+    rule_a: string;
+    section_b: string;
+    rule_b: string;
+    message: string;
+}
+
 export interface CreateSkillRequest {
     name: string;
     category?: string;
@@ -44,11 +54,33 @@ export interface Article {
     user_refined_content: string;
     skill_id: number | null;
     skill_version_used: number | null;
-    status: 'draft' | 'editing' | 'published';
+    status: 'draft' | 'generating' | 'partial' | 'editing' | 'published';
+    /** AI 初稿与用户修改稿的 AI 味评分（0–100），内容为空时为 null */
+    ai_flavor_generated: number | null;
+    ai_flavor_refined: number | null;
+    /** 计算上述评分时的内置词典版本 */
+    ai_flavor_dict_version: number | null;
+    /** AI 初稿与所用 Skill 版本样本指纹的风格相似度（0–100），该版本没有指纹时为 null */
+    style_similarity: number | null;
     created_at: string;
     updated_at: string;
 }
 
+/** article://chunk 事件负载 */
+export interface ArticleChunk {
+    article_id: number;
+    job_id: number;
+    delta: string;
+    done: boolean;
+    cancelled: boolean;
+    error: string | null;
+}
+
+export interface FewShotOptions {
+    token_budget?: number;
+    max_excerpts?: number;
+}
+
 export interface DiffRecord {
     id: number;
     article_id: number;